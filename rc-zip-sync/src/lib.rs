@@ -17,5 +17,6 @@ pub use streaming_entry_reader::StreamingEntryReader;
 // re-exports
 pub use rc_zip;
 pub use read_zip::{
-    ArchiveHandle, EntryHandle, HasCursor, ReadZip, ReadZipStreaming, ReadZipWithSize,
+    ArchiveHandle, EntryHandle, HasCursor, LocalHeaderInfo, ReadZip, ReadZipStreaming,
+    ReadZipWithSize, SeekableReader, SeekableReaderCursor,
 };
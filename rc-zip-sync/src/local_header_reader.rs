@@ -1,8 +1,8 @@
 use rc_zip::{
-    fsm::{AexData, EntryFsm, FsmResult, ParsedRanges},
+    fsm::{AexData, EntryFsm, EntryReadMetrics, FsmResult, ParsedRanges},
     parse::{Entry, LocalFileHeader},
 };
-use std::{io, rc::Rc, sync::Mutex};
+use std::{cell::RefCell, io, rc::Rc};
 use tracing::trace;
 
 pub(crate) struct LocalHeaderReader<'a, R>
@@ -13,22 +13,30 @@ where
     fsm: Option<EntryFsm>,
     local_header: Option<LocalFileHeader<'a>>,
     aex_data: Option<AexData>,
+    metrics: Option<EntryReadMetrics>,
 }
 
 impl<R> LocalHeaderReader<'_, R>
 where
     R: io::Read,
 {
-    pub(crate) fn new(entry: &Entry, rd: R, parsed_ranges: Rc<Mutex<ParsedRanges>>) -> Self {
+    pub(crate) fn new(
+        entry: &Entry,
+        rd: R,
+        parsed_ranges: Rc<RefCell<ParsedRanges>>,
+        password: Option<&[u8]>,
+    ) -> Self {
+        let mut fsm = EntryFsm::new(Some(entry.clone()), None, Some(parsed_ranges));
+        if let Some(password) = password {
+            fsm = fsm.with_password(password);
+        }
+
         Self {
             rd,
-            fsm: Some(EntryFsm::new(
-                Some(entry.clone()),
-                None,
-                Some(parsed_ranges),
-            )),
+            fsm: Some(fsm),
             local_header: None,
             aex_data: None,
+            metrics: None,
         }
     }
 
@@ -39,6 +47,10 @@ where
     pub(crate) fn take_aex_data(&mut self) -> Option<AexData> {
         self.aex_data.take()
     }
+
+    pub(crate) fn take_metrics(&mut self) -> Option<EntryReadMetrics> {
+        self.metrics.take()
+    }
 }
 
 impl<R> io::Read for LocalHeaderReader<'_, R>
@@ -78,9 +90,10 @@ where
                         return Err(io::Error::other("entry reader: no progress"));
                     }
                 }
-                FsmResult::Done((_, local_file_header, aex_data)) => {
+                FsmResult::Done((_, local_file_header, aex_data, metrics)) => {
                     self.local_header = local_file_header.map(|s| s.into_owned());
                     self.aex_data = aex_data;
+                    self.metrics = Some(metrics);
 
                     // neat!
                     return Ok(0);
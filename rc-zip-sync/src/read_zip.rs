@@ -1,6 +1,7 @@
 use rc_zip::{
-    error::Error,
-    fsm::{AexData, ArchiveFsm, FsmResult, ParsedRanges},
+    encoding::Encoding,
+    error::{Error, FormatError},
+    fsm::{AexData, ArchiveFsm, EntryReadMetrics, FsmResult, ParsedRanges},
     parse::{Archive, LocalFileHeader},
 };
 use rc_zip::{fsm::EntryFsm, parse::Entry};
@@ -9,10 +10,10 @@ use tracing::trace;
 use crate::streaming_entry_reader::StreamingEntryReader;
 use crate::{entry_reader::EntryReader, local_header_reader::LocalHeaderReader};
 use std::{
-    io::Read,
+    cell::RefCell,
+    io::{Read, Seek, SeekFrom},
     ops::{Deref, DerefMut},
     rc::Rc,
-    sync::Mutex,
 };
 
 /// A trait for reading something as a zip archive
@@ -24,6 +25,21 @@ pub trait ReadZipWithSize {
 
     /// Reads self as a zip archive.
     fn read_zip_with_size(&self, size: u64) -> Result<ArchiveHandle<'_, Self::File>, Error>;
+
+    /// Reads self as the concatenated segments of a split archive, where
+    /// `disk_offsets` has the offset at which each segment starts. See
+    /// [ArchiveFsm::with_disk_offsets].
+    fn read_zip_with_disk_offsets(
+        &self,
+        size: u64,
+        disk_offsets: Vec<u64>,
+    ) -> Result<ArchiveHandle<'_, Self::File>, Error> {
+        self.read_zip_with_fsm(ArchiveFsm::new(size).with_disk_offsets(disk_offsets))
+    }
+
+    /// Reads self as a zip archive with a state machine set up by the
+    /// caller, e.g. with [ArchiveFsm::with_encoding].
+    fn read_zip_with_fsm(&self, fsm: ArchiveFsm) -> Result<ArchiveHandle<'_, Self::File>, Error>;
 }
 
 /// A trait for reading something as a zip archive when we can tell size from
@@ -36,6 +52,13 @@ pub trait ReadZip {
 
     /// Reads self as a zip archive.
     fn read_zip(&self) -> Result<ArchiveHandle<'_, Self::File>, Error>;
+
+    /// Reads self as a zip archive, decoding names and comments with
+    /// `encoding` instead of detecting it. See [ArchiveFsm::with_encoding].
+    fn read_zip_with_encoding(
+        &self,
+        encoding: Encoding,
+    ) -> Result<ArchiveHandle<'_, Self::File>, Error>;
 }
 
 impl<F> ReadZipWithSize for F
@@ -45,60 +68,67 @@ where
     type File = F;
 
     fn read_zip_with_size(&self, size: u64) -> Result<ArchiveHandle<'_, F>, Error> {
-        struct CursorState<'a, F: HasCursor + 'a> {
-            cursor: <F as HasCursor>::Cursor<'a>,
-            offset: u64,
-        }
-        let mut cstate: Option<CursorState<'_, F>> = None;
+        read_zip_with_fsm(self, ArchiveFsm::new(size))
+    }
 
-        let mut fsm = ArchiveFsm::new(size);
-        loop {
-            if let Some(offset) = fsm.wants_read() {
-                trace!(%offset, "read_zip_with_size: wants_read, space len = {}", fsm.space().len());
-
-                let mut cstate_next = match cstate.take() {
-                    Some(cstate) => {
-                        if cstate.offset == offset {
-                            // all good, re-using
-                            cstate
-                        } else {
-                            CursorState {
-                                cursor: self.cursor_at(offset),
-                                offset,
-                            }
+    fn read_zip_with_fsm(&self, fsm: ArchiveFsm) -> Result<ArchiveHandle<'_, F>, Error> {
+        read_zip_with_fsm(self, fsm)
+    }
+}
+
+fn read_zip_with_fsm<F: HasCursor>(
+    file: &F,
+    mut fsm: ArchiveFsm,
+) -> Result<ArchiveHandle<'_, F>, Error> {
+    struct CursorState<'a, F: HasCursor + 'a> {
+        cursor: <F as HasCursor>::Cursor<'a>,
+        offset: u64,
+    }
+    let mut cstate: Option<CursorState<'_, F>> = None;
+
+    loop {
+        if let Some(offset) = fsm.wants_read() {
+            trace!(%offset, "read_zip_with_size: wants_read, space len = {}", fsm.space().len());
+
+            let mut cstate_next = match cstate.take() {
+                Some(cstate) => {
+                    if cstate.offset == offset {
+                        // all good, re-using
+                        cstate
+                    } else {
+                        CursorState {
+                            cursor: file.cursor_at(offset),
+                            offset,
                         }
                     }
-                    None => CursorState {
-                        cursor: self.cursor_at(offset),
-                        offset,
-                    },
-                };
-
-                match cstate_next.cursor.read(fsm.space()) {
-                    Ok(read_bytes) => {
-                        cstate_next.offset += read_bytes as u64;
-                        cstate = Some(cstate_next);
-
-                        trace!(%read_bytes, "read_zip_with_size: read");
-                        if read_bytes == 0 {
-                            return Err(Error::IO(std::io::ErrorKind::UnexpectedEof.into()));
-                        }
-                        fsm.fill(read_bytes);
+                }
+                None => CursorState {
+                    cursor: file.cursor_at(offset),
+                    offset,
+                },
+            };
+
+            match cstate_next.cursor.read(fsm.space()) {
+                Ok(read_bytes) => {
+                    cstate_next.offset += read_bytes as u64;
+                    cstate = Some(cstate_next);
+
+                    trace!(%read_bytes, "read_zip_with_size: read");
+                    if read_bytes == 0 {
+                        return Err(Error::IO(std::io::ErrorKind::UnexpectedEof.into()));
                     }
-                    Err(err) => return Err(Error::IO(err)),
+                    fsm.fill(read_bytes);
                 }
+                Err(err) => return Err(Error::IO(err)),
             }
+        }
 
-            fsm = match fsm.process()? {
-                FsmResult::Done(archive) => {
-                    trace!("read_zip_with_size: done");
-                    return Ok(ArchiveHandle {
-                        file: self,
-                        archive,
-                    });
-                }
-                FsmResult::Continue(fsm) => fsm,
+        fsm = match fsm.process()? {
+            FsmResult::Done(archive) => {
+                trace!("read_zip_with_size: done");
+                return Ok(ArchiveHandle { file, archive });
             }
+            FsmResult::Continue(fsm) => fsm,
         }
     }
 }
@@ -109,6 +139,13 @@ impl ReadZip for &[u8] {
     fn read_zip(&self) -> Result<ArchiveHandle<'_, Self::File>, Error> {
         self.read_zip_with_size(self.len() as u64)
     }
+
+    fn read_zip_with_encoding(
+        &self,
+        encoding: Encoding,
+    ) -> Result<ArchiveHandle<'_, Self::File>, Error> {
+        self.read_zip_with_fsm(ArchiveFsm::new(self.len() as u64).with_encoding(encoding))
+    }
 }
 
 impl ReadZip for Vec<u8> {
@@ -117,6 +154,13 @@ impl ReadZip for Vec<u8> {
     fn read_zip(&self) -> Result<ArchiveHandle<'_, Self::File>, Error> {
         self.read_zip_with_size(self.len() as u64)
     }
+
+    fn read_zip_with_encoding(
+        &self,
+        encoding: Encoding,
+    ) -> Result<ArchiveHandle<'_, Self::File>, Error> {
+        self.read_zip_with_fsm(ArchiveFsm::new(self.len() as u64).with_encoding(encoding))
+    }
 }
 
 /// A zip archive, read synchronously from a file or other I/O resource.
@@ -152,10 +196,15 @@ where
     }
 }
 
-impl<F> ArchiveHandle<'_, F>
+impl<'a, F> ArchiveHandle<'a, F>
 where
     F: HasCursor,
 {
+    /// The I/O resource this archive is read from.
+    pub fn file(&self) -> &'a F {
+        self.file
+    }
+
     /// Iterate over all files in this zip, read from the central directory.
     pub fn entries(&self) -> impl Iterator<Item = EntryHandle<'_, F>> {
         self.archive.entries().map(move |entry| EntryHandle {
@@ -196,20 +245,79 @@ impl<'a, F> EntryHandle<'a, F>
 where
     F: HasCursor,
 {
-    /// Returns a reader for the entry.
+    /// Reads the entry through its local header, decompressing (and thereby
+    /// validating) all of its data, and returns what was learned along the way.
+    ///
+    /// At most `prefix_length` bytes of decompressed content are retained,
+    /// see [LocalHeaderInfo::content_prefix]. AE-x encrypted entries are
+    /// decrypted with `password`, if any (requires the `aes` feature).
     pub fn local_header(
         &'a self,
-        parsed_ranges: Rc<Mutex<ParsedRanges>>,
-    ) -> std::io::Result<Option<(LocalFileHeader<'a>, Option<AexData>)>> {
-        let mut v = Vec::new();
+        parsed_ranges: Rc<RefCell<ParsedRanges>>,
+        prefix_length: usize,
+        password: Option<&[u8]>,
+    ) -> std::io::Result<Option<LocalHeaderInfo<'a>>> {
+        self.local_header_with(parsed_ranges, prefix_length, password, |_| {})
+    }
+
+    /// Like [Self::local_header], but also hands all of the decompressed
+    /// content to `on_data` as it's decompressed, e.g. to hash it.
+    pub fn local_header_with(
+        &'a self,
+        parsed_ranges: Rc<RefCell<ParsedRanges>>,
+        prefix_length: usize,
+        password: Option<&[u8]>,
+        mut on_data: impl FnMut(&[u8]),
+    ) -> std::io::Result<Option<LocalHeaderInfo<'a>>> {
+        let mut content_prefix = Vec::new();
         let reader = self.file.cursor_at(self.entry.header_offset);
-        let mut reader = LocalHeaderReader::new(self.entry, reader, parsed_ranges);
-        reader.read_to_end(&mut v)?;
+        let mut reader = LocalHeaderReader::new(self.entry, reader, parsed_ranges, password);
 
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            on_data(&buf[..n]);
+            let wanted = prefix_length.saturating_sub(content_prefix.len());
+            content_prefix.extend_from_slice(&buf[..n.min(wanted)]);
+        }
+
+        let metrics = reader.take_metrics();
         Ok(reader
             .take_local_header()
             .map(|v| v.to_owned())
-            .map(|local_header| (local_header, reader.take_aex_data())))
+            .zip(metrics)
+            .map(|(header, metrics)| LocalHeaderInfo {
+                header,
+                aex_data: reader.take_aex_data(),
+                metrics,
+                content_prefix,
+            }))
+    }
+
+    /// Parses just the entry's local file header, without reading its data,
+    /// so that it's available even when the data can't be decompressed, e.g.
+    /// because of an unsupported method. Returns `None` if the file ends
+    /// before the header does.
+    pub fn read_local_header(&self) -> std::io::Result<Option<LocalFileHeader<'static>>> {
+        let mut reader = self.file.cursor_at(self.entry.header_offset);
+        let mut fsm = EntryFsm::new(Some(self.entry.clone()), None, None);
+        while fsm.local_header_entry().is_none() {
+            let n = reader.read(fsm.space())?;
+            fsm.fill(n);
+            match fsm.process_till_header().map(|_| ()) {
+                Ok(()) => {}
+                // the header is kept when there's no decompressor for its method
+                Err(Error::Unsupported(_)) if fsm.local_header_entry().is_some() => {}
+                Err(e) => return Err(e.into()),
+            }
+            if n == 0 && fsm.local_header_entry().is_none() {
+                return Ok(None);
+            }
+        }
+        Ok(fsm.local_header_entry().clone())
     }
 
     /// Returns a reader for the entry.
@@ -225,6 +333,22 @@ where
     }
 }
 
+/// The result of reading an entry through its local header, see
+/// [EntryHandle::local_header].
+pub struct LocalHeaderInfo<'a> {
+    /// The local file header
+    pub header: LocalFileHeader<'a>,
+
+    /// AE-x encryption data, for AE-x encrypted entries
+    pub aex_data: Option<AexData>,
+
+    /// Sizes and checksum measured while reading the entry's data
+    pub metrics: EntryReadMetrics,
+
+    /// The first bytes of the entry's decompressed content
+    pub content_prefix: Vec<u8>,
+}
+
 /// A sliceable I/O resource: we can ask for a [Read] at a given offset.
 pub trait HasCursor {
     /// The type of [Read] returned by [HasCursor::cursor_at].
@@ -243,7 +367,11 @@ impl HasCursor for &[u8] {
         Self: 'a;
 
     fn cursor_at(&self, offset: u64) -> Self::Cursor<'_> {
-        &self[offset.try_into().unwrap()..]
+        // reading past the end hits EOF right away, like it would for a file
+        usize::try_from(offset)
+            .ok()
+            .and_then(|offset| self.get(offset..))
+            .unwrap_or_default()
     }
 }
 
@@ -254,7 +382,11 @@ impl HasCursor for Vec<u8> {
         Self: 'a;
 
     fn cursor_at(&self, offset: u64) -> Self::Cursor<'_> {
-        &self[offset.try_into().unwrap()..]
+        // reading past the end hits EOF right away, like it would for a file
+        usize::try_from(offset)
+            .ok()
+            .and_then(|offset| self.get(offset..))
+            .unwrap_or_default()
     }
 }
 
@@ -278,6 +410,78 @@ impl ReadZip for std::fs::File {
         let size = self.metadata()?.len();
         self.read_zip_with_size(size)
     }
+
+    fn read_zip_with_encoding(&self, encoding: Encoding) -> Result<ArchiveHandle<'_, Self>, Error> {
+        let size = self.metadata()?.len();
+        self.read_zip_with_fsm(ArchiveFsm::new(size).with_encoding(encoding))
+    }
+}
+
+/// Makes any [Read] + [Seek] readable as a zip archive, e.g. a memory-mapped
+/// region or a reader over part of a larger container.
+///
+/// All cursors share the one reader: each read seeks it to where the
+/// cursor is at first.
+pub struct SeekableReader<R> {
+    reader: RefCell<R>,
+}
+
+impl<R: Read + Seek> SeekableReader<R> {
+    /// Wraps `reader`
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: RefCell::new(reader),
+        }
+    }
+
+    /// Returns the wrapped reader
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+/// A [Read] at an offset of a [SeekableReader]
+pub struct SeekableReaderCursor<'a, R> {
+    reader: &'a RefCell<R>,
+    offset: u64,
+}
+
+impl<R: Read + Seek> Read for SeekableReaderCursor<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start(self.offset))?;
+        let read = reader.read(buf)?;
+        self.offset += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Read + Seek> HasCursor for SeekableReader<R> {
+    type Cursor<'a>
+        = SeekableReaderCursor<'a, R>
+    where
+        Self: 'a;
+
+    fn cursor_at(&self, offset: u64) -> Self::Cursor<'_> {
+        SeekableReaderCursor {
+            reader: &self.reader,
+            offset,
+        }
+    }
+}
+
+impl<R: Read + Seek> ReadZip for SeekableReader<R> {
+    type File = Self;
+
+    fn read_zip(&self) -> Result<ArchiveHandle<'_, Self>, Error> {
+        let size = self.reader.borrow_mut().seek(SeekFrom::End(0))?;
+        self.read_zip_with_size(size)
+    }
+
+    fn read_zip_with_encoding(&self, encoding: Encoding) -> Result<ArchiveHandle<'_, Self>, Error> {
+        let size = self.reader.borrow_mut().seek(SeekFrom::End(0))?;
+        self.read_zip_with_fsm(ArchiveFsm::new(size).with_encoding(encoding))
+    }
 }
 
 /// Allows reading zip entries in a streaming fashion, without seeking,
@@ -308,16 +512,21 @@ where
         let mut fsm = EntryFsm::new(None, None, None);
 
         loop {
+            let mut eof = false;
             if fsm.wants_read() {
                 let n = self.read(fsm.space())?;
                 trace!("read {} bytes into buf for first zip entry", n);
                 fsm.fill(n);
+                eof = n == 0;
             }
 
             if let Some(entry) = fsm.process_till_header()? {
                 let entry = entry.clone();
                 return Ok(StreamingEntryReader::new(fsm, entry, self));
             }
+            if eof {
+                return Err(FormatError::InvalidLocalHeader.into());
+            }
         }
     }
 }
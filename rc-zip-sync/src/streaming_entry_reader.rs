@@ -1,7 +1,6 @@
-use oval::Buffer;
 use rc_zip::{
     error::{Error, FormatError},
-    fsm::{EntryFsm, FsmResult},
+    fsm::{Buffer, EntryFsm, FsmResult},
     parse::Entry,
 };
 use std::io::{self, Read};
@@ -54,11 +53,13 @@ where
 
         match std::mem::take(&mut self.state) {
             State::Reading { mut fsm } => {
+                let mut eof = false;
                 if fsm.wants_read() {
                     trace!("fsm wants read");
                     let n = self.rd.read(fsm.space())?;
                     trace!("giving fsm {} bytes from rd", n);
                     fsm.fill(n);
+                    eof = n == 0;
                 } else {
                     trace!("fsm does not want read");
                 }
@@ -73,6 +74,10 @@ where
                             Ok(outcome.bytes_written)
                         } else if outcome.bytes_read == 0 {
                             trace!("no bytes have been written or read");
+                            if eof {
+                                // the stream ended before the entry did
+                                return Err(io::ErrorKind::UnexpectedEof.into());
+                            }
                             // that's EOF, baby!
                             Ok(0)
                         } else {
@@ -81,7 +86,7 @@ where
                             self.read(buf)
                         }
                     }
-                    FsmResult::Done((remain, _, _)) => {
+                    FsmResult::Done((remain, ..)) => {
                         self.state = State::Finished { remain };
 
                         // neat!
@@ -130,10 +135,12 @@ where
                 let mut fsm = EntryFsm::new(None, Some(remain), None);
 
                 loop {
+                    let mut eof = false;
                     if fsm.wants_read() {
                         let n = self.rd.read(fsm.space())?;
                         trace!("read {} bytes into buf for first zip entry", n);
                         fsm.fill(n);
+                        eof = n == 0;
                     }
 
                     match fsm.process_till_header() {
@@ -141,6 +148,10 @@ where
                             let entry = entry.clone();
                             return Ok(Some(StreamingEntryReader::new(fsm, entry, self.rd)));
                         }
+                        Ok(None) if eof => {
+                            // the stream ended without a central directory
+                            return Ok(None);
+                        }
                         Ok(None) => {
                             // needs more turns
                         }
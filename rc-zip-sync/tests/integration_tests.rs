@@ -1,7 +1,7 @@
 use rc_zip::{
     corpus::{self, zips_dir, Case, Files},
     error::Error,
-    parse::Archive,
+    parse::{Archive, EndOfCentralDirectoryRecord},
 };
 use rc_zip_sync::{ArchiveHandle, HasCursor, ReadZip, ReadZipStreaming, ReadZipWithSize};
 
@@ -49,6 +49,62 @@ fn read_from_file() {
     assert_eq!(archive.entries().count(), 2);
 }
 
+#[test]
+fn read_maximal_comment() {
+    let mut bytes = std::fs::read(zips_dir().join("test.zip")).unwrap();
+    let eocd = bytes
+        .windows(4)
+        .rposition(|window| window == b"PK\x05\x06")
+        .unwrap();
+    // replace the comment, whose length is the last field of the record
+    bytes.truncate(eocd + 20);
+    bytes.extend_from_slice(&u16::MAX.to_le_bytes());
+    bytes.resize(bytes.len() + u16::MAX as usize, b'c');
+    assert_eq!(bytes.len() - eocd, EndOfCentralDirectoryRecord::MAX_LENGTH);
+
+    let slice = &bytes[..];
+    let archive = slice.read_zip().unwrap();
+    assert_eq!(archive.comment().len(), u16::MAX as usize);
+    assert_eq!(archive.entries().count(), 2);
+}
+
+#[cfg(feature = "lzma")]
+#[test]
+fn lzma_sizes() {
+    use rc_zip::fsm::ParsedRanges;
+    use std::{cell::RefCell, rc::Rc};
+
+    let bytes = std::fs::read(zips_dir().join("found-me-lzma.zip")).unwrap();
+    let slice = &bytes[..];
+    let archive = slice.read_zip().unwrap();
+    let entry = archive.entries().next().unwrap();
+    let parsed_ranges = Rc::new(RefCell::new(ParsedRanges::new()));
+    let info = entry
+        .local_header(parsed_ranges.clone(), 0, None)
+        .unwrap()
+        .unwrap();
+
+    // the 4-byte LZMA header counts towards the compressed size, and the end
+    // of stream marker is read along with the rest of the data
+    assert_eq!(info.metrics.compressed_size, entry.compressed_size);
+    assert_eq!(info.metrics.uncompressed_size, entry.uncompressed_size);
+
+    let parsed_ranges = parsed_ranges.borrow();
+    let range = |contains: &str| {
+        parsed_ranges
+            .iter()
+            .find(|range| range.contains() == contains)
+            .unwrap()
+            .range()
+    };
+    let header_end = entry.header_offset + 30 + entry.name.len() as u64;
+    assert_eq!(range("local file header"), entry.header_offset..header_end);
+    assert_eq!(
+        range("file data"),
+        header_end..header_end + entry.compressed_size
+    );
+}
+
 #[test]
 fn real_world_files() {
     corpus::install_test_subscriber();
@@ -1,8 +1,7 @@
-use oval::Buffer;
 use pin_project_lite::pin_project;
 use rc_zip::{
     error::{Error, FormatError},
-    fsm::{EntryFsm, FsmResult},
+    fsm::{Buffer, EntryFsm, FsmResult},
     parse::Entry,
 };
 use std::{io, pin::Pin, task};
@@ -100,7 +99,7 @@ where
                             return self.poll_read(cx, buf);
                         }
                     }
-                    FsmResult::Done((remain, _, _)) => {
+                    FsmResult::Done((remain, ..)) => {
                         *this.state = State::Finished { remain };
 
                         // neat!
@@ -257,6 +257,41 @@ pub fn test_cases() -> Vec<Case> {
             }]),
             ..Default::default()
         },
+        // same with ppmd
+        #[cfg(feature = "ppmd")]
+        Case {
+            name: "found-me-ppmd.zip",
+            expected_encoding: Some(Encoding::Utf8),
+            files: Files::ExhaustiveList(vec![CaseFile {
+                name: "found-me.txt",
+                content: FileContent::Bytes("Oh no, you found me\n".repeat(5000).into()),
+                modified: Some(date((2024, 1, 26), (16, 14, 34), 0, time_zone(0)).unwrap()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        },
+        // implode with a 4K window and raw literals, and with an 8K window
+        // and a literal tree
+        #[cfg(feature = "implode")]
+        Case {
+            name: "implode.zip",
+            expected_encoding: Some(Encoding::Utf8),
+            files: Files::ExhaustiveList(vec![
+                CaseFile {
+                    name: "hamlet-4k.txt",
+                    content: FileContent::File("hamlet.txt"),
+                    modified: Some(date((1993, 5, 17), (9, 30, 0), 0, time_zone(0)).unwrap()),
+                    ..Default::default()
+                },
+                CaseFile {
+                    name: "hamlet-8k-literals.txt",
+                    content: FileContent::File("hamlet.txt"),
+                    modified: Some(date((1993, 5, 17), (9, 30, 0), 0, time_zone(0)).unwrap()),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        },
     ]
 }
 
@@ -7,7 +7,11 @@
 //! Others use the system's local character encoding, and we have no choice but
 //! to make an educated guess thanks to the chardet-ng crate.
 
-use std::fmt;
+use alloc::{
+    string::{String, ToString},
+    vec,
+};
+use core::fmt;
 
 /// Encodings supported by this crate
 #[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize)]
@@ -39,12 +43,56 @@ impl fmt::Display for Encoding {
     }
 }
 
+/// How the [Encoding] of the names and comments of an archive was chosen, to
+/// tell a confident choice from a guess.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize)]
+pub struct EncodingDetection {
+    /// The chosen encoding
+    pub encoding: Encoding,
+
+    /// Whether every name and comment was valid UTF-8 that is either plain
+    /// ASCII or flagged as UTF-8, so that nothing had to be guessed
+    pub all_utf8: bool,
+
+    /// Whether the names and comments that were guessed from contain bytes
+    /// that are box drawing characters in CP437 (0xB0 to 0xDF), which are
+    /// unlikely in file names, so that a Shift-JIS guess is kept
+    pub suspicious_for_cp437: bool,
+
+    /// The encoding chardetng guessed, by its WHATWG name, before it was
+    /// mapped to one of the supported encodings, if a guess was needed
+    pub guess: Option<&'static str>,
+
+    /// Whether chardetng's guess scored higher than at least one other
+    /// candidate; if not, it's likely wrong. Always true if nothing had to be
+    /// guessed.
+    pub confident: bool,
+
+    /// Whether the encoding was given rather than detected, in which case
+    /// nothing was guessed
+    pub forced: bool,
+}
+
+impl EncodingDetection {
+    /// The detection of an encoding that was given rather than detected
+    pub fn forced(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            all_utf8: false,
+            suspicious_for_cp437: false,
+            guess: None,
+            confident: true,
+            forced: true,
+        }
+    }
+}
+
 /// Errors encountered while converting text to UTF-8.
 #[derive(Debug, thiserror::Error)]
 pub enum DecodingError {
     /// Text claimed to be UTF-8, but wasn't (as far as we can tell).
     #[error("invalid utf-8: {0}")]
-    Utf8Error(std::str::Utf8Error),
+    Utf8Error(core::str::Utf8Error),
 
     /// Text is too large to be converted.
     ///
@@ -58,8 +106,8 @@ pub enum DecodingError {
     EncodingError(&'static str),
 }
 
-impl From<std::str::Utf8Error> for DecodingError {
-    fn from(e: std::str::Utf8Error) -> Self {
+impl From<core::str::Utf8Error> for DecodingError {
+    fn from(e: core::str::Utf8Error) -> Self {
         DecodingError::Utf8Error(e)
     }
 }
@@ -68,7 +116,7 @@ impl Encoding {
     pub(crate) fn decode(&self, i: &[u8]) -> Result<String, DecodingError> {
         match self {
             Encoding::Utf8 => {
-                let s = std::str::from_utf8(i)?;
+                let s = core::str::from_utf8(i)?;
                 Ok(s.to_string())
             }
             Encoding::Cp437 => Ok(oem_cp::decode_string_complete_table(
@@ -79,6 +127,21 @@ impl Encoding {
         }
     }
 
+    /// Decodes `i`, replacing anything that isn't valid in this encoding
+    /// with U+FFFD, so that the same name can be compared across encodings.
+    pub fn decode_lossy(&self, i: &[u8]) -> String {
+        match self {
+            Encoding::Utf8 => String::from_utf8_lossy(i).into_owned(),
+            Encoding::Cp437 => {
+                oem_cp::decode_string_complete_table(i, &oem_cp::code_table::DECODING_TABLE_CP437)
+            }
+            Encoding::ShiftJis => encoding_rs::SHIFT_JIS
+                .decode_without_bom_handling(i)
+                .0
+                .into_owned(),
+        }
+    }
+
     fn decode_as(
         &self,
         i: &[u8],
@@ -104,7 +167,7 @@ impl Encoding {
 // must be considered UTF-8 encoding (i.e., not compatible with CP-437, ASCII,
 // or any other common encoding).
 pub(crate) fn detect_utf8(input: &[u8]) -> (bool, bool) {
-    match std::str::from_utf8(input) {
+    match core::str::from_utf8(input) {
         Err(_) => {
             // not valid utf-8
             (false, false)
@@ -1,5 +1,7 @@
 //! All error types used in this crate
 
+use alloc::string::String;
+
 use crate::parse::Method;
 
 use super::encoding;
@@ -20,9 +22,19 @@ pub enum Error {
     Encoding(#[from] encoding::DecodingError),
 
     /// I/O-related error
+    #[cfg(feature = "std")]
     #[error("io: {0}")]
     IO(#[from] std::io::Error),
 
+    /// A state machine was used in a way it doesn't support, e.g. resumed
+    /// from a checkpoint that doesn't fit the entry
+    #[error("invalid input: {0}")]
+    InvalidInput(&'static str),
+
+    /// The input ended while a state machine still needed more of it
+    #[error("unexpected end of input: {0}")]
+    UnexpectedEof(&'static str),
+
     /// Decompression-related error
     #[error("{method:?} decompression error: {msg}")]
     Decompression {
@@ -60,6 +72,11 @@ pub enum UnsupportedError {
     #[error("compression method supported, but not enabled in this build: {0:?}")]
     MethodNotEnabled(Method),
 
+    /// Reading the entry can't be resumed from a checkpoint, because the
+    /// state of its decompressor can't be restored.
+    #[error("resuming from a checkpoint is only supported for stored entries, not {0:?}")]
+    ResumeNotSupported(Method),
+
     /// The zip file uses a version of LZMA that is not supported.
     #[error("only LZMA2.0 is supported, found LZMA{minor}.{major}")]
     LzmaVersionUnsupported {
@@ -120,6 +137,11 @@ pub enum FormatError {
     #[error("could not decode extra field")]
     InvalidExtraField,
 
+    /// The entry uses the AE-x method (99), but has no AE-x extra field
+    /// describing the encryption strength and actual compression method.
+    #[error("AE-x encrypted entry is missing its extra field")]
+    MissingAexField,
+
     /// The header offset of an entry is invalid.
     ///
     /// This can indicate an invalid zip archive, or an invalid user-provided global offset
@@ -165,10 +187,15 @@ pub enum FormatError {
     },
 }
 
+#[cfg(feature = "std")]
 impl From<Error> for std::io::Error {
     fn from(e: Error) -> Self {
         match e {
             Error::IO(e) => e,
+            e @ Error::InvalidInput(_) => std::io::Error::new(std::io::ErrorKind::InvalidInput, e),
+            e @ Error::UnexpectedEof(_) => {
+                std::io::Error::new(std::io::ErrorKind::UnexpectedEof, e)
+            }
             e => std::io::Error::other(e),
         }
     }
@@ -1,8 +1,9 @@
-use std::{rc::Rc, sync::Mutex};
+use alloc::{rc::Rc, vec, vec::Vec};
+use core::{cell::RefCell, cmp};
 
 use super::{FsmResult, ParsedRanges};
 use crate::{
-    encoding::Encoding,
+    encoding::{Encoding, EncodingDetection},
     error::{Error, FormatError},
     parse::{
         Archive, CentralDirectoryFileHeader, EndOfCentralDirectory, EndOfCentralDirectory64Locator,
@@ -18,6 +19,15 @@ use winnow::{
     Parser, Partial,
 };
 
+/// How many bytes at the end of the file [ArchiveFsm::new] looks for the end
+/// of central directory record in, which is also the initial size of its
+/// buffer. That's enough for the longest end of central directory record,
+/// along with room to spare for the zip64 records in front of it, and for
+/// the largest possible central directory header.
+pub const HAYSTACK_SIZE: u64 = 256 * 1024;
+
+const _: () = assert!(HAYSTACK_SIZE >= EndOfCentralDirectoryRecord::MAX_LENGTH as u64);
+
 /// [ArchiveFsm] parses a valid zip archive into an [Archive]. In particular, this struct finds
 /// an end of central directory record, parses the entire central directory, detects text encoding,
 /// and normalizes metadata.
@@ -45,7 +55,33 @@ pub struct ArchiveFsm {
     buffer: Buffer,
 
     /// The ranges that have been parsed while reading the central directory
-    parsed_ranges: Rc<Mutex<ParsedRanges>>,
+    parsed_ranges: Rc<RefCell<ParsedRanges>>,
+
+    /// What it took to read the archive so far
+    metrics: ArchiveReadMetrics,
+
+    /// For a split archive read as the concatenation of its segments, the
+    /// offset at which each segment (disk) starts, see
+    /// [Self::with_disk_offsets]
+    disk_offsets: Option<Vec<u64>>,
+
+    /// The encoding to decode names and comments with instead of detecting
+    /// it, see [Self::with_encoding]
+    forced_encoding: Option<Encoding>,
+}
+
+/// What it took to read an archive's central directory, e.g. to find out why
+/// an archive is slow to read.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArchiveReadMetrics {
+    /// Amount of bytes fed to the state machine
+    pub bytes_read: u64,
+
+    /// Number of calls to [ArchiveFsm::process]
+    pub process_calls: u64,
+
+    /// Largest amount of data that was buffered at once
+    pub peak_buffered: u64,
 }
 
 #[derive(Default)]
@@ -82,17 +118,66 @@ enum State {
 
 impl ArchiveFsm {
     /// Create a new archive reader with a specified file size.
+    ///
+    /// Looks for the end of central directory record in the last
+    /// [HAYSTACK_SIZE] bytes of the file, so the buffer doesn't grow with
+    /// the file.
     pub fn new(size: u64) -> Self {
-        // just keep looking for the EndOfCentralDirectory. This is not very efficient, but that's
-        // not a priority for our usecase.
-        let haystack_size: u64 = size;
-        let buffer = Buffer::with_capacity(size as usize);
+        Self::with_haystack_size(size, HAYSTACK_SIZE)
+    }
+
+    /// Like [Self::new], but only looks for the end of central directory
+    /// record in the last `haystack_size` bytes of the file, which are read
+    /// in one go.
+    ///
+    /// The buffer starts out as large as the haystack, and grows when a
+    /// record doesn't fit in it. A haystack smaller than
+    /// [EndOfCentralDirectoryRecord::MAX_LENGTH] misses the end of central
+    /// directory record of archives with long comments, but is enough to
+    /// read just the end of central directory of most archives, see
+    /// [Self::end_of_central_directory].
+    pub fn with_haystack_size(size: u64, haystack_size: u64) -> Self {
+        let haystack_size = haystack_size.min(size);
+        let buffer = Buffer::with_capacity(haystack_size as usize);
 
         Self {
             size,
             buffer,
             state: State::ReadEocd { haystack_size },
-            parsed_ranges: Rc::new(Mutex::new(ParsedRanges::new())),
+            parsed_ranges: Rc::new(RefCell::new(ParsedRanges::new())),
+            metrics: ArchiveReadMetrics::default(),
+            disk_offsets: None,
+            forced_encoding: None,
+        }
+    }
+
+    /// Reads a split (multi-disk) archive whose segments are fed to this
+    /// state machine back to back, as if they were a single file.
+    /// `disk_offsets` has the offset at which each segment starts, so that
+    /// offsets relative to a disk, like those of local file headers, can be
+    /// translated to offsets in the concatenation.
+    ///
+    /// Disk numbers without an offset fall back to the offset correction of
+    /// single-disk archives.
+    pub fn with_disk_offsets(mut self, disk_offsets: Vec<u64>) -> Self {
+        self.disk_offsets = Some(disk_offsets);
+        self
+    }
+
+    /// Decodes all names and comments with `encoding`, rather than guessing
+    /// the encoding from them, for archives whose provenance is known.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.forced_encoding = Some(encoding);
+        self
+    }
+
+    /// The end of central directory, once it has been read: from the moment
+    /// the state machine starts reading the central directory. Callers that
+    /// only need the end of central directory can stop there.
+    pub fn end_of_central_directory(&self) -> Option<&EndOfCentralDirectory<'static>> {
+        match &self.state {
+            State::ReadCentralDirectory { eocd, .. } => Some(eocd),
+            _ => None,
         }
     }
 
@@ -130,6 +215,7 @@ impl ArchiveFsm {
     /// a fully-parsed [Archive].
     pub fn process(mut self) -> Result<FsmResult<Self, Archive>, Error> {
         use State as S;
+        self.metrics.process_calls += 1;
         match self.state {
             S::ReadEocd { haystack_size } => {
                 if self.buffer.read_bytes() < haystack_size {
@@ -153,12 +239,14 @@ impl ArchiveFsm {
                         self.buffer.reset();
                         eocdr.offset += self.size - haystack_size;
 
-                        self.parsed_ranges.try_lock().unwrap().insert_offset_length(
-                            eocdr.offset,
-                            eocdr.inner.len() as u64,
-                            "end of central directory record",
-                            None,
-                        );
+                        if let Some(mut parsed_ranges) = ParsedRanges::lock(&self.parsed_ranges) {
+                            parsed_ranges.insert_offset_length(
+                                eocdr.offset,
+                                eocdr.inner.len() as u64,
+                                "end of central directory record",
+                                None,
+                            );
+                        }
 
                         if eocdr.offset < EndOfCentralDirectory64Locator::LENGTH as u64 {
                             // no room for an EOCD64 locator, definitely not a zip64 file
@@ -222,15 +310,19 @@ impl ArchiveFsm {
                         self.buffer.reset();
                         transition!(self.state => (S::ReadEocd64Locator { eocdr }) {
                             let length = EndOfCentralDirectory64Locator::LENGTH as u64;
-                            self.parsed_ranges.try_lock().unwrap().insert_offset_length(
-                                eocdr.offset - length,
-                                length,
-                                "zip64 end of central directory locator",
-                                None,
-                            );
+                            if let Some(mut parsed_ranges) = ParsedRanges::lock(&self.parsed_ranges) {
+                                parsed_ranges.insert_offset_length(
+                                    eocdr.offset - length,
+                                    length,
+                                    "zip64 end of central directory locator",
+                                    None,
+                                );
+                            }
 
+                            let eocdr64_offset = disk_offset(&self.disk_offsets, locator.dir_disk_number)
+                                .map_or(locator.directory_offset, |offset| offset + locator.directory_offset);
                             S::ReadEocd64 {
-                                eocdr64_offset: locator.directory_offset,
+                                eocdr64_offset,
                                 eocdr,
                             }
                         });
@@ -254,9 +346,11 @@ impl ArchiveFsm {
                     Ok((_, eocdr64)) => {
                         self.buffer.reset();
                         transition!(self.state => (S::ReadEocd64 { eocdr, eocdr64_offset }) {
-                            self.parsed_ranges.try_lock().unwrap().insert_offset_length(
-                                eocdr64_offset, eocdr64.len() as u64, "zip64 end of central directory record", None
-                            );
+                            if let Some(mut parsed_ranges) = ParsedRanges::lock(&self.parsed_ranges) {
+                                parsed_ranges.insert_offset_length(
+                                    eocdr64_offset, eocdr64.len() as u64, "zip64 end of central directory record", None
+                                );
+                            }
                             let eocd = EndOfCentralDirectory::new(
                                 self.size,
                                 eocdr,
@@ -276,7 +370,7 @@ impl ArchiveFsm {
             S::ReadCentralDirectory {
                 ref eocd,
                 ref mut directory_headers,
-                mut current_header_offset,
+                ref mut current_header_offset,
             } => {
                 trace!(
                     "ReadCentralDirectory | process(), available: {}",
@@ -292,6 +386,7 @@ impl ArchiveFsm {
                 'read_headers: while !input.is_empty() {
                     match CentralDirectoryFileHeader::parser.parse_next(&mut input) {
                         Ok(dh) => {
+                            let header_start = valid_consumed;
                             valid_consumed = input.as_bytes().offset_from(&self.buffer.data());
                             trace!(
                                 input_empty_now = input.is_empty(),
@@ -300,16 +395,22 @@ impl ArchiveFsm {
                                 "ReadCentralDirectory | parsed directory header"
                             );
 
-                            let entry = dh.as_entry(Encoding::Utf8, eocd.global_offset as u64);
-                            let current_header_end =
-                                eocd.directory_offset() + valid_consumed as u64;
-                            self.parsed_ranges.try_lock().unwrap().insert_range(
-                                current_header_offset..current_header_end,
-                                "central directory header",
-                                entry.map(|e| e.name).ok(),
+                            let entry = dh.as_entry(
+                                Encoding::Utf8,
+                                header_base(&self.disk_offsets, &dh, eocd.global_offset),
                             );
+                            let current_header_end =
+                                *current_header_offset + (valid_consumed - header_start) as u64;
+                            if let Some(mut parsed_ranges) = ParsedRanges::lock(&self.parsed_ranges)
+                            {
+                                parsed_ranges.insert_range(
+                                    *current_header_offset..current_header_end,
+                                    "central directory header",
+                                    entry.map(|e| e.name).ok(),
+                                );
+                            }
 
-                            current_header_offset += valid_consumed as u64;
+                            *current_header_offset = current_header_end;
                             directory_headers.push(dh.into_owned());
                         }
                         Err(ErrMode::Incomplete(_needed)) => {
@@ -343,63 +444,20 @@ impl ArchiveFsm {
                                 .into());
                             }
 
-                            let mut detectorng = chardetng::EncodingDetector::new();
-                            let mut all_utf8 = true;
-                            let mut had_suspicious_chars_for_cp437 = false;
-
-                            {
-                                let max_feed: usize = 4096;
-                                let mut total_fed: usize = 0;
-                                let mut feed = |slice: &[u8]| {
-                                    detectorng.feed(slice, false);
-                                    for b in slice {
-                                        if (0xB0..=0xDF).contains(b) {
-                                            // those are, like, box drawing characters
-                                            had_suspicious_chars_for_cp437 = true;
-                                        }
-                                    }
-
-                                    total_fed += slice.len();
-                                    total_fed < max_feed
-                                };
-
-                                'recognize_encoding: for fh in
-                                    directory_headers.iter().filter(|fh| fh.is_non_utf8())
-                                {
-                                    all_utf8 = false;
-                                    if !feed(&fh.name[..]) || !feed(&fh.comment[..]) {
-                                        break 'recognize_encoding;
-                                    }
-                                }
-                            }
-
-                            let encoding = {
-                                if all_utf8 {
-                                    Encoding::Utf8
-                                } else {
-                                    let encoding = detectorng.guess(None, true);
-                                    if encoding == encoding_rs::SHIFT_JIS {
-                                        // well hold on, sometimes Codepage 437 is detected as
-                                        // Shift-JIS by chardetng. If we have any characters
-                                        // that aren't valid DOS file names, then okay it's probably
-                                        // Shift-JIS. Otherwise, assume it's CP437.
-                                        if had_suspicious_chars_for_cp437 {
-                                            Encoding::ShiftJis
-                                        } else {
-                                            Encoding::Cp437
-                                        }
-                                    } else if encoding == encoding_rs::UTF_8 {
-                                        Encoding::Utf8
-                                    } else {
-                                        Encoding::Cp437
-                                    }
-                                }
+                            let encoding_detection = match self.forced_encoding {
+                                Some(encoding) => EncodingDetection::forced(encoding),
+                                None => detect_encoding(directory_headers),
                             };
+                            let encoding = encoding_detection.encoding;
 
-                            let global_offset = eocd.global_offset as u64;
                             let entries: Result<Vec<Entry>, Error> = directory_headers
                                 .iter()
-                                .map(|x| x.as_entry(encoding, global_offset))
+                                .map(|x| {
+                                    x.as_entry(
+                                        encoding,
+                                        header_base(&self.disk_offsets, x, eocd.global_offset),
+                                    )
+                                })
                                 .collect();
                             let entries = entries?;
 
@@ -412,7 +470,9 @@ impl ArchiveFsm {
                                 comment,
                                 entries,
                                 encoding,
+                                encoding_detection,
                                 parsed_ranges: self.parsed_ranges,
+                                read_metrics: self.metrics,
                             }));
                         }
                     }
@@ -436,6 +496,13 @@ impl ArchiveFsm {
         if self.buffer.available_space() == 0 {
             self.buffer.shift();
         }
+        if self.buffer.available_space() == 0 {
+            // a record that doesn't fit, e.g. a zip64 end of central
+            // directory record with a large extensible data sector
+            let capacity = self.buffer.capacity();
+            trace!(capacity, "growing full buffer");
+            self.buffer.grow(cmp::max(capacity * 2, 1024));
+        }
         self.buffer.space()
     }
 
@@ -443,14 +510,100 @@ impl ArchiveFsm {
     /// many bytes were written.
     #[inline]
     pub fn fill(&mut self, count: usize) -> usize {
-        self.buffer.fill(count)
+        let n = self.buffer.fill(count);
+        self.metrics.bytes_read += n as u64;
+        self.metrics.peak_buffered = self
+            .metrics
+            .peak_buffered
+            .max(self.buffer.available_data() as u64);
+        n
     }
 }
 
-/// A wrapper around [oval::Buffer] that keeps track of how many bytes we've read since
+/// Guesses the encoding of the names and comments in `directory_headers`,
+/// unless they're all UTF-8
+fn detect_encoding(directory_headers: &[CentralDirectoryFileHeader<'_>]) -> EncodingDetection {
+    let mut detectorng = chardetng::EncodingDetector::new();
+    let mut all_utf8 = true;
+    let mut had_suspicious_chars_for_cp437 = false;
+
+    {
+        let max_feed: usize = 4096;
+        let mut total_fed: usize = 0;
+        let mut feed = |slice: &[u8]| {
+            detectorng.feed(slice, false);
+            for b in slice {
+                if (0xB0..=0xDF).contains(b) {
+                    // those are, like, box drawing characters
+                    had_suspicious_chars_for_cp437 = true;
+                }
+            }
+
+            total_fed += slice.len();
+            total_fed < max_feed
+        };
+
+        'recognize_encoding: for fh in directory_headers.iter().filter(|fh| fh.is_non_utf8()) {
+            all_utf8 = false;
+            if !feed(&fh.name[..]) || !feed(&fh.comment[..]) {
+                break 'recognize_encoding;
+            }
+        }
+    }
+
+    let guess = (!all_utf8).then(|| detectorng.guess_assess(None, true));
+    let encoding = {
+        if let Some((encoding, _)) = guess {
+            if encoding == encoding_rs::SHIFT_JIS {
+                // well hold on, sometimes Codepage 437 is detected as
+                // Shift-JIS by chardetng. If we have any characters
+                // that aren't valid DOS file names, then okay it's probably
+                // Shift-JIS. Otherwise, assume it's CP437.
+                if had_suspicious_chars_for_cp437 {
+                    Encoding::ShiftJis
+                } else {
+                    Encoding::Cp437
+                }
+            } else if encoding == encoding_rs::UTF_8 {
+                Encoding::Utf8
+            } else {
+                Encoding::Cp437
+            }
+        } else {
+            Encoding::Utf8
+        }
+    };
+    EncodingDetection {
+        encoding,
+        all_utf8,
+        suspicious_for_cp437: had_suspicious_chars_for_cp437,
+        guess: guess.map(|(encoding, _)| encoding.name()),
+        confident: guess.is_none_or(|(_, confident)| confident),
+        forced: false,
+    }
+}
+
+/// Offset at which the segment for `disk` starts, when reading a split
+/// archive that has one
+fn disk_offset(disk_offsets: &Option<Vec<u64>>, disk: u32) -> Option<u64> {
+    disk_offsets.as_ref()?.get(disk as usize).copied()
+}
+
+/// What to add to the header offset of a directory header: the start of the
+/// segment it's on for split archives, or the offset of the whole archive
+/// within the file otherwise
+fn header_base(
+    disk_offsets: &Option<Vec<u64>>,
+    header: &CentralDirectoryFileHeader<'_>,
+    global_offset: i64,
+) -> u64 {
+    disk_offset(disk_offsets, header.disk_nbr_start as u32).unwrap_or(global_offset as u64)
+}
+
+/// A wrapper around [super::Buffer] that keeps track of how many bytes we've read since
 /// initialization or the last reset.
 pub(crate) struct Buffer {
-    pub(crate) buffer: oval::Buffer,
+    pub(crate) buffer: super::Buffer,
     pub(crate) read_bytes: u64,
 }
 
@@ -458,7 +611,7 @@ impl Buffer {
     /// creates a new buffer with the specified capacity
     pub(crate) fn with_capacity(size: usize) -> Self {
         Self {
-            buffer: oval::Buffer::with_capacity(size),
+            buffer: super::Buffer::with_capacity(size),
             read_bytes: 0,
         }
     }
@@ -495,6 +648,18 @@ impl Buffer {
         self.buffer.available_space()
     }
 
+    /// returns the size of the buffer
+    #[inline]
+    pub(crate) fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// grows the buffer to `size` bytes, keeping its data
+    #[inline]
+    pub(crate) fn grow(&mut self, size: usize) {
+        self.buffer.grow(size);
+    }
+
     /// returns a mutable slice with all the available space to
     /// write to
     #[inline]
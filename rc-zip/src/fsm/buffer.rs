@@ -0,0 +1,123 @@
+use alloc::{vec, vec::Vec};
+use core::cmp;
+
+/// A fixed-size buffer the state machines read into, keeping track of where
+/// the data that hasn't been consumed yet starts and ends.
+///
+/// This is the part of `oval::Buffer` the state machines use, which can't be
+/// used itself because it needs `std`.
+///
+/// In all cases, `0 ≤ position ≤ end ≤ memory.len()` holds.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Buffer {
+    memory: Vec<u8>,
+    /// beginning of the available data
+    position: usize,
+    /// end of the available data, and beginning of the available space
+    end: usize,
+}
+
+impl Buffer {
+    /// allocates a new buffer of `capacity` bytes
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            memory: vec![0; capacity],
+            position: 0,
+            end: 0,
+        }
+    }
+
+    /// grows the buffer to `size` bytes, keeping its data
+    ///
+    /// this does nothing if the buffer is already large enough
+    pub fn grow(&mut self, size: usize) -> bool {
+        if self.capacity() >= size {
+            return false;
+        }
+        self.memory.resize(size, 0);
+        true
+    }
+
+    /// returns how much data can be read from the buffer
+    #[inline]
+    pub fn available_data(&self) -> usize {
+        self.end - self.position
+    }
+
+    /// returns how much free space is available to write to
+    #[inline]
+    pub fn available_space(&self) -> usize {
+        self.capacity() - self.end
+    }
+
+    /// returns the size of the buffer
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// advances the position tracker
+    ///
+    /// if the position gets past the buffer's half,
+    /// this will call `shift()` to move the remaining data
+    /// to the beginning of the buffer
+    #[inline]
+    pub fn consume(&mut self, count: usize) -> usize {
+        let count = cmp::min(count, self.available_data());
+        self.position += count;
+        if self.position > self.capacity() / 2 {
+            self.shift();
+        }
+        count
+    }
+
+    /// after having written data to the buffer, use this function
+    /// to indicate how many bytes were written
+    ///
+    /// if there is not enough available space, this function can call
+    /// `shift()` to move the remaining data to the beginning of the
+    /// buffer
+    #[inline]
+    pub fn fill(&mut self, count: usize) -> usize {
+        let count = cmp::min(count, self.available_space());
+        self.end += count;
+        if self.available_space() < self.available_data() + count {
+            self.shift();
+        }
+        count
+    }
+
+    /// moves the position and end trackers to the beginning,
+    /// without modifying the data
+    #[inline]
+    pub fn reset(&mut self) {
+        self.position = 0;
+        self.end = 0;
+    }
+
+    /// returns a slice with all the available data
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.memory[self.position..self.end]
+    }
+
+    /// returns a mutable slice with all the available space to
+    /// write to
+    #[inline]
+    pub fn space(&mut self) -> &mut [u8] {
+        &mut self.memory[self.end..]
+    }
+
+    /// moves the data at the beginning of the buffer
+    ///
+    /// if the position was more than 0, it is now 0
+    #[inline]
+    pub fn shift(&mut self) {
+        if self.position > 0 {
+            let length = self.available_data();
+            self.memory.copy_within(self.position..self.end, 0);
+            self.position = 0;
+            self.end = length;
+        }
+    }
+}
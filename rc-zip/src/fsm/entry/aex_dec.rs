@@ -1,14 +1,56 @@
-use std::cmp;
+#[cfg(feature = "aes")]
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp;
 
-use crate::{error::Error, parse::ExtraAexField};
+use crate::{
+    error::{Error, FormatError},
+    parse::ExtraAexField,
+};
 
 use super::{DecompressOutcome, Decompressor, HasMoreInput};
 
+// https://www.winzip.com/en/support/aes-encryption/#file-format1
+const PASSWORD_VERIFICATION_SIZE: usize = ExtraAexField::PASSWORD_VERIFICATION_SIZE as usize;
+const AUTHENTICATION_CODE_SIZE: usize = ExtraAexField::AUTHENTICATION_CODE_SIZE as usize;
+
 pub(crate) struct AexDec {
-    aex: ExtraAexField,
-    salt_value: Option<Vec<u8>>,
-    password_verification_value: Option<Vec<u8>>,
-    authentication_code: Option<Vec<u8>>,
+    /// Size of the salt, which depends on the AES key size
+    salt_size: usize,
+
+    /// Total size of the entry's data: salt, password verification value,
+    /// encrypted data and authentication code
+    compressed_size: u64,
+
+    /// Amount of bytes consumed so far
+    consumed: u64,
+
+    /// Salt followed by the password verification value
+    header: Vec<u8>,
+
+    authentication_code: Vec<u8>,
+
+    mode: Mode,
+}
+
+enum Mode {
+    /// We don't know the password: pass the data through as-is
+    Passthrough,
+
+    /// We know the password, but haven't seen the salt yet
+    #[cfg(feature = "aes")]
+    Pending {
+        password: Vec<u8>,
+        inner: Box<super::AnyDecompressor>,
+    },
+
+    /// The password is correct: decrypt and decompress the data
+    #[cfg(feature = "aes")]
+    Decrypting(Box<crypto::Decryption>),
+
+    /// The password is wrong: skip over the data
+    #[cfg(feature = "aes")]
+    WrongPassword,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -16,78 +58,351 @@ pub struct AexData {
     salt_value: Vec<u8>,
     password_verification_value: Vec<u8>,
     authentication_code: Vec<u8>,
+
+    /// Whether the provided password matches the password verification
+    /// value, if a password was provided
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password_correct: Option<bool>,
+
+    /// Whether the authentication code matches the encrypted data, if the
+    /// password was correct
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authenticated: Option<bool>,
+}
+
+impl AexData {
+    /// Whether `password` matches the password verification value, without
+    /// decrypting anything, e.g. to test a list of candidate passwords. The
+    /// verification value is only two bytes, so about one in 65536 wrong
+    /// passwords matches too: confirm a match by decrypting with it.
+    #[cfg(feature = "aes")]
+    pub fn verify_password(&self, password: impl AsRef<[u8]>) -> bool {
+        crypto::password_matches(
+            password.as_ref(),
+            &self.salt_value,
+            &self.password_verification_value,
+        )
+    }
 }
 
 impl AexDec {
-    pub(crate) fn new(aex: ExtraAexField) -> Self {
-        Self {
-            aex,
-            salt_value: None,
-            password_verification_value: None,
-            authentication_code: None,
-        }
+    /// Creates a decoder for `compressed_size` bytes of AE-x data.
+    ///
+    /// Without a password (or without the `aes` feature), the data is passed
+    /// through as-is. With one, it is decrypted and fed to `inner`, which
+    /// decompresses it according to the actual compression method.
+    pub(crate) fn new(
+        aex: ExtraAexField,
+        compressed_size: u64,
+        decryption: Option<(&[u8], super::AnyDecompressor)>,
+    ) -> Result<Self, Error> {
+        let Some(salt_size) = aex.salt_size() else {
+            return Err(Error::Format(FormatError::InvalidExtraField));
+        };
+
+        let mode = match decryption {
+            #[cfg(feature = "aes")]
+            Some((password, inner)) => Mode::Pending {
+                password: password.to_vec(),
+                inner: Box::new(inner),
+            },
+            _ => Mode::Passthrough,
+        };
+
+        Ok(Self {
+            salt_size: salt_size as usize,
+            compressed_size,
+            consumed: 0,
+            header: Vec::new(),
+            authentication_code: Vec::new(),
+            mode,
+        })
     }
 
     pub fn take_aex_data(&mut self) -> Option<AexData> {
+        if self.header.len() < self.salt_size + PASSWORD_VERIFICATION_SIZE
+            || self.authentication_code.len() < AUTHENTICATION_CODE_SIZE
+        {
+            return None;
+        }
+
+        let (password_correct, authenticated) = match &self.mode {
+            Mode::Passthrough => (None, None),
+            #[cfg(feature = "aes")]
+            Mode::Pending { .. } => (None, None),
+            #[cfg(feature = "aes")]
+            Mode::Decrypting(decryption) => (
+                Some(true),
+                Some(decryption.verify(&self.authentication_code)),
+            ),
+            #[cfg(feature = "aes")]
+            Mode::WrongPassword => (Some(false), None),
+        };
+
+        let (salt_value, password_verification_value) = self.header.split_at(self.salt_size);
         Some(AexData {
-            salt_value: self.salt_value.take()?,
-            password_verification_value: self.password_verification_value.take()?,
-            authentication_code: self.authentication_code.take()?,
+            salt_value: salt_value.to_vec(),
+            password_verification_value: password_verification_value.to_vec(),
+            authentication_code: core::mem::take(&mut self.authentication_code),
+            password_correct,
+            authenticated,
         })
     }
+
+    /// Offset at which the authentication code starts
+    fn data_end(&self) -> u64 {
+        self.compressed_size
+            .saturating_sub(AUTHENTICATION_CODE_SIZE as u64)
+    }
+
+    /// Consumes `input`, keeping track of the salt, password verification
+    /// value and authentication code, and handing the encrypted data in
+    /// between to the current mode.
+    fn consume(&mut self, mut input: &[u8]) {
+        let header_size = self.salt_size + PASSWORD_VERIFICATION_SIZE;
+        if self.header.len() < header_size {
+            let n = cmp::min(header_size - self.header.len(), input.len());
+            self.header.extend_from_slice(&input[..n]);
+            self.consumed += n as u64;
+            input = &input[n..];
+
+            #[cfg(feature = "aes")]
+            if self.header.len() == header_size {
+                self.mode = match core::mem::replace(&mut self.mode, Mode::Passthrough) {
+                    Mode::Pending { password, inner } => {
+                        let (salt, verification) = self.header.split_at(self.salt_size);
+                        match crypto::Decryption::new(&password, salt, verification, inner) {
+                            Some(decryption) => Mode::Decrypting(Box::new(decryption)),
+                            None => Mode::WrongPassword,
+                        }
+                    }
+                    mode => mode,
+                };
+            }
+        }
+
+        let data_len = cmp::min(
+            input.len() as u64,
+            self.data_end().saturating_sub(self.consumed),
+        ) as usize;
+        let (data, rest) = input.split_at(data_len);
+        #[cfg(feature = "aes")]
+        if let Mode::Decrypting(decryption) = &mut self.mode {
+            decryption.decrypt(data);
+        }
+        self.consumed += data.len() as u64;
+
+        let n = cmp::min(
+            AUTHENTICATION_CODE_SIZE - self.authentication_code.len(),
+            rest.len(),
+        );
+        self.authentication_code.extend_from_slice(&rest[..n]);
+        self.consumed += rest.len() as u64;
+    }
 }
 
 impl Decompressor for AexDec {
+    #[cfg_attr(not(feature = "aes"), allow(unused_variables))]
     fn decompress(
         &mut self,
         in_buf: &[u8],
         out_buf: &mut [u8],
         has_more_input: HasMoreInput,
     ) -> Result<DecompressOutcome, Error> {
-        // https://www.winzip.com/en/support/aes-encryption/#file-format1
+        match &mut self.mode {
+            Mode::Passthrough => {
+                // copy the data to the output buffer to simulate decompression progress
+                // we can't actually decrypt the data because we do not know the password
+                let bytes_read = cmp::min(in_buf.len(), out_buf.len());
+                self.consume(&in_buf[..bytes_read]);
+                out_buf[..bytes_read].copy_from_slice(&in_buf[..bytes_read]);
 
-        const PASSWORD_VERIFICATION_SIZE: usize = 2;
-        const AUTHENTICATION_CODE_SIZE: usize = 10;
+                Ok(DecompressOutcome {
+                    bytes_read,
+                    bytes_written: bytes_read,
+                })
+            }
+            #[cfg(feature = "aes")]
+            Mode::Pending { .. } => {
+                // nothing to output until we've seen the salt: once we have,
+                // carry on in whichever mode the password verification led to
+                let header_size = self.salt_size + PASSWORD_VERIFICATION_SIZE;
+                let n = cmp::min(in_buf.len(), header_size - self.header.len());
+                self.consume(&in_buf[..n]);
+                if matches!(self.mode, Mode::Pending { .. }) {
+                    return Ok(DecompressOutcome {
+                        bytes_read: n,
+                        bytes_written: 0,
+                    });
+                }
 
-        let salt_size = match self.aex.mode {
-            0x1 => 8,
-            0x2 => 12,
-            0x3 => 16,
-            _ => return Err(Error::Format(crate::error::FormatError::InvalidExtraField)),
-        };
+                let outcome = self.decompress(&in_buf[n..], out_buf, has_more_input)?;
+                Ok(DecompressOutcome {
+                    bytes_read: n + outcome.bytes_read,
+                    bytes_written: outcome.bytes_written,
+                })
+            }
+            #[cfg(feature = "aes")]
+            Mode::WrongPassword => {
+                // we can't decrypt anything, skip over the data
+                self.consume(in_buf);
+                Ok(DecompressOutcome {
+                    bytes_read: in_buf.len(),
+                    bytes_written: 0,
+                })
+            }
+            #[cfg(feature = "aes")]
+            Mode::Decrypting(decryption) => {
+                // don't let decrypted data pile up if the output is the bottleneck
+                let bytes_read = cmp::min(
+                    in_buf.len(),
+                    crypto::MAX_PENDING_PLAINTEXT.saturating_sub(decryption.pending()),
+                );
+                self.consume(&in_buf[..bytes_read]);
+
+                let has_more_input = if self.consumed >= self.data_end()
+                    && matches!(has_more_input, HasMoreInput::No)
+                {
+                    HasMoreInput::No
+                } else {
+                    HasMoreInput::Yes
+                };
+
+                let Mode::Decrypting(decryption) = &mut self.mode else {
+                    unreachable!("consume doesn't change the mode once decrypting")
+                };
+                let bytes_written = decryption.decompress(out_buf, has_more_input)?;
 
-        if in_buf.len() < salt_size + 2 {
-            return Ok(DecompressOutcome {
-                bytes_read: in_buf.len(),
-                bytes_written: 0,
-            });
+                Ok(DecompressOutcome {
+                    bytes_read,
+                    bytes_written,
+                })
+            }
         }
+    }
+}
 
-        let rest = if self.salt_value.is_none() {
-            // the first few bytes contain the salt and password verification value
-            let (salt_value, rest) = in_buf.split_at(salt_size);
-            let (password_verification_value, rest) = rest.split_at(PASSWORD_VERIFICATION_SIZE);
-            self.salt_value = Some(salt_value.to_vec());
-            self.password_verification_value = Some(password_verification_value.to_vec());
-            rest
-        } else {
-            in_buf
-        };
+#[cfg(feature = "aes")]
+mod crypto {
+    use alloc::{boxed::Box, vec, vec::Vec};
+
+    use aes::cipher::{KeyIvInit, StreamCipher};
+    use hmac::{Hmac, Mac};
+    use sha1::Sha1;
+
+    use crate::error::Error;
+
+    use super::super::{AnyDecompressor, Decompressor, HasMoreInput};
+
+    /// Iteration count used by WinZip for PBKDF2
+    const KEY_DERIVATION_ITERATIONS: u32 = 1000;
+
+    /// How much decrypted data we hold on to while the inner decompressor catches up
+    pub(super) const MAX_PENDING_PLAINTEXT: usize = 64 * 1024;
+
+    enum Cipher {
+        Aes128(ctr::Ctr128LE<aes::Aes128>),
+        Aes192(ctr::Ctr128LE<aes::Aes192>),
+        Aes256(ctr::Ctr128LE<aes::Aes256>),
+    }
+
+    impl Cipher {
+        fn new(key: &[u8]) -> Self {
+            // WinZip uses a little-endian counter that starts at 1
+            let iv = 1u128.to_le_bytes();
+            match key.len() {
+                16 => Self::Aes128(ctr::Ctr128LE::new(key.into(), &iv.into())),
+                24 => Self::Aes192(ctr::Ctr128LE::new(key.into(), &iv.into())),
+                _ => Self::Aes256(ctr::Ctr128LE::new(key.into(), &iv.into())),
+            }
+        }
 
-        if matches!(has_more_input, HasMoreInput::No) {
-            // the last few bytes contain the authentication code
-            let (_rest, authentication_code) = rest.split_at(rest.len() - AUTHENTICATION_CODE_SIZE);
-            self.authentication_code = Some(authentication_code.to_vec());
+        fn apply_keystream(&mut self, buf: &mut [u8]) {
+            match self {
+                Self::Aes128(c) => c.apply_keystream(buf),
+                Self::Aes192(c) => c.apply_keystream(buf),
+                Self::Aes256(c) => c.apply_keystream(buf),
+            }
         }
+    }
 
-        // copy the data to the output buffer to simulate decompression progress
-        // we can't actually decrypt the data because we do not know the password
-        let bytes_read = cmp::min(in_buf.len(), out_buf.len());
-        out_buf[..bytes_read].copy_from_slice(&in_buf[..bytes_read]);
+    /// Derives the encryption key, authentication key and password
+    /// verification value from `password` and `salt`, in that order
+    fn derive(password: &[u8], salt: &[u8]) -> Vec<u8> {
+        // the salt is half the key size
+        let key_size = salt.len() * 2;
+        let mut derived = vec![0u8; key_size * 2 + super::PASSWORD_VERIFICATION_SIZE];
+        pbkdf2::pbkdf2_hmac::<Sha1>(password, salt, KEY_DERIVATION_ITERATIONS, &mut derived);
+        derived
+    }
 
-        Ok(DecompressOutcome {
-            bytes_read,
-            bytes_written: bytes_read,
-        })
+    /// Whether `password` matches the password verification value
+    pub(super) fn password_matches(password: &[u8], salt: &[u8], verification: &[u8]) -> bool {
+        derive(password, salt)[salt.len() * 4..] == *verification
+    }
+
+    pub(super) struct Decryption {
+        cipher: Cipher,
+        mac: Hmac<Sha1>,
+        inner: Box<AnyDecompressor>,
+        /// Decrypted data the inner decompressor hasn't consumed yet
+        plaintext: Vec<u8>,
+    }
+
+    impl Decryption {
+        /// Derives the keys from `password` and `salt`, returning `None` if
+        /// the password doesn't match the password verification value.
+        pub(super) fn new(
+            password: &[u8],
+            salt: &[u8],
+            verification: &[u8],
+            inner: Box<AnyDecompressor>,
+        ) -> Option<Self> {
+            let key_size = salt.len() * 2;
+            let derived = derive(password, salt);
+            let (encryption_key, rest) = derived.split_at(key_size);
+            let (authentication_key, derived_verification) = rest.split_at(key_size);
+            if derived_verification != verification {
+                return None;
+            }
+
+            Some(Self {
+                cipher: Cipher::new(encryption_key),
+                mac: Hmac::<Sha1>::new_from_slice(authentication_key).ok()?,
+                inner,
+                plaintext: Vec::new(),
+            })
+        }
+
+        pub(super) fn pending(&self) -> usize {
+            self.plaintext.len()
+        }
+
+        pub(super) fn decrypt(&mut self, ciphertext: &[u8]) {
+            self.mac.update(ciphertext);
+            let start = self.plaintext.len();
+            self.plaintext.extend_from_slice(ciphertext);
+            self.cipher.apply_keystream(&mut self.plaintext[start..]);
+        }
+
+        /// Feeds pending plaintext to the inner decompressor, returning the
+        /// amount of bytes written to `out`.
+        pub(super) fn decompress(
+            &mut self,
+            out: &mut [u8],
+            has_more_input: HasMoreInput,
+        ) -> Result<usize, Error> {
+            let outcome = self
+                .inner
+                .decompress(&self.plaintext, out, has_more_input)?;
+            self.plaintext.drain(..outcome.bytes_read);
+            Ok(outcome.bytes_written)
+        }
+
+        /// Whether `authentication_code` matches the encrypted data seen so far
+        pub(super) fn verify(&self, authentication_code: &[u8]) -> bool {
+            let code = self.mac.clone().finalize().into_bytes();
+            code[..authentication_code.len()] == *authentication_code
+        }
     }
 }
@@ -1,4 +1,5 @@
-use std::cmp;
+use alloc::{string::ToString, vec, vec::Vec};
+use core::cmp;
 
 use miniz_oxide::inflate::{
     core::{
@@ -0,0 +1,302 @@
+use alloc::{boxed::Box, format, string::ToString, vec::Vec};
+use core::cmp;
+
+use crate::{error::Error, parse::Method};
+
+use super::{DecompressOutcome, Decompressor, HasMoreInput};
+
+use tracing::trace;
+
+/// General purpose bit flag for an 8K sliding dictionary rather than a 4K one
+const FLAG_LARGE_WINDOW: u16 = 1 << 1;
+
+/// General purpose bit flag for literals coded with a Shannon-Fano tree
+/// rather than stored as raw bytes, which also raises the minimum match
+/// length from 2 to 3
+const FLAG_LITERAL_TREE: u16 = 1 << 2;
+
+/// Size of the largest sliding dictionary, which is also the largest
+/// distance a match can have
+const WINDOW_SIZE: usize = 8192;
+
+/// Length symbol after which an extra byte of length follows
+const MAX_LENGTH_SYMBOL: u16 = 63;
+
+/// Longest code in a Shannon-Fano tree
+const MAX_CODE_LENGTH: usize = 16;
+
+/// A Shannon-Fano tree, as the number of codes of each length and the
+/// symbols in code order, to decode canonically like puff does
+struct Tree {
+    counts: [u16; MAX_CODE_LENGTH + 1],
+    symbols: Vec<u16>,
+}
+
+/// Reads the bits of the buffered input, least significant bit first
+struct BitReader {
+    input: Vec<u8>,
+    position: usize,
+}
+
+impl BitReader {
+    fn bits_left(&self) -> usize {
+        self.input.len() * 8 - self.position
+    }
+
+    fn bit(&mut self) -> Result<u16, Error> {
+        let byte = self
+            .input
+            .get(self.position / 8)
+            .ok_or_else(|| dec_err("unexpected end of data"))?;
+        let bit = (byte >> (self.position % 8)) & 1;
+        self.position += 1;
+        Ok(bit as u16)
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u16, Error> {
+        let mut value = 0;
+        for i in 0..count {
+            value |= self.bit()? << i;
+        }
+        Ok(value)
+    }
+
+    /// Reads a tree of `symbol_count` symbols: a byte with the number of
+    /// bytes that follow minus one, each of which has a code length minus
+    /// one in its low 4 bits, and how many symbols in a row have that
+    /// length minus one in its high 4 bits
+    fn tree(&mut self, symbol_count: usize) -> Result<Tree, Error> {
+        let mut lengths = Vec::with_capacity(symbol_count);
+        for _ in 0..=self.bits(8)? {
+            let byte = self.bits(8)?;
+            let length = (byte & 0xf) as u8 + 1;
+            let run = (byte >> 4) as usize + 1;
+            lengths.extend(core::iter::repeat_n(length, run));
+        }
+        if lengths.len() != symbol_count {
+            return Err(dec_err(format!(
+                "tree has {} code lengths instead of {symbol_count}",
+                lengths.len()
+            )));
+        }
+
+        let mut counts = [0u16; MAX_CODE_LENGTH + 1];
+        for length in &lengths {
+            counts[*length as usize] += 1;
+        }
+        // the codes must use up the whole code space, no more and no less
+        let mut left = 1i32;
+        for count in &counts[1..] {
+            left = left * 2 - *count as i32;
+            if left < 0 {
+                return Err(dec_err("tree is oversubscribed"));
+            }
+        }
+        if left != 0 {
+            return Err(dec_err("tree is incomplete"));
+        }
+
+        let mut symbols: Vec<u16> = (0..symbol_count as u16).collect();
+        symbols.sort_by_key(|symbol| lengths[*symbol as usize]);
+        Ok(Tree { counts, symbols })
+    }
+
+    /// Decodes a symbol with `tree`. Codes are stored most significant bit
+    /// first, with every bit inverted.
+    fn symbol(&mut self, tree: &Tree) -> Result<u16, Error> {
+        let mut code = 0u32;
+        let mut first = 0u32;
+        let mut index = 0u32;
+        for count in tree.counts[1..].iter().map(|count| *count as u32) {
+            code |= (self.bit()? ^ 1) as u32;
+            if code < first + count {
+                return Ok(tree.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(dec_err("invalid code"))
+    }
+}
+
+#[derive(Default)]
+enum State {
+    /// The input is buffered until it's all there, so that decoding never
+    /// runs out of bits in the middle of a symbol
+    Buffering(Vec<u8>),
+    Decoding(Box<Exploder>),
+
+    #[default]
+    Transition,
+}
+
+/// Decodes imploded data, once all of it is buffered
+struct Exploder {
+    reader: BitReader,
+    literals: Option<Tree>,
+    lengths: Tree,
+    distances: Tree,
+    large_window: bool,
+    min_length: usize,
+
+    /// The last [WINDOW_SIZE] bytes of output, starting out as zeros: some
+    /// implementations refer to bytes before the start of the output
+    window: Box<[u8; WINDOW_SIZE]>,
+    written: usize,
+
+    /// Distance and remaining length of the match being copied
+    pending: Option<(usize, usize)>,
+}
+
+impl Exploder {
+    fn new(input: Vec<u8>, flags: u16) -> Result<Self, Error> {
+        let literal_tree = flags & FLAG_LITERAL_TREE != 0;
+        let mut reader = BitReader { input, position: 0 };
+        let literals = match literal_tree {
+            true => Some(reader.tree(256)?),
+            false => None,
+        };
+        let lengths = reader.tree(64)?;
+        let distances = reader.tree(64)?;
+        Ok(Self {
+            reader,
+            literals,
+            lengths,
+            distances,
+            large_window: flags & FLAG_LARGE_WINDOW != 0,
+            min_length: if literal_tree { 3 } else { 2 },
+            window: Box::new([0; WINDOW_SIZE]),
+            written: 0,
+            pending: None,
+        })
+    }
+
+    fn push(&mut self, byte: u8, out: &mut [u8], outcome: &mut DecompressOutcome) {
+        self.window[self.written % WINDOW_SIZE] = byte;
+        self.written += 1;
+        out[outcome.bytes_written] = byte;
+        outcome.bytes_written += 1;
+    }
+
+    /// Decodes into `out` until it's full, or until `at_end` says so
+    /// between two symbols
+    fn decode(
+        &mut self,
+        out: &mut [u8],
+        outcome: &mut DecompressOutcome,
+        at_end: impl Fn(&Self) -> bool,
+    ) -> Result<(), Error> {
+        while outcome.bytes_written < out.len() {
+            if let Some((distance, length)) = self.pending {
+                let count = cmp::min(length, out.len() - outcome.bytes_written);
+                for _ in 0..count {
+                    let byte = self.window[(self.written + WINDOW_SIZE - distance) % WINDOW_SIZE];
+                    self.push(byte, out, outcome);
+                }
+                self.pending = (count < length).then_some((distance, length - count));
+                continue;
+            }
+
+            if at_end(self) {
+                break;
+            }
+
+            if self.reader.bit()? == 1 {
+                let byte = match &self.literals {
+                    Some(tree) => self.reader.symbol(tree)?,
+                    None => self.reader.bits(8)?,
+                };
+                self.push(byte as u8, out, outcome);
+                continue;
+            }
+
+            let low_bits = if self.large_window { 7 } else { 6 };
+            let low = self.reader.bits(low_bits)? as usize;
+            let high = self.reader.symbol(&self.distances)? as usize;
+            let distance = (high << low_bits | low) + 1;
+
+            let symbol = self.reader.symbol(&self.lengths)?;
+            let mut length = symbol as usize + self.min_length;
+            if symbol == MAX_LENGTH_SYMBOL {
+                length += self.reader.bits(8)? as usize;
+            }
+            self.pending = Some((distance, length));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct ImplodeDec {
+    state: State,
+    flags: u16,
+
+    /// How many more bytes to produce, if known. There's no end marker, so
+    /// without it decoding stops when the data runs out.
+    remaining: Option<u64>,
+}
+
+impl ImplodeDec {
+    pub fn new(flags: u16, uncompressed_size: Option<u64>) -> Self {
+        Self {
+            state: State::Buffering(Vec::new()),
+            flags,
+            remaining: uncompressed_size,
+        }
+    }
+}
+
+impl Decompressor for ImplodeDec {
+    fn decompress(
+        &mut self,
+        in_buf: &[u8],
+        out: &mut [u8],
+        has_more_input: HasMoreInput,
+    ) -> Result<DecompressOutcome, Error> {
+        let mut outcome: DecompressOutcome = Default::default();
+
+        if let State::Buffering(input) = &mut self.state {
+            input.extend_from_slice(in_buf);
+            outcome.bytes_read = in_buf.len();
+            trace!(
+                buffered = input.len(),
+                ?has_more_input,
+                "buffering imploded input"
+            );
+            if let HasMoreInput::Yes = has_more_input {
+                return Ok(outcome);
+            }
+
+            let State::Buffering(input) = core::mem::take(&mut self.state) else {
+                unreachable!()
+            };
+            self.state = State::Decoding(Box::new(Exploder::new(input, self.flags)?));
+        }
+
+        let State::Decoding(exploder) = &mut self.state else {
+            unreachable!()
+        };
+        let room = match self.remaining {
+            Some(remaining) => cmp::min(out.len() as u64, remaining) as usize,
+            None => out.len(),
+        };
+        // without a size, stop when less than a byte is left: the last byte
+        // is padded with up to 7 bits
+        let known_size = self.remaining.is_some();
+        exploder.decode(&mut out[..room], &mut outcome, |exploder| {
+            !known_size && exploder.reader.bits_left() < 8
+        })?;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= outcome.bytes_written as u64;
+        }
+        trace!("decompressor gave us {} bytes", outcome.bytes_written);
+        Ok(outcome)
+    }
+}
+
+fn dec_err(e: impl core::fmt::Display) -> Error {
+    Error::Decompression {
+        method: Method::Implode,
+        msg: e.to_string(),
+    }
+}
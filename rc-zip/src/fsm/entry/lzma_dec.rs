@@ -89,22 +89,23 @@ impl Decompressor for LzmaDec {
                         HasMoreInput::No => {
                             trace!("no more input to come");
 
-                            // this happens when we hit the 10-byte trailer mentioned above
+                            // the decoder stops once it has produced the uncompressed size,
+                            // before the 6-byte end of stream marker, if there is one.
                             // in this case, we just pretend we wrote everything
                             match in_buf.len() {
                                 0 => {
                                     // trailer is not present, that's okay
                                 }
-                                10 => {
+                                6 => {
                                     trace!("eating LZMA trailer");
-                                    outcome.bytes_read += 10;
+                                    outcome.bytes_read += 6;
                                 }
                                 _ => {
                                     return Err(Error::Decompression { method: Method::Lzma, msg: format!("expected LZMA trailer or no LZMA trailer, but not a {}-byte trailer", in_buf.len()) });
                                 }
                             }
 
-                            match std::mem::take(&mut self.state) {
+                            match core::mem::take(&mut self.state) {
                                 State::Writing(stream) => {
                                     trace!("finishing...");
                                     self.state = State::Draining(stream.finish().map_err(dec_err)?);
@@ -129,7 +130,7 @@ impl Decompressor for LzmaDec {
     }
 }
 
-fn dec_err(e: impl std::fmt::Display) -> Error {
+fn dec_err(e: impl core::fmt::Display) -> Error {
     Error::Decompression {
         method: Method::Lzma,
         msg: e.to_string(),
@@ -1,6 +1,8 @@
-use std::{cmp, rc::Rc, sync::Mutex};
+#[cfg(any(feature = "deflate", feature = "implode"))]
+use alloc::boxed::Box;
+use alloc::{rc::Rc, vec, vec::Vec};
+use core::{cell::RefCell, cmp};
 
-use oval::Buffer;
 use tracing::trace;
 use winnow::{
     error::ErrMode,
@@ -28,16 +30,85 @@ pub use aex_dec::AexData;
 #[cfg(feature = "zstd")]
 mod zstd_dec;
 
+#[cfg(feature = "ppmd")]
+mod ppmd_dec;
+
+#[cfg(feature = "implode")]
+mod implode_dec;
+
 use crate::{
     error::{Error, FormatError, UnsupportedError},
-    parse::{DataDescriptorRecord, Entry, LocalFileHeader, Method},
+    parse::{DataDescriptorRecord, Entry, LocalFileHeader, Method, MethodSpecific},
 };
 
-use super::{FsmResult, ParsedRanges};
+use super::{Buffer, FsmResult, ParsedRanges};
+
+/// What was actually measured while reading an entry's data, as opposed to
+/// what its headers declare.
+#[derive(Debug, Clone, Copy)]
+pub struct EntryReadMetrics {
+    /// Amount of bytes fed to the decompressor
+    pub compressed_size: u64,
+
+    /// Amount of bytes the decompressor produced
+    pub uncompressed_size: u64,
+
+    /// CRC32 hash of the decompressed data
+    pub crc32: u32,
+
+    /// Whether the data descriptor had the other width than the local header
+    /// implies (16 bytes instead of 24 for zip64, or the other way around).
+    /// The declared width was tried first, but it didn't match the measured
+    /// sizes and wasn't followed by another record, whereas the other width was.
+    pub data_descriptor_size_ambiguous: bool,
+
+    /// Whether the CRC-32 and sizes in the data descriptor match the measured
+    /// ones, or `None` if there's no data descriptor or the data couldn't be
+    /// decrypted to check it against
+    pub descriptor_matches_data: Option<bool>,
+}
+
+/// How far [EntryFsm] got through an entry's data, to pause a long
+/// verification and pick it up later, see [EntryFsm::checkpoint] and
+/// [EntryFsm::resume_from].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrcCheckpoint {
+    /// Amount of bytes fed to the decompressor so far
+    pub compressed_bytes: u64,
+
+    /// Amount of bytes the decompressor produced so far
+    pub uncompressed_bytes: u64,
+
+    /// CRC32 hash of the bytes produced so far
+    pub crc32: u32,
+}
+
+/// Signatures of the records that can follow a data descriptor: the next
+/// local file header, or the start of the central directory or of the end of
+/// central directory records.
+const NEXT_RECORD_SIGNATURES: [&[u8; 4]; 4] =
+    [b"PK\x03\x04", b"PK\x01\x02", b"PK\x06\x06", b"PK\x05\x06"];
+
+/// Parses a data descriptor of the given width, returning it with the number
+/// of bytes it takes up.
+fn parse_data_descriptor(
+    data: &[u8],
+    is_zip64: bool,
+) -> winnow::PResult<(DataDescriptorRecord, usize)> {
+    let mut input = Partial::new(data);
+    let descriptor = DataDescriptorRecord::mk_parser(is_zip64).parse_next(&mut input)?;
+    Ok((descriptor, input.as_bytes().offset_from(&data)))
+}
 
-struct EntryReadMetrics {
-    uncompressed_size: u64,
-    crc32: u32,
+/// Whether one of [NEXT_RECORD_SIGNATURES] starts at `offset`, or `None` if
+/// more data is needed to tell.
+fn followed_by_record(data: &[u8], offset: usize) -> Option<bool> {
+    let next = data.get(offset..offset + 4)?;
+    Some(
+        NEXT_RECORD_SIGNATURES
+            .iter()
+            .any(|signature| next == *signature),
+    )
 }
 
 #[derive(Default)]
@@ -96,8 +167,9 @@ pub struct EntryFsm {
     entry: Option<Entry>,
     local_header: Option<LocalFileHeader<'static>>,
     buffer: Buffer,
-    parsed_ranges: Option<Rc<Mutex<ParsedRanges>>>,
+    parsed_ranges: Option<Rc<RefCell<ParsedRanges>>>,
     aex_data: Option<AexData>,
+    password: Option<Vec<u8>>,
 }
 
 impl EntryFsm {
@@ -105,7 +177,7 @@ impl EntryFsm {
     pub fn new(
         entry: Option<Entry>,
         buffer: Option<Buffer>,
-        parsed_ranges: Option<Rc<Mutex<ParsedRanges>>>,
+        parsed_ranges: Option<Rc<RefCell<ParsedRanges>>>,
     ) -> Self {
         const BUF_CAPACITY: usize = 256 * 1024;
 
@@ -114,17 +186,30 @@ impl EntryFsm {
             entry,
             local_header: None,
             buffer: match buffer {
-                Some(buffer) => {
-                    assert!(buffer.capacity() >= BUF_CAPACITY, "buffer too small");
+                Some(mut buffer) => {
+                    // keep any data left over from a previous entry
+                    if buffer.capacity() < BUF_CAPACITY {
+                        buffer.grow(BUF_CAPACITY);
+                    }
                     buffer
                 }
                 None => Buffer::with_capacity(BUF_CAPACITY),
             },
             parsed_ranges,
             aex_data: None,
+            password: None,
         }
     }
 
+    /// Sets the password used to decrypt AE-x encrypted entries.
+    ///
+    /// Only has an effect with the `aes` feature: without it, or without a
+    /// password, the encrypted data is passed through as-is.
+    pub fn with_password(mut self, password: &[u8]) -> Self {
+        self.password = Some(password.to_vec());
+        self
+    }
+
     /// If this returns true, the caller should read data from into
     /// [Self::space] — without forgetting to call [Self::fill] with the number
     /// of bytes written.
@@ -141,6 +226,75 @@ impl EntryFsm {
         }
     }
 
+    /// Where reading the entry's data is at, or `None` outside of the data:
+    /// before the local header has been parsed, or once all the data has
+    /// been decompressed.
+    ///
+    /// Data still in the buffer isn't accounted for: it is read again after
+    /// resuming.
+    pub fn checkpoint(&self) -> Option<CrcCheckpoint> {
+        match &self.state {
+            State::ReadData {
+                compressed_bytes,
+                uncompressed_bytes,
+                hasher,
+                ..
+            } => Some(CrcCheckpoint {
+                compressed_bytes: *compressed_bytes,
+                uncompressed_bytes: *uncompressed_bytes,
+                crc32: hasher.clone().finalize(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Picks up reading the entry's data where `checkpoint` was taken, e.g.
+    /// by another state machine for the same entry in an earlier run. The
+    /// local header must have been parsed already, see
+    /// [Self::local_header_entry].
+    ///
+    /// Returns the offset in the file at which the caller should continue
+    /// reading, anything buffered so far is discarded.
+    ///
+    /// Only stored entries can be resumed: the state of decompressors can't
+    /// be restored, so other entries have to be read from the start.
+    pub fn resume_from(&mut self, checkpoint: CrcCheckpoint) -> Result<u64, Error> {
+        let entry = self.entry.as_ref();
+        let State::ReadData {
+            compressed_bytes,
+            uncompressed_bytes,
+            hasher,
+            decompressor,
+            data_start,
+            ..
+        } = &mut self.state
+        else {
+            return Err(Error::InvalidInput(
+                "can only resume after the local header has been read",
+            ));
+        };
+        let method = entry.map_or(Method::Store, |entry| entry.method);
+        if !matches!(decompressor, AnyDecompressor::Store(_)) {
+            return Err(Error::Unsupported(UnsupportedError::ResumeNotSupported(
+                method,
+            )));
+        }
+        if entry.is_some_and(|entry| checkpoint.compressed_bytes > entry.compressed_size)
+            || checkpoint.compressed_bytes != checkpoint.uncompressed_bytes
+        {
+            return Err(Error::InvalidInput("checkpoint doesn't fit the entry"));
+        }
+
+        *compressed_bytes = checkpoint.compressed_bytes;
+        *uncompressed_bytes = checkpoint.uncompressed_bytes;
+        *hasher = crc32fast::Hasher::new_with_initial_len(
+            checkpoint.crc32,
+            checkpoint.uncompressed_bytes,
+        );
+        self.buffer.reset();
+        Ok(*data_start + checkpoint.compressed_bytes)
+    }
+
     /// Like `process`, but only processes the header. If this returns
     /// `Ok(None)`, the caller should read more data and call this function
     /// again.
@@ -169,8 +323,6 @@ impl EntryFsm {
             Ok(header) => {
                 let consumed = input.as_bytes().offset_from(&self.buffer.data());
                 tracing::trace!(local_file_header = ?header, consumed, "parsed local file header");
-                let decompressor = AnyDecompressor::new(header.method, self.entry.as_ref())?;
-
                 if self.entry.is_none() {
                     self.entry = Some(header.as_entry()?);
                 }
@@ -180,30 +332,62 @@ impl EntryFsm {
                     .as_ref()
                     .expect("should always be Some at this point");
                 let start = entry.header_offset;
-                let length = consumed as u64;
+                // the LZMA header is parsed along with the local header, but
+                // it's part of the compressed data and counts towards its size
+                let lzma_header_length = match header.method_specific {
+                    MethodSpecific::Lzma(_) => LZMA_HEADER_LENGTH as u64,
+                    _ => 0,
+                };
+                let length = consumed as u64 - lzma_header_length;
+
+                if let Some(parsed_ranges) = &mut self.parsed_ranges {
+                    if let Some(mut parsed_ranges) = ParsedRanges::lock(parsed_ranges) {
+                        parsed_ranges.insert_offset_length(
+                            start,
+                            length,
+                            "local file header",
+                            Some(entry.name.clone()),
+                        );
+                    }
+                }
+
+                // the header is kept even if there's no decompressor for its
+                // method, see [Self::local_header_entry]
+                self.local_header = Some(header.to_owned());
+
+                let decompressor = match AnyDecompressor::new(
+                    header.method,
+                    header.flags,
+                    Some(entry),
+                    self.password.as_deref(),
+                ) {
+                    Ok(decompressor) => decompressor,
+                    Err(error) => {
+                        // the data can't be read, but its declared extent is known
+                        if let Some(parsed_ranges) = &mut self.parsed_ranges {
+                            if let Some(mut parsed_ranges) = ParsedRanges::lock(parsed_ranges) {
+                                parsed_ranges.insert_offset_length(
+                                    start + length,
+                                    entry.compressed_size,
+                                    "file data",
+                                    Some(entry.name.clone()),
+                                );
+                            }
+                        }
+                        return Err(error);
+                    }
+                };
 
                 self.state = State::ReadData {
-                    is_zip64: header.compressed_size == u32::MAX
-                        || header.uncompressed_size == u32::MAX,
+                    is_zip64: header.has_zip64_sizes(),
                     has_data_descriptor: header.has_data_descriptor(),
-                    compressed_bytes: 0,
+                    compressed_bytes: lzma_header_length,
                     uncompressed_bytes: 0,
                     hasher: crc32fast::Hasher::new(),
                     decompressor,
                     data_start: start + length,
                 };
 
-                if let Some(parsed_ranges) = &mut self.parsed_ranges {
-                    parsed_ranges.try_lock().unwrap().insert_offset_length(
-                        start,
-                        length,
-                        "local file header",
-                        Some(entry.name.clone()),
-                    );
-                }
-
-                self.local_header = Some(header.to_owned());
-
                 self.buffer.consume(consumed);
                 Ok(true)
             }
@@ -228,7 +412,12 @@ impl EntryFsm {
     ) -> Result<
         FsmResult<
             (Self, DecompressOutcome),
-            (Buffer, Option<LocalFileHeader<'a>>, Option<AexData>),
+            (
+                Buffer,
+                Option<LocalFileHeader<'a>>,
+                Option<AexData>,
+                EntryReadMetrics,
+            ),
         >,
         Error,
     > {
@@ -316,11 +505,13 @@ impl EntryFsm {
 
                         let file_data_end = *data_start + *compressed_bytes;
                         if let Some(parsed_ranges) = &mut self.parsed_ranges {
-                            parsed_ranges.try_lock().unwrap().insert_range(
-                                *data_start..file_data_end,
-                                "file data",
-                                Some(entry.name.clone()),
-                            );
+                            if let Some(mut parsed_ranges) = ParsedRanges::lock(parsed_ranges) {
+                                parsed_ranges.insert_range(
+                                    *data_start..file_data_end,
+                                    "file data",
+                                    Some(entry.name.clone()),
+                                );
+                            }
                         }
 
                         if let AnyDecompressor::Aex(aex_dec) = decompressor {
@@ -328,10 +519,13 @@ impl EntryFsm {
                         }
 
                         // we're done, let's read the data descriptor (if there's one)
-                        transition!(self.state => (S::ReadData {  has_data_descriptor, is_zip64, uncompressed_bytes, hasher, .. }) {
+                        transition!(self.state => (S::ReadData {  has_data_descriptor, is_zip64, compressed_bytes, uncompressed_bytes, hasher, .. }) {
                             let metrics = EntryReadMetrics {
+                                compressed_size: compressed_bytes,
                                 uncompressed_size: uncompressed_bytes,
                                 crc32: hasher.finalize(),
+                                data_descriptor_size_ambiguous: false,
+                                descriptor_matches_data: None,
                             };
 
                             if has_data_descriptor {
@@ -345,10 +539,9 @@ impl EntryFsm {
                         return self.process(out);
                     } else if outcome.bytes_written == 0 && outcome.bytes_read == 0 {
                         if bytes_fed_this_turn == 0 {
-                            return Err(Error::IO(std::io::Error::new(
-                                std::io::ErrorKind::UnexpectedEof,
+                            return Err(Error::UnexpectedEof(
                                 "decompressor made no progress: this is probably an rc-zip bug",
-                            )));
+                            ));
                         } else {
                             // ok fine, continue
                         }
@@ -369,36 +562,78 @@ impl EntryFsm {
                 }
                 S::ReadDataDescriptor {
                     is_zip64,
+                    metrics,
                     data_descriptor_start,
-                    ..
                 } => {
-                    let mut input = Partial::new(self.buffer.data());
-
-                    match DataDescriptorRecord::mk_parser(*is_zip64).parse_next(&mut input) {
-                        Ok(descriptor) => {
-                            let consumed = input.as_bytes().offset_from(&self.buffer.data());
-                            self.buffer.consume(consumed);
-                            trace!("data descriptor = {:#?}", descriptor);
-
-                            if let Some(parsed_ranges) = &mut self.parsed_ranges {
-                                parsed_ranges.try_lock().unwrap().insert_offset_length(
-                                    *data_descriptor_start,
-                                    consumed as u64,
-                                    "data descriptor",
-                                    Some(self.entry.as_ref().unwrap().name.clone()),
-                                );
-                            }
+                    let data = self.buffer.data();
+                    let (descriptor, consumed) = match parse_data_descriptor(data, *is_zip64) {
+                        Ok(parsed) => parsed,
+                        Err(ErrMode::Incomplete(_)) => {
+                            return Ok(FsmResult::Continue((self, Default::default())));
+                        }
+                        Err(_e) => return Err(Error::Format(FormatError::InvalidDataDescriptor)),
+                    };
 
-                            transition!(self.state => (S::ReadDataDescriptor { metrics, .. }) {
-                                S::Validate { metrics, descriptor: Some(descriptor) }
-                            });
-                            self.process(out)
+                    // Writers don't agree on when the sizes are 8 bytes wide, so if the
+                    // descriptor doesn't add up, see whether the other width fits better:
+                    // only a descriptor of the right width is followed by the next record.
+                    let matches_metrics = descriptor.compressed_size == metrics.compressed_size
+                        && descriptor.uncompressed_size == metrics.uncompressed_size;
+                    let mut ambiguous = false;
+                    let (descriptor, consumed) = if matches_metrics {
+                        (descriptor, consumed)
+                    } else {
+                        let Some(followed) = followed_by_record(data, consumed) else {
+                            return Ok(FsmResult::Continue((self, Default::default())));
+                        };
+                        if followed {
+                            (descriptor, consumed)
+                        } else {
+                            match parse_data_descriptor(data, !*is_zip64) {
+                                Ok((alternate, alternate_consumed)) => {
+                                    match followed_by_record(data, alternate_consumed) {
+                                        None => {
+                                            return Ok(FsmResult::Continue((
+                                                self,
+                                                Default::default(),
+                                            )));
+                                        }
+                                        Some(true) => {
+                                            ambiguous = true;
+                                            (alternate, alternate_consumed)
+                                        }
+                                        Some(false) => (descriptor, consumed),
+                                    }
+                                }
+                                Err(ErrMode::Incomplete(_)) => {
+                                    return Ok(FsmResult::Continue((self, Default::default())));
+                                }
+                                Err(_e) => (descriptor, consumed),
+                            }
                         }
-                        Err(ErrMode::Incomplete(_)) => {
-                            Ok(FsmResult::Continue((self, Default::default())))
+                    };
+
+                    self.buffer.consume(consumed);
+                    trace!(ambiguous, "data descriptor = {:#?}", descriptor);
+
+                    if let Some(parsed_ranges) = &mut self.parsed_ranges {
+                        if let Some(mut parsed_ranges) = ParsedRanges::lock(parsed_ranges) {
+                            parsed_ranges.insert_offset_length(
+                                *data_descriptor_start,
+                                consumed as u64,
+                                "data descriptor",
+                                Some(self.entry.as_ref().unwrap().name.clone()),
+                            );
                         }
-                        Err(_e) => Err(Error::Format(FormatError::InvalidDataDescriptor)),
                     }
+
+                    transition!(self.state => (S::ReadDataDescriptor { metrics, .. }) {
+                        S::Validate {
+                            metrics: EntryReadMetrics { data_descriptor_size_ambiguous: ambiguous, ..metrics },
+                            descriptor: Some(descriptor),
+                        }
+                    });
+                    self.process(out)
                 }
                 S::Validate {
                     metrics,
@@ -414,18 +649,36 @@ impl EntryFsm {
                         0
                     };
 
-                    // When AE-X encryption is used, we can't access the file's data
+                    // When AE-X encryption is used, we can't access the file's data without the password
                     // Since the data is compressed before it is encrypted, the file size of the encrypted data won't match in size
-                    // so we skip this validation check
-                    if entry.uncompressed_size != metrics.uncompressed_size && entry.aex.is_none() {
+                    // so we skip these validation checks unless we could decrypt it
+                    let decrypted = self
+                        .aex_data
+                        .as_ref()
+                        .is_some_and(|aex_data| aex_data.password_correct == Some(true));
+                    let opaque = entry.aex.is_some() && !decrypted;
+
+                    // the descriptor is only needed for the CRC-32 if the headers don't
+                    // have it, but a descriptor that disagrees with the data misleads
+                    // streaming readers, which have nothing else to go by
+                    metrics.descriptor_matches_data =
+                        descriptor.as_ref().filter(|_| !opaque).map(|descriptor| {
+                            // AE-2 leaves the CRC-32 out
+                            let crc32_matches = descriptor.crc32 == metrics.crc32
+                                || (descriptor.crc32 == 0 && entry.aex.is_some());
+                            crc32_matches
+                                && descriptor.compressed_size == metrics.compressed_size
+                                && descriptor.uncompressed_size == metrics.uncompressed_size
+                        });
+
+                    if entry.uncompressed_size != metrics.uncompressed_size && !opaque {
                         return Err(Error::Format(FormatError::WrongSize {
                             expected: entry.uncompressed_size,
                             actual: metrics.uncompressed_size,
                         }));
                     }
 
-                    if expected_crc32 != 0 && expected_crc32 != metrics.crc32 && entry.aex.is_none()
-                    {
+                    if expected_crc32 != 0 && expected_crc32 != metrics.crc32 && !opaque {
                         return Err(Error::Format(FormatError::WrongChecksum {
                             expected: expected_crc32,
                             actual: metrics.crc32,
@@ -436,6 +689,7 @@ impl EntryFsm {
                         self.buffer,
                         self.local_header,
                         self.aex_data,
+                        *metrics,
                     )))
                 }
                 S::Transition => {
@@ -463,11 +717,52 @@ impl EntryFsm {
         self.buffer.fill(count)
     }
 
+    /// The local file header, once it has been parsed. It's also available
+    /// after [Self::process_till_header] fails because there's no
+    /// decompressor for the entry's method.
     pub fn local_header_entry<'a>(&self) -> &Option<LocalFileHeader<'a>> {
         &self.local_header
     }
 }
 
+/// Length of the header zip puts in front of LZMA data: the LZMA SDK version
+/// and the size of the properties that follow
+const LZMA_HEADER_LENGTH: usize = 4;
+
+/// Decompresses `data` with `method`, without an entry to go with it, e.g. to
+/// find out whether data labeled with one method was compressed with another.
+/// For LZMA, `data` starts with the header zip puts in front of the stream.
+///
+/// Stops after producing `max_output` bytes, so this only fails if the data
+/// is invalid within that much output. Returns the output, and whether all
+/// of `data` was decompressed.
+pub fn trial_decompress(
+    method: Method,
+    data: &[u8],
+    max_output: usize,
+) -> Result<(Vec<u8>, bool), Error> {
+    let data = match method {
+        Method::Lzma => data.get(LZMA_HEADER_LENGTH..).unwrap_or_default(),
+        _ => data,
+    };
+    let mut decompressor = AnyDecompressor::new(method, 0, None, None)?;
+    let mut output = Vec::new();
+    let mut chunk = vec![0u8; 64 * 1024];
+    let mut consumed = 0;
+    while output.len() < max_output {
+        let room = chunk.len().min(max_output - output.len());
+        let outcome =
+            decompressor.decompress(&data[consumed..], &mut chunk[..room], HasMoreInput::No)?;
+        if outcome.bytes_read == 0 && outcome.bytes_written == 0 {
+            break;
+        }
+        consumed += outcome.bytes_read;
+        output.extend_from_slice(&chunk[..outcome.bytes_written]);
+    }
+    let complete = consumed == data.len() && output.len() < max_output;
+    Ok((output, complete))
+}
+
 enum AnyDecompressor {
     Store(store_dec::StoreDec),
     #[cfg(feature = "deflate")]
@@ -480,6 +775,10 @@ enum AnyDecompressor {
     Lzma(Box<lzma_dec::LzmaDec>),
     #[cfg(feature = "zstd")]
     Zstd(zstd_dec::ZstdDec),
+    #[cfg(feature = "ppmd")]
+    Ppmd(Box<ppmd_dec::PpmdDec>),
+    #[cfg(feature = "implode")]
+    Implode(Box<implode_dec::ImplodeDec>),
     Aex(aex_dec::AexDec),
 }
 
@@ -510,7 +809,15 @@ trait Decompressor {
 }
 
 impl AnyDecompressor {
-    fn new(method: Method, entry: Option<&Entry>) -> Result<Self, Error> {
+    /// Creates a decompressor for `method`. `flags` are the general purpose
+    /// bit flags of the local header, which some methods take options from.
+    #[cfg_attr(not(feature = "implode"), allow(clippy::only_used_in_recursion))]
+    fn new(
+        method: Method,
+        flags: u16,
+        entry: Option<&Entry>,
+        password: Option<&[u8]>,
+    ) -> Result<Self, Error> {
         let dec = match method {
             Method::Store => Self::Store(Default::default()),
 
@@ -556,9 +863,47 @@ impl AnyDecompressor {
                 return Err(err);
             }
 
+            #[cfg(feature = "ppmd")]
+            Method::Ppmd => Self::Ppmd(Box::new(ppmd_dec::PpmdDec::new(
+                entry.map(|e| e.uncompressed_size),
+            ))),
+            #[cfg(not(feature = "ppmd"))]
+            Method::Ppmd => {
+                let err = Error::Unsupported(UnsupportedError::MethodNotEnabled(method));
+                return Err(err);
+            }
+
+            #[cfg(feature = "implode")]
+            Method::Implode => Self::Implode(Box::new(implode_dec::ImplodeDec::new(
+                flags,
+                entry.map(|e| e.uncompressed_size),
+            ))),
+            #[cfg(not(feature = "implode"))]
+            Method::Implode => {
+                let err = Error::Unsupported(UnsupportedError::MethodNotEnabled(method));
+                return Err(err);
+            }
+
             Method::Aex => match entry {
-                Some(Entry { aex: Some(aex), .. }) => Self::Aex(aex_dec::AexDec::new(*aex)),
-                _ => panic!(),
+                Some(entry @ Entry { aex: Some(aex), .. }) => {
+                    // the data is compressed with the actual method before being encrypted
+                    let decryption = match password {
+                        Some(password) if cfg!(feature = "aes") => {
+                            let actual_method = Method::from(aex.compression_method);
+                            Some((
+                                password,
+                                Self::new(actual_method, flags, Some(entry), None)?,
+                            ))
+                        }
+                        _ => None,
+                    };
+                    Self::Aex(aex_dec::AexDec::new(
+                        *aex,
+                        entry.compressed_size,
+                        decryption,
+                    )?)
+                }
+                _ => return Err(Error::Format(FormatError::MissingAexField)),
             },
 
             _ => {
@@ -591,6 +936,10 @@ impl Decompressor for AnyDecompressor {
             Self::Lzma(dec) => dec.decompress(in_buf, out, has_more_input),
             #[cfg(feature = "zstd")]
             Self::Zstd(dec) => dec.decompress(in_buf, out, has_more_input),
+            #[cfg(feature = "ppmd")]
+            Self::Ppmd(dec) => dec.decompress(in_buf, out, has_more_input),
+            #[cfg(feature = "implode")]
+            Self::Implode(dec) => dec.decompress(in_buf, out, has_more_input),
             Self::Aex(dec) => dec.decompress(in_buf, out, has_more_input),
         }
     }
@@ -0,0 +1,122 @@
+use std::{cmp, io::Read};
+
+use crate::{error::Error, parse::Method};
+
+use super::{DecompressOutcome, Decompressor, HasMoreInput};
+
+use ppmd_rust::{Ppmd8Decoder, RestoreMethod};
+use tracing::trace;
+
+/// Length of the header in front of PPMd data: model order, memory size and
+/// restore method, packed in a 16-bit little-endian integer
+const PPMD_HEADER_LENGTH: usize = 2;
+
+#[derive(Default)]
+enum State {
+    /// The decoder reads its input as it goes, and can't be paused when the
+    /// input runs out in the middle of a symbol: buffer all of it first
+    Buffering(Vec<u8>),
+    Decoding(Box<Ppmd8Decoder<std::io::Cursor<Vec<u8>>>>),
+
+    #[default]
+    Transition,
+}
+
+pub(crate) struct PpmdDec {
+    state: State,
+
+    /// How many more bytes to produce, if known. There's usually no end
+    /// marker, so decoding past the uncompressed size produces garbage.
+    remaining: Option<u64>,
+}
+
+impl PpmdDec {
+    pub fn new(uncompressed_size: Option<u64>) -> Self {
+        Self {
+            state: State::Buffering(Vec::new()),
+            remaining: uncompressed_size,
+        }
+    }
+}
+
+impl Decompressor for PpmdDec {
+    fn decompress(
+        &mut self,
+        in_buf: &[u8],
+        out: &mut [u8],
+        has_more_input: HasMoreInput,
+    ) -> Result<DecompressOutcome, Error> {
+        let mut outcome: DecompressOutcome = Default::default();
+
+        if let State::Buffering(input) = &mut self.state {
+            input.extend_from_slice(in_buf);
+            outcome.bytes_read = in_buf.len();
+            trace!(
+                buffered = input.len(),
+                ?has_more_input,
+                "buffering PPMd input"
+            );
+            if let HasMoreInput::Yes = has_more_input {
+                return Ok(outcome);
+            }
+
+            let State::Buffering(input) = core::mem::take(&mut self.state) else {
+                unreachable!()
+            };
+            self.state = State::Decoding(Box::new(new_decoder(input)?));
+        }
+
+        let State::Decoding(decoder) = &mut self.state else {
+            unreachable!()
+        };
+        let room = match self.remaining {
+            Some(remaining) => cmp::min(out.len() as u64, remaining) as usize,
+            None => out.len(),
+        };
+        let out = &mut out[..room];
+        while outcome.bytes_written < out.len() {
+            let n = decoder
+                .read(&mut out[outcome.bytes_written..])
+                .map_err(dec_err)?;
+            if n == 0 {
+                break;
+            }
+            outcome.bytes_written += n;
+        }
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= outcome.bytes_written as u64;
+        }
+        trace!("decompressor gave us {} bytes", outcome.bytes_written);
+        Ok(outcome)
+    }
+}
+
+/// Reads the PPMd header at the start of `input` and sets up a decoder for
+/// the data that follows it
+fn new_decoder(input: Vec<u8>) -> Result<Ppmd8Decoder<std::io::Cursor<Vec<u8>>>, Error> {
+    let Some(header) = input.get(..PPMD_HEADER_LENGTH) else {
+        return Err(dec_err("data too short for the PPMd header"));
+    };
+    let header = u16::from_le_bytes([header[0], header[1]]) as u32;
+    let order = (header & 0xf) + 1;
+    let mem_size = (((header >> 4) & 0xff) + 1) << 20;
+    let restore_method = RestoreMethod::from(header >> 12);
+    trace!(order, mem_size, ?restore_method, "PPMd header");
+    if restore_method == RestoreMethod::Unsupported {
+        return Err(dec_err(format!(
+            "unsupported restore method {}",
+            header >> 12
+        )));
+    }
+
+    let mut reader = std::io::Cursor::new(input);
+    reader.set_position(PPMD_HEADER_LENGTH as u64);
+    Ppmd8Decoder::new(reader, order, mem_size, restore_method).map_err(dec_err)
+}
+
+fn dec_err(e: impl core::fmt::Display) -> Error {
+    Error::Decompression {
+        method: Method::Ppmd,
+        msg: e.to_string(),
+    }
+}
@@ -96,7 +96,7 @@ impl Decompressor for ZstdDec {
                                 }
                             }
 
-                            match std::mem::take(&mut self.state) {
+                            match core::mem::take(&mut self.state) {
                                 State::Writing(mut stream) => {
                                     trace!("finishing...");
                                     stream.flush().map_err(dec_err)?;
@@ -122,7 +122,7 @@ impl Decompressor for ZstdDec {
     }
 }
 
-fn dec_err(e: impl std::fmt::Display) -> Error {
+fn dec_err(e: impl core::fmt::Display) -> Error {
     Error::Decompression {
         method: Method::Zstd,
         msg: e.to_string(),
@@ -11,7 +11,7 @@
 
 macro_rules! transition {
     ($state: expr => ($pattern: pat) $body: expr) => {
-        $state = if let $pattern = std::mem::take(&mut $state) {
+        $state = if let $pattern = core::mem::take(&mut $state) {
             $body
         } else {
             unreachable!()
@@ -19,14 +19,19 @@ macro_rules! transition {
     };
 }
 
+mod buffer;
+pub use buffer::Buffer;
+
 mod archive;
-pub use archive::ArchiveFsm;
+pub use archive::{ArchiveFsm, ArchiveReadMetrics, HAYSTACK_SIZE};
 
 mod entry;
-pub use entry::{AexData, DecompressOutcome, EntryFsm};
+pub use entry::{
+    trial_decompress, AexData, CrcCheckpoint, DecompressOutcome, EntryFsm, EntryReadMetrics,
+};
 
 mod parsed_ranges;
-pub use parsed_ranges::ParsedRanges;
+pub use parsed_ranges::{ParsedRange, ParsedRanges, RangeOverlaps};
 
 /// Indicates whether or not the state machine has completed its work
 pub enum FsmResult<M, R> {
@@ -1,16 +1,57 @@
-use std::ops::Range;
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::{
+    cell::{RefCell, RefMut},
+    ops::Range,
+};
 
+/// A range of the file that was identified as containing a given structure
 #[derive(serde::Serialize, Debug, Clone)]
-struct ParsedRange {
+pub struct ParsedRange {
     /// Start of range
     start: u64,
     /// End of range (excluding)
     end: u64,
     /// The kind of data that was parsed here
-    contains: &'static str,
+    contains: Cow<'static, str>,
     /// Additional info (e.g. filename for files)
     #[serde(skip_serializing_if = "Option::is_none")]
     filename: Option<String>,
+    /// Free-form note attached by an external analyzer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+impl ParsedRange {
+    /// The bytes covered by this range
+    pub fn range(&self) -> Range<u64> {
+        self.start..self.end
+    }
+
+    /// The kind of data that was parsed here, e.g. `local file header`
+    pub fn contains(&self) -> &str {
+        &self.contains
+    }
+
+    /// The name of the entry this range belongs to, if any
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// The note an external analyzer attached, if any, see
+    /// [ParsedRanges::annotate]
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+}
+
+/// The ranges that share bytes, see [ParsedRanges::overlaps]
+#[derive(Debug, Clone)]
+pub struct RangeOverlaps<'a> {
+    /// Each pair of ranges, with the bytes both claim
+    pub pairs: Vec<(&'a ParsedRange, &'a ParsedRange, Range<u64>)>,
+
+    /// Whether there are more pairs than were returned
+    pub truncated: bool,
 }
 
 #[derive(serde::Serialize, Debug, Clone)]
@@ -21,6 +62,12 @@ impl ParsedRanges {
         Self(Vec::new())
     }
 
+    /// Borrows shared parsed ranges mutably. Returns `None` if they are
+    /// already borrowed.
+    pub fn lock(ranges: &RefCell<Self>) -> Option<RefMut<'_, Self>> {
+        ranges.try_borrow_mut().ok()
+    }
+
     pub fn insert_range(
         &mut self,
         range: Range<u64>,
@@ -30,11 +77,14 @@ impl ParsedRanges {
         self.0.push(ParsedRange {
             start: range.start,
             end: range.end,
-            contains,
+            contains: Cow::Borrowed(contains),
             filename,
+            note: None,
         });
     }
 
+    /// Records `length` bytes at `offset`. Lengths come from the archive, so
+    /// the range ends at `u64::MAX` rather than wrapping around.
     pub fn insert_offset_length(
         &mut self,
         offset: u64,
@@ -42,12 +92,137 @@ impl ParsedRanges {
         contains: &'static str,
         filename: Option<String>,
     ) {
-        self.insert_range(offset..offset + length, contains, filename)
+        self.insert_range(offset..offset.saturating_add(length), contains, filename)
+    }
+
+    /// Record a range that was identified by something other than the zip
+    /// parser itself, e.g. a domain-specific detector recognizing an APK
+    /// signing block or an embedded image.
+    ///
+    /// Annotated ranges are treated like any other parsed range, so they
+    /// count as explained bytes in [Self::gaps].
+    pub fn annotate(
+        &mut self,
+        range: Range<u64>,
+        label: impl Into<Cow<'static, str>>,
+        note: Option<String>,
+    ) {
+        self.0.push(ParsedRange {
+            start: range.start,
+            end: range.end,
+            contains: label.into(),
+            filename: None,
+            note,
+        });
     }
 
     pub fn append(&mut self, other: &mut ParsedRanges) {
         self.0.append(&mut other.0);
     }
+
+    /// Returns the first range, in insertion order, that contains `offset`,
+    /// e.g. to find out what an odd byte in a hex dump belongs to.
+    pub fn at(&self, offset: u64) -> Option<&ParsedRange> {
+        self.0.iter().find(|r| r.range().contains(&offset))
+    }
+
+    /// Iterates over all parsed and annotated ranges, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = &ParsedRange> {
+        self.0.iter()
+    }
+
+    /// Returns the pairs of ranges that share at least one byte, along with
+    /// the shared bytes. Within a pair, the range that starts first comes first.
+    ///
+    /// Identical ranges of the same kind, e.g. a local file header that many
+    /// central directory headers point to, are reported as a single pair of
+    /// the first two, and only the first of them is paired with other
+    /// ranges. At most `limit` pairs are returned, so that an archive can't
+    /// make this take quadratic time and memory.
+    pub fn overlaps(&self, limit: usize) -> RangeOverlaps<'_> {
+        let mut sorted: Vec<&ParsedRange> = self.0.iter().filter(|r| r.start < r.end).collect();
+        sorted.sort_by_key(|r| (r.start, r.end));
+
+        // each distinct range, with the first range identical to it
+        let mut distinct: Vec<(&ParsedRange, Option<&ParsedRange>)> = Vec::new();
+        let mut run_start = 0;
+        for range in sorted {
+            let run = &mut distinct[run_start..];
+            if run
+                .first()
+                .is_some_and(|(first, _)| first.range() == range.range())
+            {
+                if let Some((_, duplicate)) =
+                    run.iter_mut().find(|(r, _)| r.contains == range.contains)
+                {
+                    duplicate.get_or_insert(range);
+                    continue;
+                }
+            } else {
+                run_start = distinct.len();
+            }
+            distinct.push((range, None));
+        }
+
+        let mut overlaps = RangeOverlaps {
+            pairs: Vec::new(),
+            truncated: false,
+        };
+        for (i, (a, duplicate)) in distinct.iter().enumerate() {
+            let following = distinct[i + 1..]
+                .iter()
+                .map(|(b, _)| *b)
+                .take_while(|b| b.start < a.end);
+            for b in duplicate.iter().copied().chain(following) {
+                if overlaps.pairs.len() == limit {
+                    overlaps.truncated = true;
+                    return overlaps;
+                }
+                overlaps.pairs.push((*a, b, b.start..a.end.min(b.end)));
+            }
+        }
+        overlaps
+    }
+
+    /// Returns the ranges within `0..total_size` that aren't claimed by any
+    /// parsed or annotated range, in ascending order.
+    pub fn gaps(&self, total_size: u64) -> Vec<Range<u64>> {
+        let mut claimed: Vec<Range<u64>> = self
+            .0
+            .iter()
+            .filter(|r| r.start < r.end)
+            .map(|r| r.start..r.end)
+            .collect();
+        claimed.sort_by_key(|r| r.start);
+
+        let mut gaps = Vec::new();
+        let mut cursor = 0;
+        for range in claimed {
+            if range.start > cursor {
+                gaps.push(cursor..range.start.min(total_size));
+            }
+            cursor = cursor.max(range.end);
+            if cursor >= total_size {
+                break;
+            }
+        }
+        if cursor < total_size {
+            gaps.push(cursor..total_size);
+        }
+        gaps.retain(|r| r.start < r.end);
+        gaps
+    }
+
+    /// Returns the fraction of `0..total_size` claimed by parsed or annotated
+    /// ranges, counting bytes claimed more than once only once. An empty file
+    /// is fully covered.
+    pub fn coverage(&self, total_size: u64) -> f64 {
+        if total_size == 0 {
+            return 1.0;
+        }
+        let unclaimed: u64 = self.gaps(total_size).iter().map(|r| r.end - r.start).sum();
+        (total_size - unclaimed) as f64 / total_size as f64
+    }
 }
 
 impl Default for ParsedRanges {
@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::type_complexity)]
 
 //! rc-zip is a [sans-io](https://sans-io.readthedocs.io/how-to-sans-io.html) library for reading zip files.
@@ -11,6 +12,12 @@
 //!
 //!   * [rc-zip-sync](https://crates.io/crates/rc-zip-sync) for using std I/O traits
 //!   * [rc-zip-tokio](https://crates.io/crates/rc-zip-tokio) for using tokio I/O traits
+//!
+//! Without the default `std` feature, the parsers and state machines build
+//! under `no_std` with `alloc`. The `deflate`, `implode` and `aes` methods
+//! work there too, the other decompressors need `std`.
+
+extern crate alloc;
 
 pub mod encoding;
 pub mod error;
@@ -1,4 +1,5 @@
-use std::{rc::Rc, sync::Mutex};
+use alloc::{rc::Rc, string::String, vec::Vec};
+use core::cell::RefCell;
 
 use chrono::{offset::Utc, DateTime, TimeZone};
 use num_enum::{FromPrimitive, IntoPrimitive};
@@ -6,8 +7,8 @@ use ownable::{IntoOwned, ToOwned};
 use winnow::{binary::le_u16, PResult, Partial};
 
 use crate::{
-    encoding::Encoding,
-    fsm::ParsedRanges,
+    encoding::{Encoding, EncodingDetection},
+    fsm::{ArchiveReadMetrics, ParsedRanges},
     parse::{Mode, Version},
 };
 
@@ -29,9 +30,11 @@ pub struct Archive {
     pub directory_headers: Vec<CentralDirectoryFileHeader<'static>>,
     pub size: u64,
     pub encoding: Encoding,
+    pub encoding_detection: EncodingDetection,
     pub entries: Vec<Entry>,
     pub comment: String,
-    pub parsed_ranges: Rc<Mutex<ParsedRanges>>,
+    pub parsed_ranges: Rc<RefCell<ParsedRanges>>,
+    pub read_metrics: ArchiveReadMetrics,
 }
 
 impl Archive {
@@ -59,6 +62,12 @@ impl Archive {
         self.encoding
     }
 
+    /// Returns how [Self::encoding] was chosen, see [EncodingDetection]
+    #[inline(always)]
+    pub fn encoding_detection(&self) -> EncodingDetection {
+        self.encoding_detection
+    }
+
     /// Returns the comment for this archive, if any. When reading
     /// a zip file with an empty comment field, this will return None.
     #[inline(always)]
@@ -106,6 +115,9 @@ pub struct Entry {
     /// See [Self::accessed] for caveats.
     pub accessed: Option<DateTime<Utc>>,
 
+    /// Where [Self::modified], [Self::created] and [Self::accessed] come from
+    pub timestamp_source: TimestampSource,
+
     /// Offset of the local file header in the zip file
     ///
     /// ```text
@@ -209,11 +221,20 @@ impl Entry {
                 self.compressed_size = z64.compressed_size;
                 self.header_offset = z64.header_offset;
             }
-            ExtraField::Timestamp(ts) => {
-                self.modified = Utc
-                    .timestamp_opt(ts.mtime as i64, 0)
-                    .single()
-                    .unwrap_or_else(zero_datetime);
+            ExtraField::Timestamp(ts) if self.timestamp_source < TimestampSource::Extended => {
+                let time = |time: u32| Utc.timestamp_opt(time as i64, 0).single();
+                if let Some(mtime) = ts.mtime {
+                    self.modified = time(mtime).unwrap_or_else(zero_datetime);
+                }
+                if let Some(atime) = ts.atime {
+                    self.accessed = time(atime);
+                }
+                if let Some(ctime) = ts.ctime {
+                    self.created = time(ctime);
+                }
+                if ts.mtime.or(ts.atime).or(ts.ctime).is_some() {
+                    self.timestamp_source = TimestampSource::Extended;
+                }
             }
             ExtraField::Ntfs(nf) => {
                 for attr in &nf.attrs {
@@ -222,14 +243,18 @@ impl Entry {
                         self.modified = attr.mtime.to_datetime().unwrap_or_else(zero_datetime);
                         self.created = attr.ctime.to_datetime();
                         self.accessed = attr.atime.to_datetime();
+                        self.timestamp_source = TimestampSource::Ntfs;
                     }
                 }
             }
             ExtraField::Unix(uf) => {
-                self.modified = Utc
-                    .timestamp_opt(uf.mtime as i64, 0)
-                    .single()
-                    .unwrap_or_else(zero_datetime);
+                if self.timestamp_source < TimestampSource::Unix {
+                    self.modified = Utc
+                        .timestamp_opt(uf.mtime as i64, 0)
+                        .single()
+                        .unwrap_or_else(zero_datetime);
+                    self.timestamp_source = TimestampSource::Unix;
+                }
 
                 if self.uid.is_none() {
                     self.uid = Some(uf.uid as u32);
@@ -251,6 +276,28 @@ impl Entry {
     }
 }
 
+/// Where the timestamps of an entry come from. When several extra fields
+/// carry timestamps, the most precise one wins, regardless of their order:
+/// NTFS over extended timestamp over Info-ZIP Unix over MS-DOS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampSource {
+    /// The MS-DOS date and time of the header, with a resolution of two
+    /// seconds and no time zone
+    Dos,
+
+    /// The Info-ZIP Unix extra field (0x5855), with a resolution of a second
+    Unix,
+
+    /// The extended timestamp extra field (0x5455), with a resolution of a
+    /// second
+    Extended,
+
+    /// The NTFS extra field (0x000a), with a resolution of 100 nanoseconds,
+    /// which also has the created and accessed timestamps
+    Ntfs,
+}
+
 /// The entry's file type: a directory, a file, or a symbolic link.
 #[derive(Debug)]
 pub enum EntryKind {
@@ -302,6 +349,10 @@ pub enum Method {
     /// No compression is applied
     Store = 0,
 
+    /// Implode, the legacy LZ77 variant with Shannon-Fano trees of PKZIP 1.x
+    /// (see section 5.3 of appnote)
+    Implode = 6,
+
     /// [DEFLATE (RFC 1951)](https://www.ietf.org/rfc/rfc1951.txt)
     Deflate = 8,
 
@@ -345,4 +396,25 @@ impl Method {
     pub fn parser(i: &mut Partial<&[u8]>) -> PResult<Self> {
         le_u16(i).map(From::from)
     }
+
+    /// Whether this build can decompress entries with this method: the
+    /// method is supported, and the feature its decompressor needs is
+    /// enabled. For [Method::Aex], this only covers the decryption, the
+    /// method of the encrypted data is in the AE-x extra field.
+    pub fn is_decodable(self) -> bool {
+        // without any decompressor features, clippy would rather see `matches!`
+        #[allow(clippy::match_like_matches_macro)]
+        match self {
+            Method::Store => true,
+            Method::Implode => cfg!(feature = "implode"),
+            Method::Deflate => cfg!(feature = "deflate"),
+            Method::Deflate64 => cfg!(feature = "deflate64"),
+            Method::Bzip2 => cfg!(feature = "bzip2"),
+            Method::Lzma => cfg!(feature = "lzma"),
+            Method::Zstd => cfg!(feature = "zstd"),
+            Method::Ppmd => cfg!(feature = "ppmd"),
+            Method::Aex => cfg!(feature = "aes"),
+            _ => false,
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
 use ownable::{IntoOwned, ToOwned};
 use tracing::trace;
@@ -14,8 +14,8 @@ use crate::{
     encoding::Encoding,
     error::{Error, FormatError},
     parse::{
-        zero_datetime, Entry, ExtraField, ExtraFieldSettings, HostSystem, Mode, MsdosMode,
-        MsdosTimestamp, UnixMode, Version,
+        zero_datetime, Entry, ExtraField, ExtraFieldSettings, ExtraUnicodeCommentField, HostSystem,
+        Mode, MsdosMode, MsdosTimestamp, TimestampSource, UnixMode, Version,
     },
 };
 
@@ -66,6 +66,9 @@ pub struct CentralDirectoryFileHeader<'a> {
     /// extra field
     pub extra: Cow<'a, [u8]>,
 
+    /// extra field length, as declared in the header
+    pub extra_length: u16,
+
     /// comment field
     pub comment: Cow<'a, [u8]>,
 }
@@ -111,6 +114,7 @@ impl<'a> CentralDirectoryFileHeader<'a> {
             header_offset,
             name: Cow::Borrowed(name),
             extra: Cow::Borrowed(extra),
+            extra_length: extra_len,
             comment: Cow::Borrowed(comment),
         })
     }
@@ -138,6 +142,27 @@ impl CentralDirectoryFileHeader<'_> {
         self.flags & 0x800 == 0
     }
 
+    /// Returns the Unicode Comment extra field, if any. Use
+    /// [ExtraUnicodeCommentField::matches] to check whether it still applies
+    /// to this header's comment.
+    pub fn unicode_comment_field(&self) -> Option<ExtraUnicodeCommentField<'_>> {
+        let settings = ExtraFieldSettings {
+            uncompressed_size_u32: self.uncompressed_size,
+            compressed_size_u32: self.compressed_size,
+            header_offset_u32: self.header_offset,
+        };
+
+        let mut slice = Partial::new(&self.extra[..]);
+        while !slice.is_empty() {
+            match ExtraField::mk_parser(settings).parse_next(&mut slice) {
+                Ok(ExtraField::UnicodeComment(field)) => return Some(field),
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+        None
+    }
+
     /// Converts the directory header into a entry: this involves
     /// parsing the extra fields and converting the timestamps.
     pub fn as_entry(&self, encoding: Encoding, global_offset: u64) -> Result<Entry, Error> {
@@ -148,6 +173,7 @@ impl CentralDirectoryFileHeader<'_> {
             modified: self.modified.to_datetime().unwrap_or_else(zero_datetime),
             created: None,
             accessed: None,
+            timestamp_source: TimestampSource::Dos,
             header_offset: (self.header_offset as u64)
                 .checked_add(global_offset)
                 .ok_or(FormatError::InvalidHeaderOffset)?,
@@ -2,8 +2,8 @@ use chrono::{
     offset::{LocalResult, TimeZone, Utc},
     DateTime, Timelike,
 };
+use core::fmt;
 use ownable::{IntoOwned, ToOwned};
-use std::fmt;
 use winnow::{
     binary::{le_u16, le_u64},
     seq, PResult, Parser, Partial,
@@ -1,5 +1,5 @@
-use std::borrow::Cow;
-use std::mem::size_of;
+use alloc::borrow::Cow;
+use core::mem::size_of;
 
 use ownable::{traits as ownable_traits, IntoOwned, ToOwned};
 use tracing::trace;
@@ -41,7 +41,14 @@ pub struct EndOfCentralDirectoryRecord<'a> {
 impl<'a> EndOfCentralDirectoryRecord<'a> {
     /// Does not include comment size & comment data
     const MIN_LENGTH: usize = 20;
-    const SIGNATURE: &'static str = "PK\x05\x06";
+
+    /// The signature for an end of central directory record
+    pub const SIGNATURE: &'static str = "PK\x05\x06";
+
+    /// Length of a record with the longest possible comment. A search for
+    /// the record backward from the end of the file must cover at least this
+    /// many bytes, or it misses records with long comments.
+    pub const MAX_LENGTH: usize = Self::SIGNATURE.len() + Self::MIN_LENGTH - 2 + u16::MAX as usize;
 
     /// Find the end of central directory record in a block of data
     pub fn find_in_block(b: &'a [u8]) -> Option<Located<Self>> {
@@ -204,7 +211,7 @@ where
 }
 
 /// Coalesces zip and zip64 "end of central directory" record info
-#[derive(ToOwned, IntoOwned, serde::Serialize)]
+#[derive(Debug, Clone, ToOwned, IntoOwned, serde::Serialize)]
 pub struct EndOfCentralDirectory<'a> {
     /// The end of central directory record
     pub dir: Located<EndOfCentralDirectoryRecord<'a>>,
@@ -263,8 +270,13 @@ impl<'a> EndOfCentralDirectory<'a> {
             .checked_sub(res.directory_size())
             .ok_or(FormatError::DirectoryOffsetPointsOutsideFile)?;
 
+        // A directory size of zero with a nonzero entry count is contradictory, and the
+        // computed offset would be that of the end of central directory record itself,
+        // where no headers can be read. Trust the recorded offset instead.
+        let size_contradicts_records = res.directory_size() == 0 && res.directory_records() > 0;
+
         // did we find a valid offset?
-        if (0..size).contains(&computed_directory_offset) {
+        if (0..size).contains(&computed_directory_offset) && !size_contradicts_records {
             // that's different from the recorded one?
             if computed_directory_offset != res.directory_offset() {
                 // then assume the whole file is offset
@@ -295,16 +307,20 @@ impl<'a> EndOfCentralDirectory<'a> {
         }
     }
 
+    /// Absolute offset of the central directory in the file, after
+    /// correcting for any prepended data.
     #[inline]
-    pub(crate) fn directory_offset(&self) -> u64 {
+    pub fn directory_offset(&self) -> u64 {
         match self.dir64.as_ref() {
             Some(d64) => d64.inner.directory_offset,
             None => self.dir.inner.directory_offset as u64,
         }
     }
 
+    /// Size of the central directory in bytes, as recorded in the end of
+    /// central directory record
     #[inline]
-    pub(crate) fn directory_size(&self) -> u64 {
+    pub fn directory_size(&self) -> u64 {
         match self.dir64.as_ref() {
             Some(d64) => d64.inner.directory_size,
             None => self.dir.inner.directory_size as u64,
@@ -319,16 +335,29 @@ impl<'a> EndOfCentralDirectory<'a> {
         };
     }
 
+    /// Total number of entries in the central directory, as recorded in the
+    /// end of central directory record
     #[inline]
-    pub(crate) fn directory_records(&self) -> u64 {
+    pub fn directory_records(&self) -> u64 {
         match self.dir64.as_ref() {
             Some(d64) => d64.inner.directory_records,
             None => self.dir.inner.directory_records as u64,
         }
     }
 
+    /// Number of the disk the end of central directory record is on, which
+    /// is the last disk of the archive, counting from 0
+    #[inline]
+    pub fn disk_nbr(&self) -> u32 {
+        match self.dir64.as_ref() {
+            Some(d64) => d64.inner.disk_nbr,
+            None => self.dir.inner.disk_nbr as u32,
+        }
+    }
+
+    /// The archive comment
     #[inline]
-    pub(crate) fn comment(&self) -> &[u8] {
+    pub fn comment(&self) -> &[u8] {
         &self.dir.inner.comment
     }
 }
@@ -1,11 +1,12 @@
-use std::borrow::Cow;
+use alloc::{borrow::Cow, vec::Vec};
 
 use ownable::{IntoOwned, ToOwned};
 use winnow::{
     binary::{le_u16, le_u32, le_u64, le_u8, length_take},
-    combinator::{opt, preceded, repeat_till},
+    combinator::{opt, repeat_till},
     error::{ErrMode, ErrorKind, ParserError, StrContext},
     seq,
+    stream::Stream,
     token::{literal, take},
     PResult, Parser, Partial,
 };
@@ -73,6 +74,8 @@ pub enum ExtraField<'a> {
     Ntfs(ExtraNtfsField),
     /// AE-X extra data field
     Aex(ExtraAexField),
+    /// Info-ZIP Unicode Comment Extra Field
+    UnicodeComment(ExtraUnicodeCommentField<'a>),
     /// Unknown extra field, with tag
     Unknown {
         /// tag of the extra field
@@ -110,6 +113,10 @@ impl<'a> ExtraField<'a> {
                 ExtraAexField::TAG => {
                     opt(ExtraAexField::parser.map(EF::Aex)).parse_next(payload)?
                 }
+                ExtraUnicodeCommentField::TAG => {
+                    opt(ExtraUnicodeCommentField::parser.map(EF::UnicodeComment))
+                        .parse_next(payload)?
+                }
                 _ => None,
             }
             .unwrap_or(EF::Unknown { tag: rec.tag });
@@ -136,7 +143,7 @@ pub struct ExtraZip64Field {
 }
 
 impl ExtraZip64Field {
-    const TAG: u16 = 0x0001;
+    pub(crate) const TAG: u16 = 0x0001;
 
     pub(crate) fn mk_parser(
         settings: ExtraFieldSettings,
@@ -170,22 +177,47 @@ impl ExtraZip64Field {
 }
 
 /// Extended timestamp extra field
+///
+/// The flags say which of the times are present in the local header. The
+/// central directory copy of the field has the same flags, but usually only
+/// the modification time, so the other times are `None` when the field ends
+/// before them.
 #[derive(Clone)]
 pub struct ExtraTimestampField {
-    /// number of seconds since epoch
-    pub mtime: u32,
+    /// bit 0: modification time present, bit 1: access time present,
+    /// bit 2: creation time present
+    pub flags: u8,
+    /// last modification time, in seconds since epoch
+    pub mtime: Option<u32>,
+    /// last access time, in seconds since epoch
+    pub atime: Option<u32>,
+    /// creation time, in seconds since epoch
+    pub ctime: Option<u32>,
 }
 
 impl ExtraTimestampField {
     const TAG: u16 = 0x5455;
 
     fn parser(i: &mut Partial<&'_ [u8]>) -> PResult<Self> {
-        preceded(
-            // 1 byte of flags, if bit 0 is set, modification time is present
-            le_u8.verify(|x| x & 0b1 != 0),
-            seq! {Self { mtime: le_u32 }},
-        )
-        .parse_next(i)
+        let flags = le_u8.parse_next(i)?;
+        let mtime = Self::time(i, flags & 0b001 != 0)?;
+        let atime = Self::time(i, flags & 0b010 != 0)?;
+        let ctime = Self::time(i, flags & 0b100 != 0)?;
+        Ok(Self {
+            flags,
+            mtime,
+            atime,
+            ctime,
+        })
+    }
+
+    /// Parses a time the flags say is `present`, unless the field ends first
+    fn time(i: &mut Partial<&'_ [u8]>, present: bool) -> PResult<Option<u32>> {
+        if present && i.eof_offset() >= 4 {
+            le_u32.map(Some).parse_next(i)
+        } else {
+            Ok(None)
+        }
     }
 }
 
@@ -356,6 +388,51 @@ impl NtfsAttr1 {
     }
 }
 
+/// 4.6.8 -Info-ZIP Unicode Comment Extra Field (0x6375):
+///
+/// ```text
+/// Value         Size        Description
+/// -----         ----        -----------
+/// 0x6375        Short       tag for this extra block type ("uc")
+/// TSize         Short       total data size for this block
+/// Version       1 byte      version of this extra field, currently 1
+/// ComCRC32      4 bytes     Comment Field CRC32 Checksum
+/// UnicodeCom    Variable    UTF-8 version of the entry comment
+/// ```
+#[derive(Clone, ToOwned, IntoOwned)]
+pub struct ExtraUnicodeCommentField<'a> {
+    /// version of this extra field, currently 1
+    pub version: u8,
+
+    /// CRC-32 of the comment in the header, which this field replaces
+    pub comment_crc32: u32,
+
+    /// UTF-8 version of the comment, as stored
+    pub comment: Cow<'a, [u8]>,
+}
+
+impl<'a> ExtraUnicodeCommentField<'a> {
+    const TAG: u16 = 0x6375;
+
+    fn parser(i: &mut Partial<&'a [u8]>) -> PResult<Self> {
+        let version = le_u8.parse_next(i)?;
+        let comment_crc32 = le_u32.parse_next(i)?;
+        let comment = take(i.eof_offset()).parse_next(i)?;
+        Ok(Self {
+            version,
+            comment_crc32,
+            comment: Cow::Borrowed(comment),
+        })
+    }
+
+    /// Whether this field applies to `header_comment`, the raw comment from
+    /// the header. If not, the header was changed after this field was
+    /// written, and the field should be ignored.
+    pub fn matches(&self, header_comment: &[u8]) -> bool {
+        crc32fast::hash(header_comment) == self.comment_crc32
+    }
+}
+
 /// AE-X Extra Data Field (0x9901):
 #[derive(Debug, Clone, Copy, serde::Serialize)]
 pub struct ExtraAexField {
@@ -368,6 +445,31 @@ pub struct ExtraAexField {
 impl ExtraAexField {
     const TAG: u16 = 0x9901;
 
+    /// Size of the password verification value following the salt
+    pub const PASSWORD_VERIFICATION_SIZE: u64 = 2;
+
+    /// Size of the authentication code following the encrypted data
+    pub const AUTHENTICATION_CODE_SIZE: u64 = 10;
+
+    /// Size of the salt preceding the encrypted data, which depends on the
+    /// AES key strength. `None` for unknown strengths.
+    pub fn salt_size(&self) -> Option<u64> {
+        match self.mode {
+            0x1 => Some(8),
+            0x2 => Some(12),
+            0x3 => Some(16),
+            _ => None,
+        }
+    }
+
+    /// Amount of bytes an entry's data takes besides the encrypted data:
+    /// salt, password verification value and authentication code.
+    pub fn overhead(&self) -> Option<u64> {
+        self.salt_size().map(|salt_size| {
+            salt_size + Self::PASSWORD_VERIFICATION_SIZE + Self::AUTHENTICATION_CODE_SIZE
+        })
+    }
+
     fn parser(i: &mut Partial<&'_ [u8]>) -> PResult<Self> {
         seq! {Self {
             version_number: le_u16,
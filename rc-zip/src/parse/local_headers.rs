@@ -1,9 +1,9 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
 use crate::{
     encoding::{detect_utf8, Encoding},
     error::{Error, FormatError, UnsupportedError},
-    parse::{Method, MsdosTimestamp, Version},
+    parse::{Method, MsdosTimestamp, TimestampSource, Version},
 };
 
 use ownable::{IntoOwned, ToOwned};
@@ -17,7 +17,9 @@ use winnow::{
     PResult, Parser, Partial,
 };
 
-use super::{zero_datetime, Entry, ExtraField, ExtraFieldSettings, Mode};
+use super::{
+    zero_datetime, Entry, ExtraField, ExtraFieldRecord, ExtraFieldSettings, ExtraZip64Field, Mode,
+};
 
 #[derive(Clone, Debug, ToOwned, IntoOwned)]
 /// 4.3.7 Local file header
@@ -49,6 +51,9 @@ pub struct LocalFileHeader<'a> {
     /// extra field
     pub extra: Cow<'a, [u8]>,
 
+    /// extra field length, as declared in the header
+    pub extra_length: u16,
+
     /// method-specific fields
     pub method_specific: MethodSpecific,
 }
@@ -110,6 +115,7 @@ impl<'a> LocalFileHeader<'a> {
             uncompressed_size,
             name,
             extra,
+            extra_length: extra_len,
             method_specific,
         })
     }
@@ -122,6 +128,28 @@ impl<'a> LocalFileHeader<'a> {
         self.flags & 0b1000 != 0
     }
 
+    /// Check whether the sizes are stored as 8-byte values in a zip64
+    /// extended information extra field. That's the case when either size is
+    /// 0xFFFF_FFFF, but also when the extra field is there regardless.
+    pub fn has_zip64_sizes(&self) -> bool {
+        self.compressed_size == u32::MAX
+            || self.uncompressed_size == u32::MAX
+            || self.has_zip64_extra_field()
+    }
+
+    /// Check for the presence of a zip64 extended information extra field.
+    /// 4.3.9.2: when it's there, the sizes in the data descriptor are 8-byte
+    /// values, whatever the sizes in this header say.
+    pub fn has_zip64_extra_field(&self) -> bool {
+        let mut input = Partial::new(&self.extra[..]);
+        while let Ok(record) = ExtraFieldRecord::parser.parse_next(&mut input) {
+            if record.tag == ExtraZip64Field::TAG {
+                return true;
+            }
+        }
+        false
+    }
+
     /// Converts the local file header into an entry.
     pub fn as_entry(&self) -> Result<Entry, Error> {
         // see APPNOTE 4.4.4: Bit 11 is the language encoding flag (EFS)
@@ -140,6 +168,7 @@ impl<'a> LocalFileHeader<'a> {
             modified: self.modified.to_datetime().unwrap_or_else(zero_datetime),
             created: None,
             accessed: None,
+            timestamp_source: TimestampSource::Dos,
             header_offset: 0,
             reader_version: self.reader_version,
             flags: self.flags,
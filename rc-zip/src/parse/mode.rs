@@ -1,4 +1,4 @@
-use std::fmt;
+use core::fmt;
 
 /// Mode represents a file's mode and permission bits.
 /// The bits have the same definition on all systems,
@@ -224,7 +224,7 @@ impl From<u32> for MsdosMode {
 
 macro_rules! derive_bitops {
     ($T: ty) => {
-        impl std::ops::BitOr for $T {
+        impl core::ops::BitOr for $T {
             type Output = Self;
 
             fn bitor(self, rhs: Self) -> Self {
@@ -232,13 +232,13 @@ macro_rules! derive_bitops {
             }
         }
 
-        impl std::ops::BitOrAssign for $T {
+        impl core::ops::BitOrAssign for $T {
             fn bitor_assign(&mut self, rhs: Self) {
                 self.0 |= rhs.0;
             }
         }
 
-        impl std::ops::BitAnd for $T {
+        impl core::ops::BitAnd for $T {
             type Output = Self;
 
             fn bitand(self, rhs: Self) -> Self {
@@ -246,7 +246,7 @@ macro_rules! derive_bitops {
             }
         }
 
-        impl std::ops::BitAndAssign for $T {
+        impl core::ops::BitAndAssign for $T {
             fn bitand_assign(&mut self, rhs: Self) {
                 self.0 &= rhs.0;
             }
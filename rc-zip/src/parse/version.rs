@@ -1,6 +1,6 @@
+use core::fmt;
 use num_enum::{FromPrimitive, IntoPrimitive};
 use ownable::{IntoOwned, ToOwned};
-use std::fmt;
 use winnow::{binary::le_u8, seq, PResult, Parser, Partial};
 
 /// A zip version (either created by, or required when reading an archive).
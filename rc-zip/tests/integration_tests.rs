@@ -2,8 +2,10 @@ use std::cmp;
 
 use rc_zip::{
     corpus,
-    fsm::{ArchiveFsm, FsmResult},
+    fsm::{ArchiveFsm, Buffer, CrcCheckpoint, EntryFsm, FsmResult, ParsedRanges, HAYSTACK_SIZE},
+    parse::{Archive, Entry, ExtraField, ExtraFieldSettings},
 };
+use winnow::{Parser, Partial};
 
 #[test]
 fn state_machine() {
@@ -49,3 +51,221 @@ fn state_machine() {
     // cool, we have the archive
     let _ = archive;
 }
+
+#[test]
+fn annotated_ranges_close_gaps() {
+    let mut ranges = ParsedRanges::new();
+    ranges.insert_range(0..30, "local file header", Some("a.txt".into()));
+    ranges.insert_range(30..40, "file data", Some("a.txt".into()));
+    ranges.insert_range(100..122, "end of central directory record", None);
+    assert_eq!(ranges.gaps(130), vec![40..100, 122..130]);
+    assert_eq!(ranges.coverage(130), 62.0 / 130.0);
+
+    ranges.annotate(40..100, "apk signing block", Some("v2".into()));
+    assert_eq!(ranges.gaps(130), vec![122..130]);
+    assert_eq!(ranges.coverage(130), 122.0 / 130.0);
+
+    assert_eq!(ranges.at(0).unwrap().filename(), Some("a.txt"));
+    assert_eq!(ranges.at(39).unwrap().contains(), "file data");
+    assert_eq!(ranges.at(40).unwrap().contains(), "apk signing block");
+    assert!(ranges.at(122).is_none());
+}
+
+#[test]
+fn overlapping_ranges() {
+    let mut ranges = ParsedRanges::new();
+    ranges.insert_range(0..30, "local file header", Some("a.txt".into()));
+    ranges.insert_range(30..100, "file data", Some("a.txt".into()));
+    ranges.insert_range(40..70, "local file header", Some("b.txt".into()));
+    ranges.insert_range(100..122, "end of central directory record", None);
+
+    let overlaps = ranges.overlaps(usize::MAX);
+    assert_eq!(overlaps.pairs.len(), 1);
+    assert!(!overlaps.truncated);
+    let (data, header, shared) = &overlaps.pairs[0];
+    assert_eq!(data.filename(), Some("a.txt"));
+    assert_eq!(header.contains(), "local file header");
+    assert_eq!(*shared, 40..70);
+}
+
+#[test]
+fn identical_ranges_overlap_once() {
+    // one local file header and its data, claimed by many entries
+    let mut ranges = ParsedRanges::new();
+    for index in 0..10_000 {
+        let name = Some(format!("{index}.txt"));
+        ranges.insert_range(0..30, "local file header", name.clone());
+        ranges.insert_range(30..100, "file data", name);
+    }
+    ranges.insert_range(20..40, "apk signing block", None);
+
+    let overlaps = ranges.overlaps(usize::MAX);
+    let described: Vec<_> = overlaps
+        .pairs
+        .iter()
+        .map(|(a, b, shared)| (a.filename(), b.contains(), shared.clone()))
+        .collect();
+    assert_eq!(
+        described,
+        [
+            (Some("0.txt"), "local file header", 0..30),
+            (Some("0.txt"), "apk signing block", 20..30),
+            (None, "file data", 30..40),
+            (Some("0.txt"), "file data", 30..100),
+        ]
+    );
+
+    // staggered ranges that all overlap each other are cut off at the limit
+    let mut ranges = ParsedRanges::new();
+    for index in 0..10_000 {
+        ranges.insert_range(index..100_000 + index, "file data", None);
+    }
+    let overlaps = ranges.overlaps(1000);
+    assert_eq!(overlaps.pairs.len(), 1000);
+    assert!(overlaps.truncated);
+}
+
+#[test]
+fn entry_fsm_grows_small_buffer() {
+    let mut buffer = Buffer::with_capacity(16);
+    buffer.space()[..4].copy_from_slice(b"PK\x03\x04");
+    buffer.fill(4);
+
+    // used to panic, now the leftover data is kept in a larger buffer
+    let mut fsm = EntryFsm::new(None, Some(buffer), None);
+    assert!(fsm.space().len() > 16);
+}
+
+#[test]
+fn archive_fsm_buffer_is_bounded() {
+    // used to allocate a buffer as large as the file
+    let size = u64::MAX / 2;
+    let mut fsm = ArchiveFsm::new(size);
+    assert_eq!(fsm.wants_read(), Some(size - HAYSTACK_SIZE));
+    assert_eq!(fsm.space().len() as u64, HAYSTACK_SIZE);
+
+    // the buffer grows for a central directory header larger than it
+    let bytes = std::fs::read("../testdata/long_file_name.zip").unwrap();
+    let archive = read_archive_with(
+        ArchiveFsm::with_haystack_size(bytes.len() as u64, 1024),
+        &bytes,
+    );
+    assert_eq!(archive.entries().next().unwrap().name.len(), 40000);
+}
+
+fn read_archive(bytes: &[u8]) -> Archive {
+    read_archive_with(ArchiveFsm::new(bytes.len() as u64), bytes)
+}
+
+fn read_archive_with(mut fsm: ArchiveFsm, bytes: &[u8]) -> Archive {
+    loop {
+        if let Some(offset) = fsm.wants_read() {
+            let slice = &bytes[offset as usize..];
+            let len = cmp::min(slice.len(), fsm.space().len());
+            fsm.space()[..len].copy_from_slice(&slice[..len]);
+            fsm.fill(len);
+        }
+        fsm = match fsm.process().unwrap() {
+            FsmResult::Continue(fsm) => fsm,
+            FsmResult::Done(archive) => return archive,
+        }
+    }
+}
+
+/// Reads `entry` from `bytes` in small steps, stopping once `stop_after`
+/// bytes have been produced. Returns the checkpoint at that point, or the
+/// CRC32 hash of all data if it got to the end.
+fn read_entry(
+    bytes: &[u8],
+    entry: &Entry,
+    resume: Option<CrcCheckpoint>,
+    stop_after: u64,
+) -> Result<CrcCheckpoint, u32> {
+    let mut fsm = EntryFsm::new(Some(entry.clone()), None, None);
+    let mut offset = entry.header_offset as usize;
+    let mut out = [0u8; 100];
+    loop {
+        if fsm.wants_read() {
+            let slice = &bytes[offset..cmp::min(offset + 64, bytes.len())];
+            let len = cmp::min(slice.len(), fsm.space().len());
+            fsm.space()[..len].copy_from_slice(&slice[..len]);
+            offset += fsm.fill(len);
+        }
+        if let Some(checkpoint) = resume {
+            fsm.process_till_header().unwrap();
+            if fsm.checkpoint().is_none() && fsm.local_header_entry().is_some() {
+                offset = fsm.resume_from(checkpoint).unwrap() as usize;
+                continue;
+            }
+        }
+        fsm = match fsm.process(&mut out).unwrap() {
+            FsmResult::Continue((fsm, _)) => fsm,
+            FsmResult::Done((_, _, _, metrics)) => return Err(metrics.crc32),
+        };
+        if let Some(checkpoint) = fsm.checkpoint() {
+            if checkpoint.uncompressed_bytes >= stop_after {
+                return Ok(checkpoint);
+            }
+        }
+    }
+}
+
+#[test]
+fn entry_fsm_resumes_from_checkpoint() {
+    let cases = corpus::test_cases();
+    let case = cases.iter().find(|x| x.name == "test.zip").unwrap();
+    let bytes = case.bytes();
+    let archive = read_archive(&bytes);
+    let entry = archive.by_name("gophercolor16x16.png").unwrap();
+
+    let checkpoint = read_entry(&bytes, entry, None, 300).unwrap();
+    assert!(checkpoint.uncompressed_bytes >= 300);
+    assert!(checkpoint.uncompressed_bytes < entry.uncompressed_size);
+
+    // a fresh state machine picks up where the first one stopped, and the
+    // hash over both parts matches the one of the whole entry
+    let crc32 = read_entry(&bytes, entry, Some(checkpoint), u64::MAX).unwrap_err();
+    assert_eq!(crc32, entry.crc32);
+
+    // the compressed entry can't be resumed
+    let entry = archive.by_name("test.txt").unwrap();
+    let mut fsm = EntryFsm::new(Some(entry.clone()), None, None);
+    let header = &bytes[entry.header_offset as usize..];
+    let len = cmp::min(header.len(), fsm.space().len());
+    fsm.space()[..len].copy_from_slice(&header[..len]);
+    fsm.fill(len);
+    fsm.process_till_header().unwrap();
+    assert!(fsm.local_header_entry().is_some());
+    assert!(fsm.resume_from(checkpoint).is_err());
+}
+
+#[test]
+fn extended_timestamp_with_fewer_times_than_flagged() {
+    let settings = ExtraFieldSettings {
+        uncompressed_size_u32: 0,
+        compressed_size_u32: 0,
+        header_offset_u32: 0,
+    };
+    let parse = |bytes: &[u8]| match ExtraField::mk_parser(settings)
+        .parse_next(&mut Partial::new(bytes))
+        .unwrap()
+    {
+        ExtraField::Timestamp(ts) => ts,
+        _ => panic!("not an extended timestamp"),
+    };
+
+    // in the local header: all three times the flags announce
+    let local = parse(&[0x55, 0x54, 13, 0, 0b111, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+    assert_eq!(
+        (local.mtime, local.atime, local.ctime),
+        (Some(1), Some(2), Some(3))
+    );
+
+    // in the central directory: the same flags, but only the modification time
+    let central = parse(&[0x55, 0x54, 5, 0, 0b111, 1, 0, 0, 0]);
+    assert_eq!(central.flags, 0b111);
+    assert_eq!(
+        (central.mtime, central.atime, central.ctime),
+        (Some(1), None, None)
+    );
+}
@@ -0,0 +1,52 @@
+//! Reading archives from Python file-like objects
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use pyo3::{prelude::*, types::PyBytes};
+
+/// A Python object with `read()` and `seek()`, such as an open binary file or
+/// a streaming wrapper around remote storage, as [Read] and [Seek]
+pub struct FileLike<'py>(pub Bound<'py, PyAny>);
+
+impl Read for FileLike<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self
+            .0
+            .call_method1("read", (buf.len(),))
+            .map_err(io::Error::other)?;
+        let data = match data.downcast::<PyBytes>() {
+            Ok(bytes) => bytes.as_bytes().to_vec(),
+            // e.g. a bytearray or memoryview
+            Err(_) => data.extract::<Vec<u8>>().map_err(io::Error::other)?,
+        };
+        if data.len() > buf.len() {
+            return Err(io::Error::other(format!(
+                "read({}) returned {} bytes",
+                buf.len(),
+                data.len()
+            )));
+        }
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
+    }
+}
+
+impl Seek for FileLike<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, 0),
+            SeekFrom::Current(offset) => (offset, 1),
+            SeekFrom::End(offset) => (offset, 2),
+        };
+        let position = self
+            .0
+            .call_method1("seek", (offset, whence))
+            .map_err(io::Error::other)?;
+        // not every file-like object returns the new position
+        let position = match position.is_none() {
+            true => self.0.call_method0("tell").map_err(io::Error::other)?,
+            false => position,
+        };
+        position.extract().map_err(io::Error::other)
+    }
+}
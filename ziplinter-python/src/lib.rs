@@ -1,24 +1,81 @@
-use pyo3::prelude::*;
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
 use pythonize::pythonize;
 
+mod file_like;
+mod typed;
+
+create_exception!(
+    ziplinter,
+    ZipLinterError,
+    PyException,
+    "Raised when an archive can't be analyzed at all"
+);
+
+fn options(password: Option<String>) -> ::ziplinter::ParseOptions {
+    ::ziplinter::ParseOptions {
+        password: password.map(String::into_bytes),
+        ..Default::default()
+    }
+}
+
+/// Converts a report to a dict, or raises [ZipLinterError] if it's the error
+/// of an archive that couldn't be analyzed
+fn to_dict<'a>(py: Python<'a>, value: &serde_json::Value) -> PyResult<Bound<'a, PyAny>> {
+    if let Some(error) = value.get("error") {
+        return Err(ZipLinterError::new_err(
+            error.as_str().unwrap_or_default().to_string(),
+        ));
+    }
+    Ok(pythonize(py, value)?)
+}
+
 #[pyfunction]
-fn parse_file<'a>(py: Python<'a>, path: String) -> PyResult<pyo3::Bound<'a, PyAny>> {
+#[pyo3(signature = (path, password=None))]
+fn parse_file<'a>(
+    py: Python<'a>,
+    path: String,
+    password: Option<String>,
+) -> PyResult<pyo3::Bound<'a, PyAny>> {
     let file = std::fs::File::open(&path)?;
-    let value = ::ziplinter::parse_file(&file);
+    let value = ::ziplinter::parse_file_with(&file, &options(password));
+    to_dict(py, &value)
+}
 
-    Ok(pythonize(py, &value)?)
+#[pyfunction]
+#[pyo3(signature = (bytes, password=None))]
+fn parse_bytes<'a>(
+    py: Python<'a>,
+    bytes: Vec<u8>,
+    password: Option<String>,
+) -> PyResult<pyo3::Bound<'a, PyAny>> {
+    let value = ::ziplinter::parse_bytes_with(&bytes, &options(password));
+    to_dict(py, &value)
 }
 
+/// Like `parse_bytes`, but reads the archive from a file-like object with
+/// `read()` and `seek()` as the analysis goes, instead of all at once
 #[pyfunction]
-fn parse_bytes<'a>(py: Python<'a>, bytes: Vec<u8>) -> PyResult<pyo3::Bound<'a, PyAny>> {
-    let value = ::ziplinter::parse_bytes(&bytes);
-    Ok(pythonize(py, &value)?)
+#[pyo3(signature = (obj, password=None))]
+fn parse_fileobj<'a>(
+    py: Python<'a>,
+    obj: Bound<'a, PyAny>,
+    password: Option<String>,
+) -> PyResult<pyo3::Bound<'a, PyAny>> {
+    let value = ::ziplinter::parse_reader_with(file_like::FileLike(obj), &options(password));
+    to_dict(py, &value)
 }
 
 #[pymodule]
 fn ziplinter(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("ZipLinterError", m.py().get_type::<ZipLinterError>())?;
     m.add_function(wrap_pyfunction!(parse_file, m)?)?;
     m.add_function(wrap_pyfunction!(parse_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_fileobj, m)?)?;
+    m.add_function(wrap_pyfunction!(typed::parse_file_typed, m)?)?;
+    m.add_class::<typed::ZipReport>()?;
+    m.add_class::<typed::EntryInfo>()?;
+    m.add_class::<typed::ParsedRange>()?;
+    m.add_class::<typed::Finding>()?;
 
     Ok(())
 }
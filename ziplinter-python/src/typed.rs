@@ -0,0 +1,228 @@
+//! Typed wrappers around the report, for callers that want attributes they
+//! can autocomplete rather than nested dicts
+
+use pyo3::prelude::*;
+use pythonize::pythonize;
+
+/// A range of the file that was identified as containing a given structure
+#[pyclass(frozen, get_all)]
+#[derive(Clone)]
+pub struct ParsedRange {
+    /// Start of the range
+    start: u64,
+    /// End of the range (excluding)
+    end: u64,
+    /// The kind of data that was parsed here, e.g. `local file header`
+    contains: String,
+    /// The name of the entry this range belongs to, if any
+    filename: Option<String>,
+    /// A note attached by a detector that recognized the range, e.g. the
+    /// signature schemes of an APK signing block
+    note: Option<String>,
+}
+
+#[pymethods]
+impl ParsedRange {
+    fn __repr__(&self) -> String {
+        format!(
+            "ParsedRange(start={}, end={}, contains={:?})",
+            self.start, self.end, self.contains
+        )
+    }
+}
+
+/// Something noteworthy about the archive or one of its entries
+#[pyclass(frozen, get_all)]
+#[derive(Clone)]
+pub struct Finding {
+    /// Stable identifier for this kind of finding
+    code: String,
+    /// `info`, `warning` or `error`
+    severity: String,
+    /// Human-readable description of what was found
+    message: String,
+    /// Index into `contents` of the entry this finding is about, if any
+    entry: Option<usize>,
+    /// Offset in the file this finding is about, if any
+    offset: Option<u64>,
+}
+
+#[pymethods]
+impl Finding {
+    fn __repr__(&self) -> String {
+        format!(
+            "Finding(code={:?}, severity={:?})",
+            self.code, self.severity
+        )
+    }
+}
+
+/// An entry of the archive, mostly as its central directory header describes
+/// it
+#[pyclass(frozen, get_all)]
+#[derive(Clone)]
+pub struct EntryInfo {
+    /// Identifier of the entry that doesn't depend on its index
+    id: String,
+    /// The name, decoded with the archive's encoding
+    name: String,
+    /// The compression method, e.g. `Deflate`
+    method: String,
+    /// The last modification time, as an RFC 3339 string
+    modified: String,
+    /// crc32 hash of the uncompressed data
+    crc32: u32,
+    /// Compressed size, as declared in the central directory, with the one
+    /// from the zip64 extra field in place of a 0xFFFFFFFF placeholder
+    compressed_size: u64,
+    /// Uncompressed size, as declared in the central directory, with the one
+    /// from the zip64 extra field in place of a 0xFFFFFFFF placeholder
+    uncompressed_size: u64,
+    /// Absolute offset of the local file header
+    header_offset: u64,
+    /// The file comment
+    comment: String,
+    /// Whether extracting the name naively may write outside the destination
+    is_path_traversal: bool,
+    /// Why the local file header couldn't be read, if it couldn't
+    local_error: Option<String>,
+}
+
+#[pymethods]
+impl EntryInfo {
+    fn __repr__(&self) -> String {
+        format!("EntryInfo(name={:?}, method={})", self.name, self.method)
+    }
+}
+
+/// The report on an archive. Properties that have no attribute here are
+/// available through [ZipReport::to_dict].
+#[pyclass(frozen)]
+pub struct ZipReport {
+    /// Version of the shape of the report
+    #[pyo3(get)]
+    format_version: u32,
+    /// Version of ziplinter that produced the report
+    #[pyo3(get)]
+    ziplinter_version: String,
+    /// Size of the file in bytes
+    #[pyo3(get)]
+    size: u64,
+    /// Text encoding used for names and comments
+    #[pyo3(get)]
+    encoding: String,
+    /// The archive comment
+    #[pyo3(get)]
+    comment: String,
+    /// The entries, in the order of the central directory
+    #[pyo3(get)]
+    contents: Vec<EntryInfo>,
+    /// The ranges of the file that were parsed
+    #[pyo3(get)]
+    parsed_ranges: Vec<ParsedRange>,
+    /// The ranges of the file that no parsed range covers, as (start, end)
+    #[pyo3(get)]
+    gaps: Vec<(u64, u64)>,
+    /// The fraction of the file that parsed ranges cover
+    #[pyo3(get)]
+    coverage: f64,
+    /// Everything the checks found
+    #[pyo3(get)]
+    findings: Vec<Finding>,
+    /// Whether the archive conforms to the zip specification
+    #[pyo3(get)]
+    strict_conformant: bool,
+    /// The report as JSON, for [ZipReport::to_dict]
+    json: serde_json::Value,
+}
+
+#[pymethods]
+impl ZipReport {
+    /// The full report, as the dict `parse_file` returns
+    fn to_dict<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        Ok(pythonize(py, &self.json)?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ZipReport(size={}, entries={}, findings={})",
+            self.size,
+            self.contents.len(),
+            self.findings.len()
+        )
+    }
+}
+
+impl From<::ziplinter::ZipMetadata> for ZipReport {
+    fn from(metadata: ::ziplinter::ZipMetadata) -> Self {
+        let contents = metadata
+            .contents
+            .iter()
+            .map(|file| EntryInfo {
+                id: file.id.clone(),
+                name: file.central.name.clone(),
+                method: format!("{:?}", file.central.method),
+                modified: file.central.modified.to_rfc3339(),
+                crc32: file.central.crc32,
+                compressed_size: file.central.zip64_compressed_size,
+                uncompressed_size: file.central.zip64_uncompressed_size,
+                header_offset: file.header_offset,
+                comment: file.central.comment.clone(),
+                is_path_traversal: file.central.is_path_traversal,
+                local_error: file.local.as_ref().err().map(|e| e.to_string()),
+            })
+            .collect();
+        let parsed_ranges = metadata
+            .parsed_ranges
+            .iter()
+            .map(|range| ParsedRange {
+                start: range.range().start,
+                end: range.range().end,
+                contains: range.contains().to_string(),
+                filename: range.filename().map(str::to_string),
+                note: range.note().map(str::to_string),
+            })
+            .collect();
+        let findings = metadata
+            .findings
+            .iter()
+            .map(|finding| Finding {
+                code: finding.code.to_string(),
+                severity: finding.severity.as_str().to_string(),
+                message: finding.message.clone(),
+                entry: finding.entry,
+                offset: finding.offset,
+            })
+            .collect();
+        ZipReport {
+            format_version: metadata.format_version,
+            ziplinter_version: metadata.ziplinter_version.to_string(),
+            size: metadata.size,
+            encoding: metadata.encoding.to_string(),
+            comment: metadata.comment.clone(),
+            contents,
+            parsed_ranges,
+            gaps: metadata
+                .gaps
+                .iter()
+                .map(|gap| (gap.start, gap.end))
+                .collect(),
+            coverage: metadata.coverage,
+            findings,
+            strict_conformant: metadata.strict_conformant,
+            json: serde_json::to_value(&metadata)
+                .unwrap_or_else(|error| serde_json::json!({ "error": error.to_string() })),
+        }
+    }
+}
+
+/// Like `parse_file`, but returns a [ZipReport] instead of a dict
+#[pyfunction]
+#[pyo3(signature = (path, password=None))]
+pub fn parse_file_typed(path: String, password: Option<String>) -> PyResult<ZipReport> {
+    let file = std::fs::File::open(&path)?;
+    match ::ziplinter::analyze_file_with(&file, &super::options(password)) {
+        Ok(metadata) => Ok(metadata.into()),
+        Err(error) => Err(super::ZipLinterError::new_err(error.to_string())),
+    }
+}
@@ -1,3 +1,4 @@
+import io
 import ziplinter
 
 def main():
@@ -22,5 +23,37 @@ def main():
 
     assert info1 != info2
 
+    with open("../testdata/concatenated.zip", "rb") as f:
+        assert ziplinter.parse_fileobj(f) == info1
+    assert ziplinter.parse_fileobj(io.BytesIO(data[:start])) == info2
+
+    report = ziplinter.parse_file_typed("../testdata/unix.zip")
+    assert report.comment == ""
+    assert [e.name for e in report.contents] == [e["central"]["name"] for e in info["contents"]]
+    assert report.parsed_ranges[0].end == report.size
+    assert report.to_dict() == info
+
+    # sizes in the zip64 extra field replace their 0xFFFFFFFF placeholders
+    report = ziplinter.parse_file_typed("../testdata/zip64.zip")
+    assert [(e.compressed_size, e.uncompressed_size) for e in report.contents] == [(36, 36)]
+
+    # notes of recognized ranges are kept, as are the severities of findings
+    report = ziplinter.parse_file_typed("../testdata/apk-signing-block.zip")
+    assert [r.note for r in report.parsed_ranges if r.contains == "apk signing block"] == ["v2, v3"]
+    assert [f.severity for f in report.findings] == [f["severity"] for f in report.to_dict()["findings"]]
+
+    for parse in (lambda: ziplinter.parse_bytes(b"not a zip"), lambda: ziplinter.parse_file("../testdata/hamlet.txt")):
+        try:
+            parse()
+            assert False, "expected ZipLinterError"
+        except ziplinter.ZipLinterError as error:
+            assert "DirectoryEndSignatureNotFound" in str(error)
+
+    try:
+        ziplinter.parse_file("../testdata/does-not-exist.zip")
+        assert False, "expected OSError"
+    except OSError:
+        pass
+
 if __name__ == '__main__':
     main()
@@ -0,0 +1,159 @@
+//! Recognition of the APK Signing Block.
+//!
+//! Android APKs carry their v2+ signatures in a block that sits between the
+//! last entry's data and the central directory. It isn't part of the zip
+//! format, so without recognizing it the whole block shows up as unexplained
+//! bytes. See <https://source.android.com/docs/security/features/apksigning/v2#apk-signing-block>.
+//!
+//! ```text
+//! [size of block, excluding this field] u64
+//! [ID-value pairs]                      u64 length, u32 id, value
+//! [size of block, same as above]        u64
+//! [magic "APK Sig Block 42"]            16 bytes
+//! ```
+
+use std::io::Read;
+
+use rc_zip::fsm::ParsedRanges;
+use rc_zip_sync::HasCursor;
+
+const MAGIC: &[u8; 16] = b"APK Sig Block 42";
+
+/// Size of the trailing `size` field plus the magic
+const FOOTER_LENGTH: u64 = 8 + 16;
+
+/// An APK Signing Block found right before the central directory
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ApkSigningBlock {
+    /// Offset of the block in the file
+    pub offset: u64,
+
+    /// Total length of the block in bytes, including both size fields and the magic
+    pub length: u64,
+
+    /// The ID-value pairs stored in the block
+    pub pairs: Vec<ApkSigningBlockPair>,
+
+    /// Signature schemes present in the block, e.g. `v2`, `v3`
+    pub signature_schemes: Vec<&'static str>,
+}
+
+/// A single ID-value pair from the APK Signing Block
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ApkSigningBlockPair {
+    /// ID of the pair
+    pub id: u32,
+
+    /// Length of the value in bytes
+    pub length: u64,
+
+    /// Human-readable name for well-known IDs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<&'static str>,
+}
+
+impl ApkSigningBlockPair {
+    fn kind(id: u32) -> Option<&'static str> {
+        match id {
+            0x7109_871a => Some("v2 signature scheme"),
+            0xf053_68c0 => Some("v3 signature scheme"),
+            0x1b93_ad61 => Some("v3.1 signature scheme"),
+            0x4272_6577 => Some("verity padding"),
+            0x2146_444e => Some("google play frosting"),
+            0x6dff_800d => Some("source stamp"),
+            0x504b_4453 => Some("dependency info"),
+            _ => None,
+        }
+    }
+
+    fn signature_scheme(&self) -> Option<&'static str> {
+        match self.id {
+            0x7109_871a => Some("v2"),
+            0xf053_68c0 => Some("v3"),
+            0x1b93_ad61 => Some("v3.1"),
+            _ => None,
+        }
+    }
+}
+
+impl ApkSigningBlock {
+    /// Records the block in `parsed_ranges`, so that it counts as explained,
+    /// with its signature schemes as the note
+    pub(crate) fn annotate(&self, parsed_ranges: &mut ParsedRanges) {
+        parsed_ranges.annotate(
+            self.offset..self.offset + self.length,
+            "apk signing block",
+            Some(self.signature_schemes.join(", ")).filter(|s| !s.is_empty()),
+        );
+    }
+}
+
+fn read_exact_at<F: HasCursor>(file: &F, offset: u64, buf: &mut [u8]) -> Option<()> {
+    file.cursor_at(offset).read_exact(buf).ok()
+}
+
+/// Looks for an APK Signing Block ending at `directory_offset`.
+///
+/// Returns `None` if there is no block, or if it is malformed in a way that
+/// makes its boundaries unknowable.
+pub(crate) fn find_signing_block<F: HasCursor>(
+    file: &F,
+    directory_offset: u64,
+) -> Option<ApkSigningBlock> {
+    let footer_offset = directory_offset.checked_sub(FOOTER_LENGTH)?;
+    let mut footer = [0u8; FOOTER_LENGTH as usize];
+    read_exact_at(file, footer_offset, &mut footer)?;
+    if &footer[8..] != MAGIC {
+        return None;
+    }
+
+    // the size excludes the leading size field, but includes everything else
+    let size = u64::from_le_bytes(footer[..8].try_into().unwrap());
+    let length = size.checked_add(8)?;
+    if size < FOOTER_LENGTH {
+        return None;
+    }
+    let offset = directory_offset.checked_sub(length)?;
+
+    let mut block = vec![0u8; usize::try_from(length).ok()?];
+    read_exact_at(file, offset, &mut block)?;
+    if block[..8] != footer[..8] {
+        return None;
+    }
+
+    let mut pairs = Vec::new();
+    let mut rest = &block[8..block.len() - FOOTER_LENGTH as usize];
+    while rest.len() >= 12 {
+        let pair_length = u64::from_le_bytes(rest[..8].try_into().unwrap());
+        let id = u32::from_le_bytes(rest[8..12].try_into().unwrap());
+        let Some(value_length) = pair_length.checked_sub(4) else {
+            break;
+        };
+        pairs.push(ApkSigningBlockPair {
+            id,
+            length: value_length,
+            kind: ApkSigningBlockPair::kind(id),
+        });
+
+        match usize::try_from(pair_length)
+            .ok()
+            .and_then(|l| l.checked_add(8))
+            .and_then(|l| rest.get(l..))
+        {
+            Some(next) => rest = next,
+            None => break,
+        }
+    }
+
+    let signature_schemes = pairs
+        .iter()
+        .filter_map(ApkSigningBlockPair::signature_scheme)
+        .collect();
+
+    Some(ApkSigningBlock {
+        offset,
+        length,
+        pairs,
+        signature_schemes,
+    })
+}
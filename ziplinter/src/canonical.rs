@@ -0,0 +1,89 @@
+//! Canonical JSON: a single, deterministic serialization of a report, so that
+//! scans of the same archive are byte-identical and can be diffed or hashed.
+//!
+//! Object keys are sorted by their UTF-16 code units, there is no whitespace
+//! between tokens and numbers are written in their shortest form, with
+//! integral floats written as integers. This follows the JSON
+//! Canonicalization Scheme (RFC 8785) for everything a report contains.
+
+use std::fmt::Write;
+
+use serde_json::{Number, Value};
+
+/// Largest integer that an `f64` represents exactly, along with all smaller ones
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Serializes `value` as canonical JSON
+pub fn to_canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(out, n),
+        Value::String(s) => write_string(out, s),
+        Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, value);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, value);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_number(out: &mut String, n: &Number) {
+    if let Some(i) = n.as_i64() {
+        let _ = write!(out, "{i}");
+    } else if let Some(u) = n.as_u64() {
+        let _ = write!(out, "{u}");
+    } else if let Some(f) = n.as_f64() {
+        if f.fract() == 0.0 && f.abs() <= MAX_SAFE_INTEGER {
+            // also turns -0 into 0
+            let _ = write!(out, "{}", f as i64);
+        } else if f.abs() >= 1e21 || f.abs() < 1e-6 {
+            // the shortest representation that round-trips, in exponential
+            // notation with an explicitly signed exponent, like ECMAScript
+            let formatted = format!("{f:e}");
+            match formatted.split_once('e') {
+                Some((mantissa, exponent)) if !exponent.starts_with('-') => {
+                    let _ = write!(out, "{mantissa}e+{exponent}");
+                }
+                _ => out.push_str(&formatted),
+            }
+        } else {
+            // the shortest representation that round-trips
+            let _ = write!(out, "{f}");
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    // serde_json escapes deterministically: only what must be escaped, and
+    // control characters without a short escape as \u00XX
+    match serde_json::to_string(s) {
+        Ok(escaped) => out.push_str(&escaped),
+        Err(_) => out.push_str("\"\""),
+    }
+}
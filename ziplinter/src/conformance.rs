@@ -0,0 +1,153 @@
+//! Spec legality: whether an archive meets the requirements of the zip
+//! specification (APPNOTE.TXT), as opposed to whether it looks suspicious.
+//! Many findings are about archives that are perfectly legal, and the other
+//! way around.
+
+use rc_zip::parse::{EndOfCentralDirectory, Method};
+
+use crate::{
+    findings::{FLAG_DATA_DESCRIPTOR, FLAG_ENCRYPTED},
+    FileMetadata, Finding,
+};
+
+/// Findings that always mean a requirement of the spec is violated, with
+/// the requirement
+const VIOLATING_FINDINGS: &[(&str, &str)] = &[
+    ("header_data_overlap", "entries must not overlap"),
+    (
+        "overruns_central_directory",
+        "file data must end before the central directory",
+    ),
+    (
+        "name_length_mismatch",
+        "the local and central directory headers must store the same file name",
+    ),
+    (
+        "measured_size_mismatch",
+        "declared sizes must match the file data",
+    ),
+    (
+        "empty_directory_inconsistent",
+        "the end of central directory record must record the size and offset of the central directory",
+    ),
+    (
+        "directory_size_sum_mismatch",
+        "the end of central directory record must record the size of the central directory",
+    ),
+    ("zero_crc_nonempty", "the CRC-32 must match the file data"),
+    (
+        "encryption_flag_inconsistent",
+        "the encryption flag, method and extra field must agree",
+    ),
+    (
+        "aex_length_inconsistent",
+        "AE-x encrypted data must hold the salt, password verification value and authentication code",
+    ),
+];
+
+/// Whether `method` is a compression method defined by 4.4.5, rather than a
+/// reserved or unassigned value
+fn is_defined_method(method: Method) -> bool {
+    match method {
+        Method::Unrecognized(code) => matches!(code, 1..=10 | 16 | 18..=20),
+        _ => true,
+    }
+}
+
+/// Lists the requirements of the spec that the archive violates, each as
+/// `requirement: detail`. The archive is strictly conformant if there are none.
+pub(crate) fn violations(
+    eocd: &EndOfCentralDirectory<'_>,
+    contents: &[FileMetadata],
+    findings: &[Finding],
+) -> Vec<String> {
+    let mut violations = eocd_violations(eocd, contents.len());
+    violations.extend(contents.iter().flat_map(entry_violations));
+    violations.extend(findings.iter().filter_map(finding_violation));
+    violations
+}
+
+/// The requirements the end of central directory records violate, for a
+/// central directory of `entries` entries
+pub(crate) fn eocd_violations(eocd: &EndOfCentralDirectory<'_>, entries: usize) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    let records = eocd.directory_records();
+    if records != entries as u64 {
+        violations.push(format!(
+            "the end of central directory record must count the entries in the central directory: it counts {records}, but there are {entries}"
+        ));
+    }
+    let records_this_disk = match &eocd.dir64 {
+        Some(dir64) => dir64.inner.dir_records_this_disk,
+        None => eocd.dir.inner.dir_records_this_disk as u64,
+    };
+    if records_this_disk != records {
+        violations.push(format!(
+            "a single-disk archive must count all entries as being on this disk: {records_this_disk} of {records} are"
+        ));
+    }
+    violations
+}
+
+/// The requirements the headers of an entry violate
+pub(crate) fn entry_violations(file: &FileMetadata) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    let central = &file.central;
+    if central.name.contains('\\') {
+        violations.push(format!(
+            "4.4.17.1: file names must only use forward slashes: \"{}\"",
+            central.name
+        ));
+    }
+    if !is_defined_method(central.method) {
+        violations.push(format!(
+            "4.4.5: compression method must be defined: \"{}\" uses {:?}",
+            central.name, central.method
+        ));
+    }
+
+    let local = match &file.local {
+        Ok(local) => local,
+        // without the password, failing to read encrypted data is expected
+        Err(_) if central.flags & FLAG_ENCRYPTED != 0 => return violations,
+        Err(error) => {
+            violations.push(format!(
+                "each entry must have a valid local file header and data: \"{}\": {}",
+                central.name, error.error
+            ));
+            return violations;
+        }
+    };
+    if local.method != central.method {
+        violations.push(format!(
+            "the local and central directory headers must agree on the compression method: \"{}\" has {:?} and {:?}",
+            central.name, local.method, central.method
+        ));
+    }
+    // non-ASCII names may be decoded differently in each header, e.g. due to the UTF-8 flag
+    let comparable = local.name.is_ascii() && central.name.is_ascii();
+    if comparable && local.name != central.name && local.name_length == central.name_length {
+        violations.push(format!(
+            "the local and central directory headers must store the same file name: \"{}\" and \"{}\"",
+            local.name, central.name
+        ));
+    }
+    let deferred = local.flags & FLAG_DATA_DESCRIPTOR != 0 && local.crc32 == 0;
+    if !deferred && local.crc32 != central.crc32 {
+        violations.push(format!(
+            "the local and central directory headers must agree on the CRC-32: \"{}\" has {:#010x} and {:#010x}",
+            central.name, local.crc32, central.crc32
+        ));
+    }
+    violations
+}
+
+/// The requirement `finding` means is violated, if any
+pub(crate) fn finding_violation(finding: &Finding) -> Option<String> {
+    VIOLATING_FINDINGS
+        .iter()
+        .find(|(code, _)| *code == finding.code)
+        .map(|(_, requirement)| format!("{requirement}: {}", finding.message))
+}
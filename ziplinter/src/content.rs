@@ -0,0 +1,181 @@
+//! Heuristics over the (decompressed) content of entries.
+
+use std::io::Read;
+
+use rc_zip_sync::HasCursor;
+
+/// How many bytes of each entry's decompressed content are kept for analysis
+pub(crate) const CONTENT_SAMPLE_LENGTH: usize = 4096;
+
+/// How many bytes of content [detected_type] looks at, enough for the
+/// longest signature
+pub(crate) const SIGNATURE_LENGTH: usize = 8;
+
+/// Upper bound on the content preview of each entry
+pub(crate) const MAX_CONTENT_PREVIEW_LENGTH: usize = 64 * 1024;
+
+/// Signatures of formats that are already compressed (or otherwise
+/// incompressible), and are therefore expected to have a high entropy.
+const COMPRESSED_FORMATS: &[&[u8]] = &[
+    b"\x89PNG\r\n\x1a\n",
+    b"\xff\xd8\xff",
+    b"GIF8",
+    b"PK\x03\x04",
+    b"\x1f\x8b",
+    b"BZh",
+    b"\xfd7zXZ\x00",
+    b"\x28\xb5\x2f\xfd",
+    b"7z\xbc\xaf\x27\x1c",
+    b"Rar!\x1a\x07",
+];
+
+/// Shannon entropy of `data`, in bits per byte (between 0 and 8)
+pub(crate) fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+
+    let mut counts = [0u64; 256];
+    for &b in data {
+        counts[b as usize] += 1;
+    }
+
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Whether `data` starts with the signature of an already-compressed format
+pub(crate) fn is_compressed_format(data: &[u8]) -> bool {
+    COMPRESSED_FORMATS
+        .iter()
+        .any(|magic| data.starts_with(magic))
+}
+
+/// Signatures of formats worth recognizing, by name
+const DETECTED_TYPES: &[(&[u8], &str)] = &[
+    (b"MZ", "PE executable"),
+    (b"\x7fELF", "ELF"),
+    (b"\xfe\xed\xfa\xce", "Mach-O"),
+    (b"\xfe\xed\xfa\xcf", "Mach-O"),
+    (b"\xce\xfa\xed\xfe", "Mach-O"),
+    (b"\xcf\xfa\xed\xfe", "Mach-O"),
+    (b"#!", "script"),
+    (b"\x89PNG\r\n\x1a\n", "PNG"),
+    (b"\xff\xd8\xff", "JPEG"),
+    (b"GIF8", "GIF"),
+    (b"%PDF-", "PDF"),
+    (b"PK\x03\x04", "zip"),
+    (b"PK\x05\x06", "zip"),
+    (b"\x1f\x8b", "gzip"),
+    (b"BZh", "bzip2"),
+    (b"\xfd7zXZ\x00", "xz"),
+    (b"\x28\xb5\x2f\xfd", "zstd"),
+    (b"7z\xbc\xaf\x27\x1c", "7z"),
+    (b"Rar!\x1a\x07", "RAR"),
+];
+
+/// Name of the format `data` is in, as recognized by its magic
+pub(crate) fn detected_type(data: &[u8]) -> Option<&'static str> {
+    DETECTED_TYPES
+        .iter()
+        .find(|(magic, _)| data.starts_with(magic))
+        .map(|(_, name)| *name)
+}
+
+/// Formats that files with these extensions are in. Plain text formats have no
+/// magic, so they're expected to have no detected type at all.
+const EXTENSION_TYPES: &[(&[&str], Option<&str>)] = &[
+    (
+        &["exe", "dll", "sys", "scr", "efi", "ocx", "cpl"],
+        Some("PE executable"),
+    ),
+    (&["so", "elf"], Some("ELF")),
+    (&["png"], Some("PNG")),
+    (&["jpg", "jpeg"], Some("JPEG")),
+    (&["gif"], Some("GIF")),
+    (&["pdf"], Some("PDF")),
+    (
+        &[
+            "zip", "jar", "apk", "docx", "xlsx", "pptx", "odt", "ods", "odp", "epub",
+        ],
+        Some("zip"),
+    ),
+    (&["gz", "tgz"], Some("gzip")),
+    (&["bz2"], Some("bzip2")),
+    (&["xz"], Some("xz")),
+    (&["zst"], Some("zstd")),
+    (&["7z"], Some("7z")),
+    (&["rar"], Some("RAR")),
+    (
+        &[
+            "txt", "md", "csv", "json", "xml", "html", "htm", "css", "js", "yml", "yaml",
+        ],
+        None,
+    ),
+];
+
+/// If the extension of `name` says what format the file is in, and
+/// `detected` contradicts that, returns the extension
+pub(crate) fn extension_mismatch<'a>(name: &'a str, detected: &str) -> Option<&'a str> {
+    let file_name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let (_, extension) = file_name.rsplit_once('.')?;
+    let (_, expected) = EXTENSION_TYPES.iter().find(|(extensions, _)| {
+        extensions
+            .iter()
+            .any(|candidate| candidate.eq_ignore_ascii_case(extension))
+    })?;
+    // a shebang doesn't make a text file any less of a text file
+    let plausible = *expected == Some(detected) || (expected.is_none() && detected == "script");
+    (!plausible).then_some(extension)
+}
+
+/// Lowercase hex representation of `data`
+pub(crate) fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Size of the fixed part of a local file header, up to the file name
+pub(crate) const LOCAL_HEADER_FIXED_LENGTH: u64 = 30;
+
+/// Reads the fixed part of the local file header at `header_offset`, and
+/// returns the offset of the data that follows it, along with the compressed
+/// size it declares
+pub(crate) fn data_offset<F: HasCursor>(file: &F, header_offset: u64) -> Option<(u64, u32)> {
+    let mut header = [0u8; LOCAL_HEADER_FIXED_LENGTH as usize];
+    file.cursor_at(header_offset).read_exact(&mut header).ok()?;
+    let name_length = u16::from_le_bytes([header[26], header[27]]) as u64;
+    let extra_length = u16::from_le_bytes([header[28], header[29]]) as u64;
+    let compressed_size = u32::from_le_bytes([header[18], header[19], header[20], header[21]]);
+    Some((
+        header_offset + LOCAL_HEADER_FIXED_LENGTH + name_length + extra_length,
+        compressed_size,
+    ))
+}
+
+/// Reads up to `length` bytes of the raw (still compressed) data of the
+/// entry whose local file header is at `header_offset`
+pub(crate) fn raw_data_prefix<F: HasCursor>(
+    file: &F,
+    header_offset: u64,
+    length: usize,
+) -> Option<Vec<u8>> {
+    let (data_offset, compressed_size) = data_offset(file, header_offset)?;
+    // without a data descriptor, the data ends where the local header says
+    let length = match compressed_size {
+        0 | u32::MAX => length as u64,
+        compressed_size => (length as u64).min(compressed_size as u64),
+    };
+    let mut data = Vec::new();
+    file.cursor_at(data_offset)
+        .take(length)
+        .read_to_end(&mut data)
+        .ok()?;
+    Some(data)
+}
@@ -0,0 +1,126 @@
+//! Statistics over the reports of many archives, e.g. all artifacts of a
+//! project, so that trends stand out that no single report shows.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde_json::Value;
+
+/// How many of the most common suspicious names are kept
+const MAX_SUSPICIOUS_NAMES: usize = 20;
+
+/// How often an entry name occurs in the corpus
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NameCount {
+    /// The entry name
+    pub name: String,
+
+    /// Number of entries with this name
+    pub count: u64,
+}
+
+/// Aggregated statistics over the reports of many archives
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorpusReport {
+    /// Number of archives
+    pub archives: u64,
+
+    /// Archives that couldn't be parsed at all, with the error
+    pub unparseable: BTreeMap<PathBuf, String>,
+
+    /// Number of entries over all archives
+    pub total_entries: u64,
+
+    /// Number of entries per compression method, from the central directory
+    pub methods: BTreeMap<String, u64>,
+
+    /// Number of archives with at least one finding of each code
+    pub archives_by_finding: BTreeMap<String, u64>,
+
+    /// Names of entries with warnings or errors, most common first
+    pub suspicious_names: Vec<NameCount>,
+}
+
+impl CorpusReport {
+    /// Combines reports, as returned by [crate::parse_file], each with the
+    /// path of the archive it is about.
+    pub fn merge(results: impl IntoIterator<Item = (PathBuf, Value)>) -> Self {
+        let mut corpus = CorpusReport::default();
+        let mut suspicious_names: BTreeMap<String, u64> = BTreeMap::new();
+
+        for (path, report) in results {
+            corpus.archives += 1;
+            if let Some(error) = report["error"].as_str() {
+                corpus.unparseable.insert(path, error.to_string());
+                continue;
+            }
+
+            let contents = report["contents"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            corpus.total_entries += contents.len() as u64;
+            for file in contents {
+                if let Some(method) = method_name(&file["central"]["method"]) {
+                    *corpus.methods.entry(method).or_default() += 1;
+                }
+            }
+
+            let findings = report["findings"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let mut codes: Vec<&str> = findings
+                .iter()
+                .filter_map(|finding| finding["code"].as_str())
+                .collect();
+            codes.sort_unstable();
+            codes.dedup();
+            for code in codes {
+                *corpus
+                    .archives_by_finding
+                    .entry(code.to_string())
+                    .or_default() += 1;
+            }
+
+            let mut entries: Vec<u64> = findings
+                .iter()
+                .filter(|finding| finding["severity"] != "info")
+                .filter_map(|finding| finding["entry"].as_u64())
+                .collect();
+            entries.sort_unstable();
+            entries.dedup();
+            for entry in entries {
+                if let Some(name) = contents
+                    .get(entry as usize)
+                    .and_then(|file| file["central"]["name"].as_str())
+                {
+                    *suspicious_names.entry(name.to_string()).or_default() += 1;
+                }
+            }
+        }
+
+        let mut suspicious_names: Vec<NameCount> = suspicious_names
+            .into_iter()
+            .map(|(name, count)| NameCount { name, count })
+            .collect();
+        // stable sort, so names with the same count stay in alphabetical order
+        suspicious_names.sort_by_key(|name| std::cmp::Reverse(name.count));
+        suspicious_names.truncate(MAX_SUSPICIOUS_NAMES);
+        corpus.suspicious_names = suspicious_names;
+
+        corpus
+    }
+}
+
+/// Name of a serialized [rc_zip::parse::Method], e.g. `Deflate`, or
+/// `Unrecognized(7)` for methods this crate doesn't know
+fn method_name(method: &Value) -> Option<String> {
+    match method {
+        Value::String(name) => Some(name.clone()),
+        Value::Object(map) => map
+            .iter()
+            .next()
+            .map(|(name, code)| format!("{name}({code})")),
+        _ => None,
+    }
+}
@@ -0,0 +1,264 @@
+//! Drift between two scans of what should be the same archive, e.g. a
+//! published artifact scanned on every release-monitoring run: a tampered
+//! re-upload shows up as changed entries or new findings.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fs::File,
+    io::{self, Read},
+};
+
+use rc_zip_sync::HasCursor;
+use serde_json::Value;
+
+/// A finding, without the offset and entry index that may shift between scans
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DiffFinding {
+    /// The finding's code, e.g. `header_data_overlap`
+    pub code: String,
+
+    /// The finding's severity
+    pub severity: String,
+
+    /// The finding's message
+    pub message: String,
+}
+
+/// What one report has that the other doesn't
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffSide {
+    /// Names of entries
+    pub entries: Vec<String>,
+
+    /// Findings, compared by code, severity and message
+    pub findings: Vec<DiffFinding>,
+}
+
+/// An entry that is in both reports, but with different content
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EntryChange {
+    /// The entry's name
+    pub name: String,
+
+    /// CRC-32 in the old report
+    pub old_crc32: u64,
+
+    /// CRC-32 in the new report
+    pub new_crc32: u64,
+
+    /// Uncompressed size in the old report
+    pub old_uncompressed_size: u64,
+
+    /// Uncompressed size in the new report
+    pub new_uncompressed_size: u64,
+}
+
+/// The differences between two reports, see [diff_reports]
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportDiff {
+    /// Entries and findings that are only in the new report
+    pub added: DiffSide,
+
+    /// Entries and findings that are only in the old report
+    pub removed: DiffSide,
+
+    /// Entries in both reports whose CRC-32 or uncompressed size changed
+    pub modified: Vec<EntryChange>,
+}
+
+impl ReportDiff {
+    /// Whether the reports describe the same entries with the same findings
+    pub fn is_empty(&self) -> bool {
+        *self == ReportDiff::default()
+    }
+}
+
+/// Central directory headers of a report by name. Entries with the same name
+/// are kept in order, so they're compared in order.
+fn entries_by_name(report: &Value) -> BTreeMap<String, Vec<&Value>> {
+    let mut entries: BTreeMap<String, Vec<&Value>> = BTreeMap::new();
+    for file in report["contents"].as_array().into_iter().flatten() {
+        let central = &file["central"];
+        if let Some(name) = central["name"].as_str() {
+            entries.entry(name.to_string()).or_default().push(central);
+        }
+    }
+    entries
+}
+
+/// Number of occurrences of each finding of a report
+fn finding_counts(report: &Value) -> BTreeMap<DiffFinding, usize> {
+    let mut findings = BTreeMap::new();
+    for finding in report["findings"].as_array().into_iter().flatten() {
+        let field = |name: &str| finding[name].as_str().unwrap_or_default().to_string();
+        let finding = DiffFinding {
+            code: field("code"),
+            severity: field("severity"),
+            message: field("message"),
+        };
+        *findings.entry(finding).or_default() += 1;
+    }
+    findings
+}
+
+/// Compares two reports, as returned by [crate::parse_file], of what should
+/// be the same archive. Entries are matched by name, findings by code,
+/// severity and message, so that shifted offsets alone don't count as drift.
+pub fn diff_reports(old: &Value, new: &Value) -> ReportDiff {
+    let mut diff = ReportDiff::default();
+
+    let old_entries = entries_by_name(old);
+    let new_entries = entries_by_name(new);
+    let names: BTreeSet<&String> = old_entries.keys().chain(new_entries.keys()).collect();
+    for name in names {
+        let old_headers = old_entries.get(name).map(Vec::as_slice).unwrap_or_default();
+        let new_headers = new_entries.get(name).map(Vec::as_slice).unwrap_or_default();
+        for (old, new) in old_headers.iter().zip(new_headers) {
+            let number = |header: &Value, field: &str| header[field].as_u64().unwrap_or_default();
+            let change = EntryChange {
+                name: name.clone(),
+                old_crc32: number(old, "crc32"),
+                new_crc32: number(new, "crc32"),
+                old_uncompressed_size: number(old, "uncompressed_size"),
+                new_uncompressed_size: number(new, "uncompressed_size"),
+            };
+            if change.old_crc32 != change.new_crc32
+                || change.old_uncompressed_size != change.new_uncompressed_size
+            {
+                diff.modified.push(change);
+            }
+        }
+        for _ in new_headers.len()..old_headers.len() {
+            diff.removed.entries.push(name.clone());
+        }
+        for _ in old_headers.len()..new_headers.len() {
+            diff.added.entries.push(name.clone());
+        }
+    }
+
+    let old_findings = finding_counts(old);
+    let new_findings = finding_counts(new);
+    for (finding, count) in &new_findings {
+        let old_count = old_findings.get(finding).copied().unwrap_or_default();
+        for _ in old_count..*count {
+            diff.added.findings.push(finding.clone());
+        }
+    }
+    for (finding, count) in &old_findings {
+        let new_count = new_findings.get(finding).copied().unwrap_or_default();
+        for _ in new_count..*count {
+            diff.removed.findings.push(finding.clone());
+        }
+    }
+
+    diff
+}
+
+/// Where two archives first differ byte for byte, see [first_divergence]
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Offset of the first byte that differs. If one archive is a prefix of
+    /// the other, this is the size of the shorter one.
+    pub offset: u64,
+
+    /// The parsed structure that byte is part of, e.g. `central directory
+    /// header`, if any
+    pub structure: Option<String>,
+
+    /// Offset at which that structure starts
+    pub structure_offset: Option<u64>,
+
+    /// Name of the entry the structure belongs to, if any
+    pub filename: Option<String>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.structure, &self.filename) {
+            (Some(structure), Some(filename)) => write!(f, "{structure} for \"{filename}\"")?,
+            (Some(structure), None) => write!(f, "{structure}")?,
+            (None, _) => write!(f, "unparsed bytes")?,
+        }
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+/// Size of the chunks both archives are compared in
+const COMPARE_CHUNK_LENGTH: usize = 64 * 1024;
+
+/// Compares two archives byte for byte, e.g. a download against a known-good
+/// copy, and returns where they first differ, or `None` if they're
+/// identical. That offset is looked up in the parsed ranges of `reference`
+/// (or, past its end, those of `other`) to tell which structure changed
+/// first, e.g. the central directory header of one entry.
+pub fn first_divergence(reference: &File, other: &File) -> io::Result<Option<Divergence>> {
+    let Some(offset) = first_differing_offset(reference, other)? else {
+        return Ok(None);
+    };
+
+    let structure = [reference, other]
+        .into_iter()
+        .find_map(|file| innermost_range(&crate::parse_file(file), offset).cloned());
+    Ok(Some(Divergence {
+        offset,
+        structure: structure
+            .as_ref()
+            .and_then(|range| range["contains"].as_str())
+            .map(str::to_string),
+        structure_offset: structure.as_ref().and_then(|range| range["start"].as_u64()),
+        filename: structure
+            .as_ref()
+            .and_then(|range| range["filename"].as_str())
+            .map(str::to_string),
+    }))
+}
+
+fn first_differing_offset(a: &File, b: &File) -> io::Result<Option<u64>> {
+    let (mut a, mut b) = (a.cursor_at(0), b.cursor_at(0));
+    let mut a_chunk = vec![0u8; COMPARE_CHUNK_LENGTH];
+    let mut b_chunk = vec![0u8; COMPARE_CHUNK_LENGTH];
+    let mut offset = 0;
+    loop {
+        let a_length = read_chunk(&mut a, &mut a_chunk)?;
+        let b_length = read_chunk(&mut b, &mut b_chunk)?;
+        let common = a_length.min(b_length);
+        if let Some(position) = a_chunk[..common]
+            .iter()
+            .zip(&b_chunk[..common])
+            .position(|(a, b)| a != b)
+        {
+            return Ok(Some(offset + position as u64));
+        }
+        if a_length != b_length {
+            return Ok(Some(offset + common as u64));
+        }
+        if a_length == 0 {
+            return Ok(None);
+        }
+        offset += common as u64;
+    }
+}
+
+/// Fills `chunk` as far as possible, only returning less at the end of the file
+fn read_chunk(reader: &mut impl Read, chunk: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < chunk.len() {
+        match reader.read(&mut chunk[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+/// The smallest of the `parsed_ranges` of `report` that contains `offset`
+fn innermost_range(report: &Value, offset: u64) -> Option<&Value> {
+    report["parsed_ranges"]
+        .as_array()?
+        .iter()
+        .filter_map(|range| Some((range, range["start"].as_u64()?, range["end"].as_u64()?)))
+        .filter(|(_, start, end)| (*start..*end).contains(&offset))
+        .min_by_key(|(_, start, end)| end - start)
+        .map(|(range, _, _)| range)
+}
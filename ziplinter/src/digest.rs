@@ -0,0 +1,85 @@
+//! Cryptographic digests of the decompressed content of entries, for
+//! deduplication and for matching against hashes of known files, which the
+//! CRC-32 of an entry is too weak for.
+
+use sha1::Digest;
+
+use crate::content::to_hex;
+
+/// The hash function [crate::ParseOptions::digest] computes digests with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    /// SHA-256, what threat intelligence feeds mostly use
+    Sha256,
+
+    /// SHA-1, for matching against older hash sets
+    Sha1,
+
+    /// BLAKE3, much faster than the SHA family on large archives
+    Blake3,
+}
+
+impl std::str::FromStr for DigestAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(DigestAlgorithm::Sha256),
+            "sha1" => Ok(DigestAlgorithm::Sha1),
+            "blake3" => Ok(DigestAlgorithm::Blake3),
+            _ => Err(format!(
+                "unknown digest algorithm \"{s}\", expected sha256, sha1 or blake3"
+            )),
+        }
+    }
+}
+
+/// The digest of an entry's decompressed content, as lowercase hex. In the
+/// report, it's a property named after the algorithm, e.g. `sha256`.
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryDigest {
+    /// SHA-256
+    Sha256(String),
+
+    /// SHA-1
+    Sha1(String),
+
+    /// BLAKE3
+    Blake3(String),
+}
+
+/// Computes an [EntryDigest] over data fed to it in chunks
+pub(crate) enum Hasher {
+    Sha256(sha2::Sha256),
+    Sha1(sha1::Sha1),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub(crate) fn new(algorithm: DigestAlgorithm) -> Self {
+        match algorithm {
+            DigestAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            DigestAlgorithm::Sha1 => Hasher::Sha1(sha1::Sha1::new()),
+            DigestAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> EntryDigest {
+        match self {
+            Hasher::Sha256(hasher) => EntryDigest::Sha256(to_hex(&hasher.finalize())),
+            Hasher::Sha1(hasher) => EntryDigest::Sha1(to_hex(&hasher.finalize())),
+            Hasher::Blake3(hasher) => EntryDigest::Blake3(to_hex(hasher.finalize().as_bytes())),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Stable identifiers of entries, so that findings and external annotations
+//! can refer to an entry without relying on its index in `contents`, which
+//! shifts as soon as an entry is added or removed before it.
+
+use std::collections::BTreeMap;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// The identifier of the entry named `name` whose local file header is at
+/// `header_offset`: the 64-bit FNV-1a hash of the UTF-8 name followed by the
+/// offset as 8 little-endian bytes, as 16 hex digits.
+///
+/// This is what [crate::parse_file] reports as the `id` of an entry, unless
+/// several entries share it, see [EntryIds].
+pub fn entry_id(name: &str, header_offset: u64) -> String {
+    let hash = name
+        .as_bytes()
+        .iter()
+        .chain(&header_offset.to_le_bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+        });
+    format!("{hash:016x}")
+}
+
+/// Hands out the identifiers of the entries of an archive, in the order of
+/// the central directory. Entries with the same name and header offset, e.g.
+/// duplicate central directory headers, would get the same [entry_id]: the
+/// second one gets `-2` appended, the third one `-3` and so on.
+#[derive(Default)]
+pub(crate) struct EntryIds {
+    seen: BTreeMap<String, usize>,
+}
+
+impl EntryIds {
+    pub(crate) fn next(&mut self, name: &str, header_offset: u64) -> String {
+        let id = entry_id(name, header_offset);
+        let count = self.seen.entry(id.clone()).or_default();
+        *count += 1;
+        match *count {
+            1 => id,
+            count => format!("{id}-{count}"),
+        }
+    }
+}
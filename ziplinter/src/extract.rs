@@ -0,0 +1,424 @@
+//! Extraction of a whole archive, either into memory for scripts and tests
+//! that just want the files, or into a directory without ever writing
+//! outside of it, or as a stream of chunks for hashing or scanning.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use rc_zip::parse::{Entry, EntryKind};
+use rc_zip_sync::{EntryHandle, HasCursor, ReadZip};
+
+use crate::{findings::FLAG_ENCRYPTED, Finding, Severity};
+
+/// Limits on how much [extract_all] decompresses
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    /// Maximum number of decompressed bytes over all entries. Exceeding it
+    /// aborts the extraction with [ExtractError::BudgetExceeded].
+    pub max_total_bytes: u64,
+
+    /// Maximum number of decompressed bytes of a single entry. Larger entries
+    /// are skipped and reported as an `entry_too_large` finding.
+    pub max_entry_bytes: u64,
+
+    /// Whether [extract_to_dir] creates symbolic links. Even then, links
+    /// pointing outside of the destination are skipped. Ignored on platforms
+    /// without symbolic links.
+    pub allow_symlinks: bool,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: 256 * 1024 * 1024,
+            max_entry_bytes: 64 * 1024 * 1024,
+            allow_symlinks: false,
+        }
+    }
+}
+
+/// The outcome of [extract_all]
+#[derive(Debug, Clone, Default)]
+pub struct Extracted {
+    /// Decompressed content of each file, keyed by entry name. Of entries
+    /// with the same name, the last one wins.
+    pub files: BTreeMap<String, Vec<u8>>,
+
+    /// Entries that were skipped or overwritten. The `entry` of each finding
+    /// is the index of the entry in the central directory.
+    pub findings: Vec<Finding>,
+}
+
+/// The outcome of [extract_to_dir]
+#[derive(Debug, Clone, Default)]
+pub struct ExtractedToDir {
+    /// Files, directories and symbolic links that were written, relative to
+    /// the destination, in the order of the central directory
+    pub paths: Vec<PathBuf>,
+
+    /// Entries that were skipped, and why. The `entry` of each finding is
+    /// the index of the entry in the central directory.
+    pub findings: Vec<Finding>,
+}
+
+/// Why [extract_all] or [extract_to_dir] failed
+#[derive(Debug)]
+pub enum ExtractError {
+    /// The archive couldn't be read, or an entry couldn't be decompressed
+    Zip(rc_zip::error::Error),
+
+    /// The entries decompress to more than [ExtractOptions::max_total_bytes]
+    BudgetExceeded {
+        /// The budget that was exceeded
+        max_total_bytes: u64,
+    },
+}
+
+impl std::fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractError::Zip(error) => error.fmt(f),
+            ExtractError::BudgetExceeded { max_total_bytes } => write!(
+                f,
+                "entries decompress to more than {max_total_bytes} bytes in total"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExtractError::Zip(error) => Some(error),
+            ExtractError::BudgetExceeded { .. } => None,
+        }
+    }
+}
+
+impl From<rc_zip::error::Error> for ExtractError {
+    fn from(error: rc_zip::error::Error) -> Self {
+        ExtractError::Zip(error)
+    }
+}
+
+impl From<std::io::Error> for ExtractError {
+    fn from(error: std::io::Error) -> Self {
+        ExtractError::Zip(error.into())
+    }
+}
+
+fn encrypted_skipped(index: usize, name: &str) -> Finding {
+    Finding::new(
+        "encrypted_entry_skipped",
+        Severity::Info,
+        format!("\"{name}\" is encrypted and was not extracted"),
+    )
+    .for_entry(index)
+}
+
+fn too_large_skipped(index: usize, name: &str, opts: &ExtractOptions) -> Finding {
+    Finding::new(
+        "entry_too_large",
+        Severity::Warning,
+        format!(
+            "\"{name}\" decompresses to more than {} bytes and was not extracted",
+            opts.max_entry_bytes
+        ),
+    )
+    .for_entry(index)
+}
+
+/// Decompresses `entry`, adding its size to `total`. Returns `None` if it
+/// is larger than [ExtractOptions::max_entry_bytes].
+fn read_entry<F: HasCursor>(
+    entry: &EntryHandle<'_, F>,
+    opts: &ExtractOptions,
+    total: &mut u64,
+) -> Result<Option<Vec<u8>>, ExtractError> {
+    // read one byte past the limit to tell whether it was exceeded
+    let remaining = opts.max_total_bytes - *total;
+    let limit = opts.max_entry_bytes.min(remaining);
+    let mut data = Vec::new();
+    entry
+        .reader()
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut data)?;
+    let length = data.len() as u64;
+
+    if length > opts.max_entry_bytes {
+        return Ok(None);
+    }
+    if length > remaining {
+        return Err(ExtractError::BudgetExceeded {
+            max_total_bytes: opts.max_total_bytes,
+        });
+    }
+    *total += length;
+    Ok(Some(data))
+}
+
+/// Decompresses every file in the archive into memory, keyed by name.
+///
+/// Directories are left out. Encrypted entries, and entries larger than
+/// [ExtractOptions::max_entry_bytes], are skipped with a finding. This is
+/// meant for small archives: the sizes are enforced while decompressing, so
+/// declared sizes can't be used to get around them.
+pub fn extract_all(file: &File, opts: &ExtractOptions) -> Result<Extracted, ExtractError> {
+    let archive = file.read_zip()?;
+    let mut extracted = Extracted::default();
+    let mut total: u64 = 0;
+
+    for (index, entry) in archive.entries().enumerate() {
+        if matches!(entry.kind(), EntryKind::Directory) {
+            continue;
+        }
+        if entry.flags & FLAG_ENCRYPTED != 0 {
+            extracted
+                .findings
+                .push(encrypted_skipped(index, &entry.name));
+            continue;
+        }
+
+        let Some(data) = read_entry(&entry, opts, &mut total)? else {
+            extracted
+                .findings
+                .push(too_large_skipped(index, &entry.name, opts));
+            continue;
+        };
+
+        if extracted.files.insert(entry.name.clone(), data).is_some() {
+            extracted.findings.push(
+                Finding::new(
+                    "duplicate_name",
+                    Severity::Warning,
+                    format!(
+                        "\"{}\" appears more than once, only the last copy was kept",
+                        entry.name
+                    ),
+                )
+                .for_entry(index),
+            );
+        }
+    }
+
+    Ok(extracted)
+}
+
+/// Size of the chunks [for_each_entry_data] hands out
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Decompresses every entry of the archive in turn, handing the data to `f`
+/// in chunks as it's decompressed, e.g. to hash or scan the contents of
+/// archives too large to extract. Nothing is kept in memory beyond the
+/// current chunk.
+///
+/// Entries are visited in the order of the central directory. Directories
+/// and empty files produce no chunks, and encrypted entries are skipped.
+/// Fails if an entry can't be decompressed or doesn't match its CRC-32, after
+/// `f` got the chunks decompressed up to that point.
+pub fn for_each_entry_data<F: FnMut(&Entry, &[u8])>(
+    file: &File,
+    mut f: F,
+) -> Result<(), rc_zip::error::Error> {
+    let archive = file.read_zip()?;
+    let mut chunk = vec![0; CHUNK_SIZE];
+    for entry in archive.entries() {
+        if entry.flags & FLAG_ENCRYPTED != 0 {
+            continue;
+        }
+        let mut reader = entry.reader();
+        loop {
+            match reader.read(&mut chunk)? {
+                0 => break,
+                n => f(&entry, &chunk[..n]),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Turns an entry name into a path relative to the destination. Returns
+/// `None` for absolute names, names with a drive or `..` component, and
+/// names without any component.
+fn relative_path(name: &str) -> Option<PathBuf> {
+    if name.starts_with(['/', '\\']) {
+        return None;
+    }
+    let mut path = PathBuf::new();
+    for component in name.split(['/', '\\']) {
+        match component {
+            "" | "." => continue,
+            ".." => return None,
+            // drive letters and NTFS alternate data streams, and names the
+            // file system would cut short
+            _ if component.contains([':', '\0']) => return None,
+            _ => path.push(component),
+        }
+    }
+    (path.components().next().is_some()).then_some(path)
+}
+
+/// Whether a symbolic link at `link` (relative to `dest`) pointing to
+/// `target` stays within `dest`.
+///
+/// A `..` component is only followed out of directories that exist on disk
+/// without a symbolic link in their path: going up out of a link resolves
+/// against wherever the link points to, and a directory can't be replaced
+/// by a link later on. Other components may go through links, which were
+/// all checked in the same way when they were created.
+fn link_stays_inside(dest: &Path, link: &Path, target: &str) -> bool {
+    if target.starts_with(['/', '\\']) {
+        return false;
+    }
+    // the link's own directory
+    let mut current = link.parent().map(Path::to_path_buf).unwrap_or_default();
+    for component in target.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if crosses_symlink(dest, &current) || !dest.join(&current).is_dir() {
+                    return false;
+                }
+                if !current.pop() {
+                    return false;
+                }
+            }
+            _ if component.contains(':') => return false,
+            _ => current.push(component),
+        }
+    }
+    true
+}
+
+/// Whether `path` (relative to `dest`) or any of its parents is a symbolic
+/// link on disk, so that writing to it could end up outside of `dest`
+fn crosses_symlink(dest: &Path, path: &Path) -> bool {
+    let mut current = dest.to_path_buf();
+    path.components().any(|component| {
+        current.push(component);
+        fs::symlink_metadata(&current).is_ok_and(|metadata| metadata.file_type().is_symlink())
+    })
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &str, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(_target: &str, _link: &Path) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// Extracts every entry of the archive into `dest`, which is created if it
+/// doesn't exist.
+///
+/// Nothing is ever written outside of `dest`: entries with absolute names or
+/// `..` components are skipped, and so are entries whose path would go
+/// through a symbolic link. Symbolic links are only created if
+/// [ExtractOptions::allow_symlinks] is set, and only if they point within
+/// `dest`, also when followed through the links extracted before them.
+/// Encrypted entries and entries larger than [ExtractOptions::max_entry_bytes]
+/// are skipped as in [extract_all]. Every skipped entry gets a finding. Of
+/// entries with the same name, the last one wins.
+pub fn extract_to_dir(
+    file: &File,
+    dest: &Path,
+    opts: &ExtractOptions,
+) -> Result<ExtractedToDir, ExtractError> {
+    let archive = file.read_zip()?;
+    let mut extracted = ExtractedToDir::default();
+    let mut total: u64 = 0;
+    fs::create_dir_all(dest)?;
+
+    for (index, entry) in archive.entries().enumerate() {
+        let skipped = |code, message: &str| {
+            Finding::new(
+                code,
+                Severity::Warning,
+                format!("\"{}\" {message}, and was not extracted", entry.name),
+            )
+            .for_entry(index)
+        };
+
+        let Some(path) = relative_path(&entry.name) else {
+            extracted.findings.push(skipped(
+                "unsafe_path_skipped",
+                "is absolute or refers to a parent directory",
+            ));
+            continue;
+        };
+        if crosses_symlink(dest, &path) {
+            extracted.findings.push(skipped(
+                "symlink_in_path_skipped",
+                "would be written through a symbolic link",
+            ));
+            continue;
+        }
+        if entry.flags & FLAG_ENCRYPTED != 0 {
+            extracted
+                .findings
+                .push(encrypted_skipped(index, &entry.name));
+            continue;
+        }
+
+        let kind = entry.kind();
+        if matches!(kind, EntryKind::Directory) {
+            fs::create_dir_all(dest.join(&path))?;
+            extracted.paths.push(path);
+            continue;
+        }
+
+        if matches!(kind, EntryKind::Symlink) && !opts.allow_symlinks {
+            extracted.findings.push(skipped(
+                "symlink_skipped",
+                "is a symbolic link, which isn't allowed",
+            ));
+            continue;
+        }
+
+        let Some(data) = read_entry(&entry, opts, &mut total)? else {
+            extracted
+                .findings
+                .push(too_large_skipped(index, &entry.name, opts));
+            continue;
+        };
+        let target = dest.join(&path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if matches!(kind, EntryKind::Symlink) {
+            let link_target = String::from_utf8_lossy(&data);
+            if !link_stays_inside(dest, &path, &link_target) {
+                extracted.findings.push(skipped(
+                    "symlink_skipped",
+                    &format!("is a symbolic link to \"{link_target}\", outside of the destination"),
+                ));
+                continue;
+            }
+            if fs::symlink_metadata(&target).is_ok() {
+                fs::remove_file(&target)?;
+            }
+            if let Err(error) = create_symlink(&link_target, &target) {
+                if error.kind() != std::io::ErrorKind::Unsupported {
+                    return Err(error.into());
+                }
+                extracted.findings.push(skipped(
+                    "symlink_skipped",
+                    "is a symbolic link, which this platform doesn't support",
+                ));
+                continue;
+            }
+        } else {
+            fs::write(&target, data)?;
+        }
+        extracted.paths.push(path);
+    }
+
+    Ok(extracted)
+}
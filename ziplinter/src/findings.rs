@@ -0,0 +1,1431 @@
+//! Findings: anomalies and spec violations detected while analyzing an archive.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+};
+
+use rc_zip::{
+    fsm::{ParsedRange, ParsedRanges},
+    parse::{EndOfCentralDirectory, EndOfCentralDirectoryRecord, Entry, ExtraAexField, Method},
+};
+use rc_zip_sync::HasCursor;
+
+use crate::{
+    content, CentralDirectoryFileHeader, FileMetadata, LocalFileHeader, ParseOptions,
+    PolyglotCandidate,
+};
+
+/// How serious a finding is
+#[derive(serde::Serialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Noteworthy, but not a problem in itself
+    #[default]
+    Info,
+
+    /// Unusual, and possibly an attempt at confusing zip readers
+    Warning,
+
+    /// The archive is malformed or likely malicious
+    Error,
+}
+
+impl Severity {
+    /// The severity as it's spelled in reports: `info`, `warning` or `error`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!(
+                "unknown severity \"{s}\", expected info, warning or error"
+            )),
+        }
+    }
+}
+
+/// Something noteworthy about the archive or one of its entries
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct Finding {
+    /// Stable identifier for this kind of finding, e.g. `encryption_flag_inconsistent`
+    pub code: &'static str,
+
+    /// How serious this finding is
+    pub severity: Severity,
+
+    /// Human-readable description of what was found
+    pub message: String,
+
+    /// Index into `contents` of the entry this finding is about, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<usize>,
+
+    /// Identifier of the entry this finding is about, if any, which unlike
+    /// the index doesn't change when other entries are added or removed. See
+    /// [crate::entry_id].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry_id: Option<String>,
+
+    /// Offset in the file this finding is about, if any. For findings about
+    /// an entry, this defaults to the offset of its local file header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+}
+
+impl Finding {
+    pub(crate) fn new(code: &'static str, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity,
+            message: message.into(),
+            entry: None,
+            entry_id: None,
+            offset: None,
+        }
+    }
+
+    pub(crate) fn for_entry(mut self, index: usize) -> Self {
+        self.entry = Some(index);
+        self
+    }
+
+    pub(crate) fn at(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+}
+
+/// How many pairs of overlapping parsed ranges are looked at, see
+/// [ParsedRanges::overlaps]
+pub(crate) const MAX_OVERLAPS: usize = 1000;
+
+/// 4.4.4 general purpose bit flag: Bit 0: If set, indicates that the file is encrypted.
+pub(crate) const FLAG_ENCRYPTED: u16 = 1 << 0;
+
+/// 4.4.4 general purpose bit flag: Bit 3: If set, the sizes and crc-32 in the
+/// local header are set to zero, and the real values follow the data.
+pub(crate) const FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+
+/// 4.4.4 general purpose bit flag: Bit 4: Reserved for use with method 8,
+/// for enhanced deflating.
+const FLAG_ENHANCED_DEFLATING: u16 = 1 << 4;
+
+/// 4.4.4 general purpose bit flag: Bit 5: If set, this indicates that the
+/// file is compressed patched data.
+const FLAG_COMPRESSED_PATCHED_DATA: u16 = 1 << 5;
+
+/// Flags that only specialized producers set, with what they mean
+const UNUSUAL_FLAGS: &[(u16, &str)] = &[
+    (FLAG_ENHANCED_DEFLATING, "bit 4 (enhanced deflating)"),
+    (
+        FLAG_COMPRESSED_PATCHED_DATA,
+        "bit 5 (compressed patched data)",
+    ),
+];
+
+/// Describes the [UNUSUAL_FLAGS] set in `flags`
+fn unusual_flags(flags: u16) -> Vec<&'static str> {
+    UNUSUAL_FLAGS
+        .iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, description)| *description)
+        .collect()
+}
+
+/// Traditional PKWARE encryption prepends a 12-byte header to the file data
+const TRADITIONAL_ENCRYPTION_HEADER_LENGTH: u64 = 12;
+
+/// Checks that the encryption flag, the method and the AE-x extra field agree
+/// with each other, as seen from a single header.
+fn encryption_inconsistencies(
+    flags: u16,
+    method: Method,
+    aex: Option<&ExtraAexField>,
+    compressed_size: u64,
+) -> Vec<String> {
+    let encrypted = flags & FLAG_ENCRYPTED != 0;
+    let mut problems = Vec::new();
+
+    if method == Method::Aex {
+        if !encrypted {
+            problems.push("method is AE-x (99), but the encryption flag (bit 0) is not set".into());
+        }
+        if aex.is_none() {
+            problems.push("method is AE-x (99), but there is no AE-x extra field (0x9901)".into());
+        }
+    } else if aex.is_some() {
+        problems.push(format!(
+            "has an AE-x extra field (0x9901), but method is {method:?} instead of AE-x (99)"
+        ));
+    } else if encrypted && compressed_size < TRADITIONAL_ENCRYPTION_HEADER_LENGTH {
+        problems.push(format!(
+            "encryption flag (bit 0) is set without AE-x, but {compressed_size} bytes of data is too small for a traditional encryption header"
+        ));
+    }
+
+    problems
+}
+
+/// Checks that the declared sizes of an AE-x entry leave room for the salt,
+/// password verification value and authentication code that surround the
+/// encrypted data, as seen from a single header.
+fn aex_length_inconsistency(
+    aex: &ExtraAexField,
+    compressed_size: u64,
+    uncompressed_size: u64,
+) -> Option<String> {
+    let Some(overhead) = aex.overhead() else {
+        return Some(format!(
+            "unknown AES strength {} in the AE-x extra field",
+            aex.mode
+        ));
+    };
+
+    if compressed_size < overhead {
+        return Some(format!(
+            "{compressed_size} bytes of data can't hold the {overhead} bytes of salt, password verification value and authentication code"
+        ));
+    }
+
+    // stored data doesn't change size when encrypted
+    let expected = uncompressed_size.saturating_add(overhead);
+    if Method::from(aex.compression_method) == Method::Store && compressed_size != expected {
+        return Some(format!(
+            "{uncompressed_size} bytes of stored data should take {expected} bytes once encrypted, but {compressed_size} are declared"
+        ));
+    }
+
+    None
+}
+
+/// Lists the fields the central directory header and local file header of an
+/// entry disagree on, each with both values. `central_entry` has the sizes
+/// of the central directory header, with those in the zip64 extra field in
+/// place of their placeholders. A zero CRC-32 or size in a local file header
+/// that defers them to a data descriptor isn't a discrepancy.
+pub(crate) fn discrepancies(
+    central_entry: &Entry,
+    central: &CentralDirectoryFileHeader,
+    local: &LocalFileHeader,
+) -> Vec<String> {
+    let deferred = local.flags & FLAG_DATA_DESCRIPTOR != 0;
+    let mut discrepancies = Vec::new();
+    if central.method != local.method {
+        discrepancies.push(format!(
+            "method: central={:?}, local={:?}",
+            central.method, local.method
+        ));
+    }
+    if central.crc32 != local.crc32 && !(deferred && local.crc32 == 0) {
+        discrepancies.push(format!(
+            "crc32: central={:#010x}, local={:#010x}",
+            central.crc32, local.crc32
+        ));
+    }
+    for (field, central_size, local_size) in [
+        (
+            "compressed_size",
+            central_entry.compressed_size,
+            local.compressed_size,
+        ),
+        (
+            "uncompressed_size",
+            central_entry.uncompressed_size,
+            local.uncompressed_size,
+        ),
+    ] {
+        if central_size != local_size && !(deferred && local_size == 0) {
+            discrepancies.push(format!(
+                "{field}: central={central_size}, local={local_size}"
+            ));
+        }
+    }
+    // the same bytes may be decoded differently in each header, e.g. due to the UTF-8 flag
+    if central.raw_name != local.raw_name {
+        discrepancies.push(format!(
+            "name: central={:?}, local={:?}",
+            central.name, local.name
+        ));
+    }
+    if central.flags != local.flags {
+        discrepancies.push(format!(
+            "flags: central={:#06x}, local={:#06x}",
+            central.flags, local.flags
+        ));
+    }
+    discrepancies
+}
+
+/// Compares the sizes measured while decompressing an entry with the sizes
+/// its headers declare. The CRC can still match when these disagree, e.g. for
+/// truncated data followed by a crafted data descriptor.
+fn measured_size_mismatches(
+    central: &CentralDirectoryFileHeader,
+    local: &LocalFileHeader,
+) -> Vec<String> {
+    let mut declared = Vec::new();
+    if central.compressed_size != u32::MAX {
+        declared.push((
+            "central directory header",
+            "compressed",
+            central.compressed_size as u64,
+            local.measured_compressed,
+        ));
+    }
+    if central.uncompressed_size != u32::MAX {
+        declared.push((
+            "central directory header",
+            "uncompressed",
+            central.uncompressed_size as u64,
+            local.measured_uncompressed,
+        ));
+    }
+    // with a data descriptor, the local header is allowed to leave sizes at zero
+    let deferred = local.flags & FLAG_DATA_DESCRIPTOR != 0;
+    if !(deferred && local.compressed_size == 0) {
+        declared.push((
+            "local file header",
+            "compressed",
+            local.compressed_size,
+            local.measured_compressed,
+        ));
+    }
+    if !(deferred && local.uncompressed_size == 0) {
+        declared.push((
+            "local file header",
+            "uncompressed",
+            local.uncompressed_size,
+            local.measured_uncompressed,
+        ));
+    }
+
+    // unless decrypted, the measured uncompressed size of AE-x entries is that of the ciphertext
+    let decrypted = local
+        .aex_data
+        .as_ref()
+        .is_some_and(|aex_data| aex_data.password_correct == Some(true));
+    let opaque = local.aex.is_some() && !decrypted;
+
+    declared
+        .into_iter()
+        .filter(|(_, kind, _, _)| !(*kind == "uncompressed" && opaque))
+        .filter(|(_, _, declared, measured)| declared != measured)
+        .map(|(header, kind, declared, measured)| {
+            format!("{header} declares {declared} {kind} bytes, but {measured} were measured")
+        })
+        .collect()
+}
+
+/// Whether a header declares a CRC-32 of zero for nonempty content, which
+/// real data almost never has. A data descriptor may supply the real CRC, and
+/// AE-2 deliberately zeroes it, so those are left alone.
+pub(crate) fn zero_crc_nonempty(
+    flags: u16,
+    crc32: u32,
+    uncompressed_size: u64,
+    aex: Option<&ExtraAexField>,
+) -> bool {
+    crc32 == 0
+        && uncompressed_size > 0
+        && flags & FLAG_DATA_DESCRIPTOR == 0
+        && aex.is_none_or(|aex| aex.version_number != 2)
+}
+
+/// Checks the Unicode Comment extra field against the comment in the header.
+/// Readers that use the field and readers that don't show different comments
+/// when these disagree.
+fn unicode_comment_problem(central: &CentralDirectoryFileHeader) -> Option<String> {
+    let field = central.unicode_comment_field.as_ref()?;
+    if field.version != 1 {
+        return Some(format!(
+            "Unicode Comment extra field (0x6375) has unsupported version {}",
+            field.version
+        ));
+    }
+    if !field.matches(&central.raw_comment) {
+        return Some(format!(
+            "Unicode Comment extra field (0x6375) was written for a header comment with CRC-32 {:#010x}, but the header comment has CRC-32 {:#010x}",
+            field.comment_crc32,
+            crc32fast::hash(&central.raw_comment)
+        ));
+    }
+    match std::str::from_utf8(&field.comment) {
+        Err(_) => Some("Unicode Comment extra field (0x6375) is not valid UTF-8".into()),
+        Ok(comment) if comment != central.comment => Some(format!(
+            "Unicode Comment extra field (0x6375) says {comment:?}, but the header comment decodes to {:?}",
+            central.comment
+        )),
+        Ok(_) => None,
+    }
+}
+
+/// Entropy (in bits per byte) above which stored content looks compressed or encrypted
+const HIGH_ENTROPY_THRESHOLD: f64 = 7.5;
+
+/// Below this many bytes, entropy isn't a meaningful signal
+const MIN_ENTROPY_SAMPLE_LENGTH: usize = 256;
+
+/// Flags stored entries whose content looks compressed or encrypted, even
+/// though it isn't in a well-known compressed format.
+fn stored_high_entropy(name: &str, local: &LocalFileHeader) -> Option<Finding> {
+    let sample = &local.content_sample[..local
+        .content_sample
+        .len()
+        .min(content::CONTENT_SAMPLE_LENGTH)];
+    if local.method != Method::Store
+        || local.flags & FLAG_ENCRYPTED != 0
+        || sample.len() < MIN_ENTROPY_SAMPLE_LENGTH
+        || content::is_compressed_format(sample)
+    {
+        return None;
+    }
+
+    let entropy = content::shannon_entropy(sample);
+    (entropy > HIGH_ENTROPY_THRESHOLD).then(|| {
+        Finding::new(
+            "stored_high_entropy",
+            Severity::Info,
+            format!(
+                "stored entry \"{name}\" has an entropy of {entropy:.2} bits per byte over its first {} bytes, but isn't in a known compressed format: it may be compressed or encrypted data mislabeled as stored",
+                sample.len()
+            ),
+        )
+    })
+}
+
+/// Names of devices that Windows resolves in any directory, whatever the extension
+const RESERVED_DEVICE_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "CONIN$", "CONOUT$", "COM1", "COM2", "COM3", "COM4", "COM5",
+    "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8",
+    "LPT9",
+];
+
+/// Returns the first path component of `name` that Windows treats as a
+/// device, e.g. `nul.txt` or `COM1`. Windows ignores the extension and
+/// trailing spaces when matching these.
+fn reserved_device_component(name: &str) -> Option<&str> {
+    name.split(['/', '\\']).find(|component| {
+        let stem = component.split('.').next().unwrap_or_default().trim_end();
+        RESERVED_DEVICE_NAMES
+            .iter()
+            .any(|device| device.eq_ignore_ascii_case(stem))
+    })
+}
+
+/// Number of path components of `name`, with either kind of slash as the
+/// separator and empty components (as in `a//b` or `dir/`) left out
+pub(crate) fn path_depth(name: &str) -> usize {
+    name.split(['/', '\\'])
+        .filter(|component| !component.is_empty())
+        .count()
+}
+
+/// Whether `name` starts with a drive letter, as in `C:\` or `c:file`
+fn has_drive_prefix(name: &str) -> bool {
+    matches!(name.as_bytes(), [letter, b':', ..] if letter.is_ascii_alphabetic())
+}
+
+/// Whether extracting an entry named `name` naively may write outside the
+/// destination: the name starts with a slash or a drive letter, or has a
+/// `..` component, with either kind of slash as the separator
+pub(crate) fn is_path_traversal(name: &str) -> bool {
+    name.starts_with(['/', '\\'])
+        || has_drive_prefix(name)
+        || name.split(['/', '\\']).any(|component| component == "..")
+}
+
+/// `name` the way `unzip` extracts it: without a drive letter, and without
+/// empty, `.` and `..` components, joined with forward slashes
+pub(crate) fn sanitized_name(name: &str) -> String {
+    let name = match has_drive_prefix(name) {
+        true => &name[2..],
+        false => name,
+    };
+    name.split(['/', '\\'])
+        .filter(|component| !matches!(*component, "" | "." | ".."))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Deflate block type 3 is reserved, and an error for any decoder
+const DEFLATE_RESERVED_BLOCK_TYPE: u8 = 0b11;
+
+/// Reports a Deflate entry whose data couldn't be decompressed, given `raw`,
+/// the start of its data. Lenient decoders, or readers that fall back to
+/// treating the data as stored, may still extract it.
+pub(crate) fn deflate_declared_but_invalid(
+    index: usize,
+    central: &CentralDirectoryFileHeader,
+    raw: &[u8],
+) -> Finding {
+    let reason = match raw.first() {
+        Some(first) if (first >> 1) & 0b11 == DEFLATE_RESERVED_BLOCK_TYPE => {
+            "its first block has the reserved block type 3"
+        }
+        _ => "the decoder fails",
+    };
+    let as_stored = match content::detected_type(raw) {
+        Some(detected) => format!("; read as stored data, its content is detected as {detected}"),
+        None => String::new(),
+    };
+    Finding::new(
+        "deflate_declared_but_invalid",
+        Severity::Warning,
+        format!(
+            "\"{}\" declares Deflate, but its data isn't valid Deflate: {reason}{as_stored}",
+            central.name
+        ),
+    )
+    .for_entry(index)
+}
+
+/// Methods to try decoding an entry's data with when its own method fails,
+/// most telling first: that data decodes as stored says the least
+const TRIAL_METHODS: &[Method] = &[
+    Method::Deflate,
+    Method::Deflate64,
+    Method::Bzip2,
+    Method::Lzma,
+    Method::Zstd,
+    Method::Store,
+];
+
+/// How many bytes of an entry's data are decoded per trial, and how many
+/// decoded bytes are enough to believe the data is in that format
+const MAX_TRIAL_BYTES: usize = 1 << 20;
+
+/// For an entry whose data couldn't be decoded with its declared method,
+/// tries the other supported methods on the data. A method counts if it
+/// decodes all of the data to the declared CRC-32 and size, or decodes
+/// [MAX_TRIAL_BYTES] without error.
+pub(crate) fn method_mislabeled<F: HasCursor>(
+    file: &F,
+    index: usize,
+    entry: &Entry,
+) -> Option<Finding> {
+    if entry.flags & FLAG_ENCRYPTED != 0 || entry.method == Method::Aex {
+        return None;
+    }
+    let length = entry.compressed_size.min(MAX_TRIAL_BYTES as u64) as usize;
+    let raw = content::raw_data_prefix(file, entry.header_offset, length)?;
+    let actual = TRIAL_METHODS
+        .iter()
+        .filter(|method| **method != entry.method && method.is_decodable())
+        .find(
+            |method| match rc_zip::fsm::trial_decompress(**method, &raw, MAX_TRIAL_BYTES) {
+                Ok((output, true)) => {
+                    crc32fast::hash(&output) == entry.crc32
+                        && output.len() as u64 == entry.uncompressed_size
+                }
+                Ok((output, false)) => **method != Method::Store && output.len() == MAX_TRIAL_BYTES,
+                Err(_) => false,
+            },
+        )?;
+    Some(
+        Finding::new(
+            "method_mislabeled",
+            Severity::Warning,
+            format!(
+                "\"{}\" is labeled {:?}, but its data doesn't decode as such and does decode as {actual:?}",
+                entry.name, entry.method
+            ),
+        )
+        .for_entry(index),
+    )
+}
+
+/// Checks whether the data of the entry at `index`, starting at
+/// `data_start` right after its local file header and as long as either
+/// header declares, runs past the start of the central directory at
+/// `directory_offset`. This works even if the local file header couldn't be
+/// read in full, e.g. because the data couldn't be decompressed.
+pub(crate) fn central_directory_overrun(
+    index: usize,
+    data_start: u64,
+    central: &Entry,
+    local_compressed_size: u64,
+    directory_offset: u64,
+) -> Option<Finding> {
+    let compressed_size = central.compressed_size.max(local_compressed_size);
+    let data_end = data_start.saturating_add(compressed_size);
+    (data_start < directory_offset && data_end > directory_offset).then(|| {
+        Finding::new(
+            "overruns_central_directory",
+            Severity::Error,
+            format!(
+                "the {compressed_size} bytes of data of \"{}\" end at offset {data_end}, past the start of the central directory at offset {directory_offset}",
+                central.name
+            ),
+        )
+        .for_entry(index)
+        .at(data_start)
+    })
+}
+
+/// Runs the per-entry checks for the entry at `index` in `contents`.
+pub(crate) fn check_entry(
+    index: usize,
+    central: &CentralDirectoryFileHeader,
+    local: Option<&LocalFileHeader>,
+    options: &ParseOptions,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let mut headers = vec![(
+        "central directory header",
+        encryption_inconsistencies(
+            central.flags,
+            central.method,
+            central.aex.as_ref(),
+            central.compressed_size as u64,
+        ),
+    )];
+    if let Some(local) = local {
+        headers.push((
+            "local file header",
+            encryption_inconsistencies(
+                local.flags,
+                local.method,
+                local.aex.as_ref(),
+                local.compressed_size,
+            ),
+        ));
+    }
+    for (header, problems) in headers {
+        for problem in problems {
+            findings.push(
+                Finding::new(
+                    "encryption_flag_inconsistent",
+                    Severity::Warning,
+                    format!("{header} for \"{}\": {problem}", central.name),
+                )
+                .for_entry(index),
+            );
+        }
+    }
+
+    let mut aex_headers = Vec::new();
+    if let Some(aex) = &central.aex {
+        if central.compressed_size != u32::MAX && central.uncompressed_size != u32::MAX {
+            aex_headers.push((
+                "central directory header",
+                aex,
+                central.compressed_size as u64,
+                central.uncompressed_size as u64,
+            ));
+        }
+    }
+    if let Some((local, aex)) = local.and_then(|local| Some((local, local.aex.as_ref()?))) {
+        // with a data descriptor, the local header is allowed to leave sizes at zero
+        let deferred = local.flags & FLAG_DATA_DESCRIPTOR != 0 && local.compressed_size == 0;
+        if !deferred {
+            aex_headers.push((
+                "local file header",
+                aex,
+                local.compressed_size,
+                local.uncompressed_size,
+            ));
+        }
+    }
+    for (header, aex, compressed_size, uncompressed_size) in aex_headers {
+        if let Some(problem) = aex_length_inconsistency(aex, compressed_size, uncompressed_size) {
+            findings.push(
+                Finding::new(
+                    "aex_length_inconsistent",
+                    Severity::Warning,
+                    format!("{header} for \"{}\": {problem}", central.name),
+                )
+                .for_entry(index),
+            );
+        }
+    }
+
+    if let Some(local) = local.filter(|local| local.name_length != central.name_length) {
+        // decoded names can match despite this, e.g. through truncation at a NUL byte
+        let same_decoded = if local.name == central.name {
+            ", even though both decode to the same name"
+        } else {
+            ""
+        };
+        findings.push(
+            Finding::new(
+                "name_length_mismatch",
+                Severity::Warning,
+                format!(
+                    "central directory header stores a {}-byte name for \"{}\", but the local file header stores a {}-byte name (\"{}\"){same_decoded}",
+                    central.name_length, central.name, local.name_length, local.name
+                ),
+            )
+            .for_entry(index),
+        );
+    }
+
+    let mut zero_crc_headers = Vec::new();
+    if zero_crc_nonempty(
+        central.flags,
+        central.crc32,
+        central.uncompressed_size as u64,
+        central.aex.as_ref(),
+    ) {
+        zero_crc_headers.push(("central directory header", central.uncompressed_size as u64));
+    }
+    if let Some(local) = local.filter(|local| local.zero_crc_nonempty) {
+        zero_crc_headers.push(("local file header", local.uncompressed_size));
+    }
+    for (header, uncompressed_size) in zero_crc_headers {
+        findings.push(
+            Finding::new(
+                "zero_crc_nonempty",
+                Severity::Warning,
+                format!(
+                    "{header} for \"{}\" declares a CRC-32 of 0 for {uncompressed_size} bytes of content, without a data descriptor to supply the real one",
+                    central.name
+                ),
+            )
+            .for_entry(index),
+        );
+    }
+
+    // without a decompressor, nothing was measured
+    if let Some(local) = local.filter(|local| local.method_supported) {
+        for problem in measured_size_mismatches(central, local) {
+            findings.push(
+                Finding::new(
+                    "measured_size_mismatch",
+                    Severity::Warning,
+                    format!("\"{}\": {problem}", central.name),
+                )
+                .for_entry(index),
+            );
+        }
+    }
+
+    // the other checks can't make the archive nonconformant, so each is
+    // skipped when its findings wouldn't be reported
+    if options.reports("data_descriptor_size_ambiguous", Severity::Warning)
+        && local.is_some_and(|local| local.data_descriptor_size_ambiguous)
+    {
+        findings.push(
+            Finding::new(
+                "data_descriptor_size_ambiguous",
+                Severity::Warning,
+                format!(
+                    "the data descriptor of \"{}\" only lines up with the next record when its sizes are read with the other width (4 or 8 bytes) than the local file header implies",
+                    central.name
+                ),
+            )
+            .for_entry(index),
+        );
+    }
+
+    if let Some(local) = local
+        .filter(|_| options.reports("suspicious_directory_entry", Severity::Warning))
+        .filter(|local| local.suspicious_directory_entry)
+    {
+        findings.push(
+            Finding::new(
+                "suspicious_directory_entry",
+                Severity::Warning,
+                format!(
+                    "\"{}\" is a directory, but has content ({} bytes declared, {} bytes decompressed), which extractors that create it as a directory never look at",
+                    central.name, central.uncompressed_size, local.measured_uncompressed
+                ),
+            )
+            .for_entry(index),
+        );
+    }
+
+    if let Some(problem) = options
+        .reports("unicode_comment_mismatch", Severity::Warning)
+        .then(|| unicode_comment_problem(central))
+        .flatten()
+    {
+        findings.push(
+            Finding::new(
+                "unicode_comment_mismatch",
+                Severity::Warning,
+                format!(
+                    "central directory header for \"{}\": {problem}",
+                    central.name
+                ),
+            )
+            .for_entry(index),
+        );
+    }
+
+    let mut flag_headers = vec![("central directory header", central.flags)];
+    if let Some(local) = local {
+        flag_headers.push(("local file header", local.flags));
+    }
+    for (header, flags) in flag_headers {
+        let unusual = unusual_flags(flags);
+        if !unusual.is_empty() && options.reports("unusual_gp_flags", Severity::Info) {
+            findings.push(
+                Finding::new(
+                    "unusual_gp_flags",
+                    Severity::Info,
+                    format!(
+                        "{header} for \"{}\" sets general purpose {}, which ordinary archives don't use and some readers ignore",
+                        central.name,
+                        unusual.join(" and ")
+                    ),
+                )
+                .for_entry(index),
+            );
+        }
+    }
+
+    let mut extra_headers = vec![(
+        "central directory header",
+        central.extra.len(),
+        central.extra_length,
+    )];
+    if let Some(local) = local {
+        extra_headers.push(("local file header", local.extra.len(), local.extra_length));
+    }
+    for (header, actual, declared) in extra_headers {
+        if actual != declared as usize
+            && options.reports("extra_length_mismatch", Severity::Warning)
+        {
+            findings.push(
+                Finding::new(
+                    "extra_length_mismatch",
+                    Severity::Warning,
+                    format!(
+                        "{header} for \"{}\" declares a {declared}-byte extra field, but {actual} bytes were read",
+                        central.name
+                    ),
+                )
+                .for_entry(index),
+            );
+        }
+    }
+
+    if let Some(local) = local
+        .filter(|_| options.reports("zip64_determination_inconsistent", Severity::Warning))
+        .filter(|local| local.zip64_extra_field != local.zip64_size_sentinel)
+    {
+        let problem = if local.zip64_extra_field {
+            "has a zip64 extra field, but neither size is 0xFFFFFFFF"
+        } else {
+            "has a size of 0xFFFFFFFF, but no zip64 extra field"
+        };
+        findings.push(
+            Finding::new(
+                "zip64_determination_inconsistent",
+                Severity::Warning,
+                format!(
+                    "local file header for \"{}\" {problem}: readers may disagree on whether its data descriptor has 4- or 8-byte sizes",
+                    central.name
+                ),
+            )
+            .for_entry(index),
+        );
+    }
+
+    if central.path_depth > options.max_path_depth
+        && options.reports("excessive_path_depth", Severity::Warning)
+    {
+        findings.push(
+            Finding::new(
+                "excessive_path_depth",
+                Severity::Warning,
+                format!(
+                    "\"{}\" has {} path components, more than {}, which can exceed path limits when extracted",
+                    central.name, central.path_depth, options.max_path_depth
+                ),
+            )
+            .for_entry(index),
+        );
+    }
+
+    if let Some(component) = options
+        .reports("reserved_device_name", Severity::Warning)
+        .then(|| reserved_device_component(&central.name))
+        .flatten()
+    {
+        findings.push(
+            Finding::new(
+                "reserved_device_name",
+                Severity::Warning,
+                format!(
+                    "\"{}\" has a path component \"{component}\" that is a reserved device name on Windows",
+                    central.name
+                ),
+            )
+            .for_entry(index),
+        );
+    }
+
+    if let Some((detected, extension)) = local
+        .filter(|_| options.reports("extension_mismatch", Severity::Warning))
+        .and_then(|local| local.detected_type)
+        .and_then(|detected| {
+            Some((
+                detected,
+                content::extension_mismatch(&central.name, detected)?,
+            ))
+        })
+    {
+        findings.push(
+            Finding::new(
+                "extension_mismatch",
+                Severity::Warning,
+                format!(
+                    "\"{}\" has a .{extension} extension, but its content is detected as {detected}",
+                    central.name
+                ),
+            )
+            .for_entry(index),
+        );
+    }
+
+    if let Some(finding) = local
+        .filter(|_| options.reports("stored_high_entropy", Severity::Info))
+        .and_then(|local| stored_high_entropy(&central.name, local))
+    {
+        findings.push(finding.for_entry(index));
+    }
+
+    findings
+}
+
+/// Flags local file headers that lie within another entry's file data: an
+/// extractor reading that data reads a header too, which is how overlapping
+/// zip bombs and zip quines reuse bytes.
+pub(crate) fn check_ranges(ranges: &ParsedRanges, entries: &HashMap<u64, usize>) -> Vec<Finding> {
+    ranges
+        .overlaps(MAX_OVERLAPS)
+        .pairs
+        .into_iter()
+        .filter_map(|(a, b, shared)| match (a.contains(), b.contains()) {
+            ("local file header", "file data") => Some((a, b, shared)),
+            ("file data", "local file header") => Some((b, a, shared)),
+            _ => None,
+        })
+        .map(|(header, data, shared)| {
+            let finding = Finding::new(
+                "header_data_overlap",
+                Severity::Error,
+                format!(
+                    "local file header of \"{}\" overlaps the file data of \"{}\" at bytes {}..{}",
+                    header.filename().unwrap_or_default(),
+                    data.filename().unwrap_or_default(),
+                    shared.start,
+                    shared.end,
+                ),
+            )
+            .at(shared.start);
+            match entries.get(&header.range().start) {
+                Some(&index) => finding.for_entry(index),
+                None => finding,
+            }
+        })
+        .collect()
+}
+
+/// Describes a parsed range for a message, e.g. `central directory header of "a.txt"`
+fn describe_range(range: &ParsedRange) -> String {
+    match range.filename() {
+        Some(filename) => format!("{} of \"{filename}\"", range.contains()),
+        None => range.contains().to_string(),
+    }
+}
+
+/// Flags any two structures that claim the same bytes, other than a local
+/// file header within file data, which [check_ranges] reports. Readers that
+/// get to the shared bytes through different structures see different data.
+pub(crate) fn check_overlaps(ranges: &ParsedRanges) -> Vec<Finding> {
+    let overlaps = ranges.overlaps(MAX_OVERLAPS);
+    let truncated = overlaps.truncated.then(|| {
+        Finding::new(
+            "structure_overlap",
+            Severity::Warning,
+            format!("more than {MAX_OVERLAPS} pairs of structures claim the same bytes, the rest aren't reported"),
+        )
+    });
+    overlaps
+        .pairs
+        .into_iter()
+        .filter(|(a, b, _)| {
+            !matches!(
+                (a.contains(), b.contains()),
+                ("local file header", "file data") | ("file data", "local file header")
+            )
+        })
+        .map(|(a, b, shared)| {
+            Finding::new(
+                "structure_overlap",
+                Severity::Warning,
+                format!(
+                    "{} and {} both claim bytes {}..{}",
+                    describe_range(a),
+                    describe_range(b),
+                    shared.start,
+                    shared.end,
+                ),
+            )
+            .at(shared.start)
+        })
+        .chain(truncated)
+        .collect()
+}
+
+/// Maps the offset of each local file header to the index of the first entry
+/// pointing to it, to find the entry of a parsed range
+pub(crate) fn entries_by_header_offset(contents: &[FileMetadata]) -> HashMap<u64, usize> {
+    let mut entries = HashMap::with_capacity(contents.len());
+    for (index, file) in contents.iter().enumerate() {
+        entries.entry(file.header_offset).or_insert(index);
+    }
+    entries
+}
+
+/// Compares the number of bytes the parser consumed for each local file
+/// header, as recorded in `ranges`, to the size its name and extra field
+/// lengths declare.
+pub(crate) fn check_local_header_sizes(
+    ranges: &ParsedRanges,
+    contents: &[FileMetadata],
+    entries: &HashMap<u64, usize>,
+) -> Vec<Finding> {
+    ranges
+        .iter()
+        .filter(|range| range.contains() == "local file header")
+        .filter_map(|range| {
+            let range = range.range();
+            let index = *entries.get(&range.start)?;
+            let local = contents[index].local.as_ref().ok()?;
+            let declared = content::LOCAL_HEADER_FIXED_LENGTH
+                + local.name_length as u64
+                + local.extra_length as u64;
+            let consumed = range.end - range.start;
+            (consumed != declared).then(|| {
+                Finding::new(
+                    "local_header_size_inconsistent",
+                    Severity::Warning,
+                    format!(
+                        "local file header of \"{}\" takes up {consumed} bytes, but its field lengths add up to {declared}",
+                        local.name
+                    ),
+                )
+                .for_entry(index)
+                .at(range.start)
+            })
+        })
+        .collect()
+}
+
+/// Compares the compressed size of each entry with the space its data has:
+/// from the end of the local file header up to the next parsed structure.
+/// The data of stored entries must also be exactly as large as its content.
+pub(crate) fn check_data_regions(ranges: &ParsedRanges, contents: &[FileMetadata]) -> Vec<Finding> {
+    let mut data_ranges = HashMap::new();
+    for range in ranges
+        .iter()
+        .filter(|range| range.contains() == "file data")
+    {
+        data_ranges
+            .entry(range.range().start)
+            .or_insert(range.range());
+    }
+    let mut sorted: Vec<_> = ranges
+        .iter()
+        .map(|range| (range.range().start, range.range().end))
+        .collect();
+    sorted.sort_unstable();
+
+    let mut findings = Vec::new();
+    for (index, file) in contents.iter().enumerate() {
+        let (central, Ok(local)) = (&file.central, &file.local) else {
+            continue;
+        };
+        let data_start = file.header_offset
+            + content::LOCAL_HEADER_FIXED_LENGTH
+            + local.name_length as u64
+            + local.extra_length as u64;
+        let Some(recorded) = data_ranges.get(&data_start).cloned() else {
+            continue;
+        };
+        let declared = match central.compressed_size {
+            u32::MAX => recorded.end - recorded.start,
+            size => size as u64,
+        };
+
+        let stored = central.method == Method::Store && central.flags & FLAG_ENCRYPTED == 0;
+        if stored && central.compressed_size != central.uncompressed_size {
+            findings.push(
+                Finding::new(
+                    "data_region_size_mismatch",
+                    Severity::Warning,
+                    format!(
+                        "\"{}\" is stored, but declares {} compressed and {} uncompressed bytes",
+                        central.name, central.compressed_size, central.uncompressed_size
+                    ),
+                )
+                .for_entry(index)
+                .at(data_start),
+            );
+        }
+
+        // entries sharing their data with this one are reported elsewhere, so
+        // only other structures starting at the same offset count
+        let from = sorted.partition_point(|(start, _)| *start < data_start);
+        let after = sorted.partition_point(|(start, _)| *start <= data_start);
+        let only_recorded = sorted[from..after]
+            .iter()
+            .all(|(_, end)| *end == recorded.end);
+        let next = if only_recorded {
+            match sorted.get(after) {
+                Some((start, _)) => *start,
+                None => continue,
+            }
+        } else {
+            data_start
+        };
+        let actual = next - data_start;
+        if actual != declared {
+            findings.push(
+                Finding::new(
+                    "data_region_size_mismatch",
+                    Severity::Warning,
+                    format!(
+                        "\"{}\" declares {declared} bytes of compressed data, but there are {actual} bytes before the next structure at {next}",
+                        central.name
+                    ),
+                )
+                .for_entry(index)
+                .at(data_start),
+            );
+        }
+    }
+    findings
+}
+
+/// Alignments that writers pad entries to, e.g. 4 bytes for zipalign and
+/// 4 KiB pages for shared libraries in APKs
+const KNOWN_ALIGNMENTS: &[u64] = &[4, 4096];
+
+/// Looks for bytes between each data descriptor and the next parsed
+/// structure, which should follow right after it. Gaps that pad the next
+/// structure to a known alignment are reported as likely benign.
+pub(crate) fn check_post_descriptor_gaps(
+    ranges: &ParsedRanges,
+    contents: &[FileMetadata],
+) -> Vec<Finding> {
+    let mut starts: Vec<_> = ranges.iter().map(|range| range.range().start).collect();
+    starts.sort_unstable();
+    let mut headers: Vec<_> = contents
+        .iter()
+        .enumerate()
+        .map(|(index, file)| (file.header_offset, index))
+        .collect();
+    headers.sort_unstable();
+
+    ranges
+        .iter()
+        .filter(|range| range.contains() == "data descriptor")
+        .filter_map(|descriptor| {
+            let end = descriptor.range().end;
+            let next = *starts.get(starts.partition_point(|start| *start < end))?;
+            let gap = next - end;
+            if gap == 0 {
+                return None;
+            }
+
+            let alignment = KNOWN_ALIGNMENTS
+                .iter()
+                .find(|alignment| next.is_multiple_of(**alignment) && gap < **alignment);
+            let name = descriptor.filename().unwrap_or_default();
+            let finding = match alignment {
+                Some(alignment) => Finding::new(
+                    "post_descriptor_gap",
+                    Severity::Info,
+                    format!(
+                        "{gap} bytes follow the data descriptor of \"{name}\", likely padding to a {alignment}-byte boundary"
+                    ),
+                ),
+                None => Finding::new(
+                    "post_descriptor_gap",
+                    Severity::Warning,
+                    format!("{gap} unclaimed bytes follow the data descriptor of \"{name}\""),
+                ),
+            }
+            .at(end);
+            // the entry is the last one whose local header precedes the descriptor
+            match headers.partition_point(|(offset, _)| *offset < end) {
+                0 => Some(finding),
+                preceding => Some(finding.for_entry(headers[preceding - 1].1)),
+            }
+        })
+        .collect()
+}
+
+/// Where a streaming extractor that trusts the size fields expects the next
+/// local file header after `file`'s: right after the header and the
+/// compressed data. Readers compute this in the width of the size fields, so
+/// it wraps around at 4 GiB unless the entry is zip64. Returns `None` for
+/// entries whose sizes are deferred to a data descriptor.
+///
+/// This uses the local file header as parsed for the report. For entries
+/// whose data couldn't be read, there is none, and the central directory
+/// header stands in for it.
+fn next_header_offset(file: &FileMetadata) -> Option<u64> {
+    let (flags, length, compressed_size, zip64) = match &file.local {
+        Ok(local) => (
+            local.flags,
+            local.name_length as u64 + local.extra_length as u64,
+            local.compressed_size,
+            local.zip64_size_sentinel,
+        ),
+        Err(_) => (
+            file.central.flags,
+            file.central.name_length as u64 + file.central.extra_length as u64,
+            file.central.zip64_compressed_size,
+            file.central.compressed_size == u32::MAX,
+        ),
+    };
+    if flags & FLAG_DATA_DESCRIPTOR != 0 {
+        return None;
+    }
+    let offset = file.header_offset;
+    let length = 30 + length;
+    if zip64 {
+        Some(offset.wrapping_add(length).wrapping_add(compressed_size))
+    } else {
+        Some(
+            (offset as u32)
+                .wrapping_add(length as u32)
+                .wrapping_add(compressed_size as u32) as u64,
+        )
+    }
+}
+
+/// Flags entries whose local file headers form a cycle when following the
+/// size fields from one header to the next, as a streaming extractor does:
+/// such an extractor never reaches the end of the archive. Each cycle is
+/// reported once, for its first entry in the central directory. This takes
+/// a single pass over the entries, however they are chained.
+pub(crate) fn check_offset_cycles(contents: &[FileMetadata]) -> Vec<Finding> {
+    let mut index_by_offset = BTreeMap::new();
+    for (index, file) in contents.iter().enumerate() {
+        index_by_offset.entry(file.header_offset).or_insert(index);
+    }
+    let next: Vec<Option<usize>> = contents
+        .iter()
+        .map(|entry| {
+            let offset = next_header_offset(entry)?;
+            index_by_offset.get(&offset).copied()
+        })
+        .collect();
+
+    // every entry is walked once: a walk ends at an entry that was already
+    // walked, and it's a cycle if that entry is on the current walk
+    let mut walked = vec![false; contents.len()];
+    let mut findings = Vec::new();
+    for start in 0..contents.len() {
+        let mut path = Vec::new();
+        let mut current = Some(start);
+        while let Some(index) = current.filter(|index| !walked[*index]) {
+            walked[index] = true;
+            path.push(index);
+            current = next[index];
+        }
+        let Some(position) = current.and_then(|index| path.iter().position(|i| *i == index)) else {
+            continue;
+        };
+
+        let cycle = &path[position..];
+        let names: Vec<String> = cycle
+            .iter()
+            .chain(&cycle[..1])
+            .map(|index| format!("\"{}\"", contents[*index].central.name))
+            .collect();
+        findings.push(
+            Finding::new(
+                "offset_cycle",
+                Severity::Error,
+                format!(
+                    "following the declared sizes from one local file header to the next loops: {}",
+                    names.join(" -> ")
+                ),
+            )
+            .for_entry(*cycle.iter().min().expect("cycles aren't empty")),
+        );
+    }
+    findings
+}
+
+/// Archives with fewer entries with identical CRC-32 and sizes than this are
+/// left alone, a handful of copies of the same file isn't unusual
+const MIN_REPEATED_ENTRIES: usize = 8;
+
+/// Flags archives where at least half of the entries, and at least
+/// [MIN_REPEATED_ENTRIES], share the same CRC-32, compressed size and
+/// uncompressed size. Generated zip bombs repeat the same compressed block
+/// like that. Empty entries, such as directories, don't count.
+pub(crate) fn check_repeated_entries<'a>(
+    entries: impl IntoIterator<Item = &'a Entry>,
+) -> Option<Finding> {
+    let mut total = 0;
+    let mut counts: BTreeMap<(u32, u64, u64), usize> = BTreeMap::new();
+    for entry in entries {
+        total += 1;
+        if entry.uncompressed_size > 0 {
+            *counts
+                .entry((entry.crc32, entry.compressed_size, entry.uncompressed_size))
+                .or_default() += 1;
+        }
+    }
+
+    let ((crc32, compressed_size, uncompressed_size), count) =
+        counts.into_iter().max_by_key(|(_, count)| *count)?;
+    if count < MIN_REPEATED_ENTRIES || count * 2 < total {
+        return None;
+    }
+    Some(Finding::new(
+        "repeated_identical_entries",
+        Severity::Warning,
+        format!(
+            "{count} of {total} entries have CRC-32 {crc32:#010x}, a compressed size of {compressed_size} and an uncompressed size of {uncompressed_size}"
+        ),
+    ))
+}
+
+/// Flags an archive whose prefix starts like a file of another format. Such
+/// a file may pass as harmless under one format and be opened as the other.
+/// Executables are expected for self-extracting archives, so they're only
+/// noteworthy.
+pub(crate) fn check_polyglot(candidate: &PolyglotCandidate) -> Finding {
+    let severity = match candidate.is_executable() {
+        true => Severity::Info,
+        false => Severity::Warning,
+    };
+    Finding::new(
+        "polyglot_candidate",
+        severity,
+        format!(
+            "the {}-byte prefix before the first entry starts like a {} file",
+            candidate.prefix_size,
+            candidate.format.to_uppercase()
+        ),
+    )
+    .at(0)
+}
+
+/// Flags an end of central directory record whose entry count contradicts
+/// the size or location it records for the central directory. Readers that
+/// trust one field over the other see different archives.
+pub(crate) fn check_empty_directory(eocd: &EndOfCentralDirectory<'_>) -> Option<Finding> {
+    let records = eocd.directory_records();
+    let size = eocd.directory_size();
+    let problem = if records == 0 && size != 0 {
+        format!("counts no entries, but declares a {size}-byte central directory")
+    } else if records == 0 && eocd.global_offset != 0 {
+        let recorded_offset = eocd.directory_offset() as i64 - eocd.global_offset;
+        format!(
+            "counts no entries, but declares a central directory at offset {recorded_offset} rather than where the record itself is"
+        )
+    } else if records != 0 && size == 0 {
+        format!("counts {records} entries, but declares an empty central directory")
+    } else {
+        return None;
+    };
+    Some(
+        Finding::new(
+            "empty_directory_inconsistent",
+            Severity::Warning,
+            format!("the end of central directory record {problem}"),
+        )
+        .at(eocd.dir.offset),
+    )
+}
+
+/// Compares the central directory size declared by the end of central
+/// directory record with the sizes of the central directory headers parsed,
+/// as recorded in `ranges`. Unlike the offset, this catches padding between
+/// headers as well as headers that weren't read. Empty directories are left
+/// to [check_empty_directory].
+pub(crate) fn check_directory_size(
+    ranges: &ParsedRanges,
+    eocd: &EndOfCentralDirectory<'_>,
+) -> Option<Finding> {
+    let declared = eocd.directory_size();
+    if eocd.directory_records() == 0 || declared == 0 {
+        return None;
+    }
+    let summed: u64 = ranges
+        .iter()
+        .filter(|range| range.contains() == "central directory header")
+        .map(|range| range.range().end - range.range().start)
+        .sum();
+    (summed != declared).then(|| {
+        Finding::new(
+            "directory_size_sum_mismatch",
+            Severity::Warning,
+            format!(
+                "the end of central directory record declares a {declared}-byte central directory, but its headers add up to {summed} bytes"
+            ),
+        )
+        .at(eocd.dir.offset)
+    })
+}
+
+/// Readers look for the end of central directory record by scanning backward
+/// from the end of the file, over at most this many bytes: the fixed part of
+/// the record plus the longest possible comment.
+const EOCD_SEARCH_WINDOW: u64 = EndOfCentralDirectoryRecord::MAX_LENGTH as u64;
+
+/// Flags end of central directory signatures in the trailing search window,
+/// other than that of the record at `eocd_offset`. Depending on where they
+/// start scanning and which candidates they accept, readers may pick one of
+/// these instead, e.g. a signature in the archive comment.
+pub(crate) fn check_eocd_signatures<F: HasCursor>(
+    file: &F,
+    size: u64,
+    eocd_offset: u64,
+) -> Vec<Finding> {
+    let window_start = size.saturating_sub(EOCD_SEARCH_WINDOW);
+    let mut window = Vec::new();
+    if file
+        .cursor_at(window_start)
+        .take(size - window_start)
+        .read_to_end(&mut window)
+        .is_err()
+    {
+        return Vec::new();
+    }
+
+    let signature = EndOfCentralDirectoryRecord::SIGNATURE.as_bytes();
+    window
+        .windows(signature.len())
+        .enumerate()
+        .filter(|(_, candidate)| *candidate == signature)
+        .map(|(position, _)| window_start + position as u64)
+        .filter(|offset| *offset != eocd_offset)
+        .map(|offset| {
+            let side = if offset < eocd_offset { "before" } else { "after" };
+            Finding::new(
+                "eocd_signature_collision",
+                Severity::Warning,
+                format!(
+                    "end of central directory signature at offset {offset}, {side} the actual record at offset {eocd_offset}: readers scanning for the record may pick either"
+                ),
+            )
+            .at(offset)
+        })
+        .collect()
+}
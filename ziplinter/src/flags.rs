@@ -0,0 +1,51 @@
+//! The general purpose bit flags of a header, broken out into the bits that
+//! mean something, so that reading a report doesn't take the bit layout of
+//! the zip specification.
+
+use crate::findings::{FLAG_DATA_DESCRIPTOR, FLAG_ENCRYPTED};
+
+/// 4.4.4 general purpose bit flag: Bit 1 and 2: compression options, whose
+/// meaning depends on the method.
+const FLAG_COMPRESSION_OPTIONS: u16 = 0b11 << 1;
+
+/// 4.4.4 general purpose bit flag: Bit 6: Strong encryption.
+const FLAG_STRONG_ENCRYPTION: u16 = 1 << 6;
+
+/// 4.4.4 general purpose bit flag: Bit 11: Language encoding flag (EFS). If
+/// this bit is set, the filename and comment fields for this file MUST be
+/// encoded using UTF-8.
+const FLAG_UTF8: u16 = 1 << 11;
+
+/// The meaningful bits of a general purpose bit flag
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedFlags {
+    /// Bit 0: the file is encrypted
+    pub encrypted: bool,
+
+    /// Bits 1 and 2, as a number from 0 to 3: for Deflate, the compression
+    /// level (normal, maximum, fast or super fast), for Implode, the size of
+    /// the sliding dictionary (bit 1) and the number of Shannon-Fano trees
+    /// (bit 2), and for LZMA, whether an end-of-stream marker is used (bit 1)
+    pub compression_options: u8,
+
+    /// Bit 3: the CRC-32 and sizes are in a data descriptor after the data
+    pub data_descriptor: bool,
+
+    /// Bit 6: the file uses strong encryption
+    pub strong_encryption: bool,
+
+    /// Bit 11: the name and comment are encoded as UTF-8
+    pub utf8: bool,
+}
+
+impl From<u16> for DecodedFlags {
+    fn from(flags: u16) -> Self {
+        Self {
+            encrypted: flags & FLAG_ENCRYPTED != 0,
+            compression_options: ((flags & FLAG_COMPRESSION_OPTIONS) >> 1) as u8,
+            data_descriptor: flags & FLAG_DATA_DESCRIPTOR != 0,
+            strong_encryption: flags & FLAG_STRONG_ENCRYPTION != 0,
+            utf8: flags & FLAG_UTF8 != 0,
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Structure of the data preceding the first entry.
+//!
+//! Self-extracting archives and installers are executables with a zip file
+//! appended. Between the executable stub and the first local file header
+//! there is sometimes padding, e.g. to align the archive. Rather than lumping
+//! all leading bytes together, the stub and the padding are reported
+//! separately. The stub itself isn't parsed, only recognized by its magic.
+
+use std::io::Read;
+
+use rc_zip_sync::HasCursor;
+
+/// How much of the prefix is read at once while looking for padding
+const CHUNK_LENGTH: u64 = 64 * 1024;
+
+/// Byte values that are used to pad the stub
+const FILLER_BYTES: &[u8] = &[0x00, 0xff];
+
+/// Shorter runs of filler bytes are more likely the tail end of the stub
+/// (e.g. a little-endian integer) than deliberate padding
+const MIN_PADDING_LENGTH: u64 = 8;
+
+/// A range of bytes in the file
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct Region {
+    /// Offset of the first byte
+    pub start: u64,
+
+    /// Offset right after the last byte
+    pub end: u64,
+}
+
+/// What precedes the first entry, e.g. a self-extractor stub
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct Layout {
+    /// All bytes before the first local file header
+    pub prefix: Region,
+
+    /// The part of the prefix that isn't padding
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stub: Option<Region>,
+
+    /// The kind of stub, as recognized by its magic: `pe`, `elf`, `mach-o` or `script`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stub_kind: Option<&'static str>,
+
+    /// Run of filler bytes between the stub and the first local file header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding: Option<Region>,
+
+    /// The byte value the padding consists of
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding_byte: Option<u8>,
+}
+
+/// Formats whose files are still valid when a zip file is appended to them,
+/// by the magic they start with
+const POLYGLOT_FORMATS: &[(&[u8], &str)] = &[
+    (b"MZ", "pe"),
+    (b"\x7fELF", "elf"),
+    (b"%PDF", "pdf"),
+    (b"\x89PNG", "png"),
+    (b"GIF8", "gif"),
+];
+
+/// Byte order mark some PDF writers put before the magic
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// An archive whose prefix starts like a file of another format, so that the
+/// file may be read as that format as well as a zip file
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PolyglotCandidate {
+    /// The format the prefix starts like: `pe`, `elf`, `pdf`, `png` or `gif`
+    pub format: &'static str,
+
+    /// Size of the prefix in bytes
+    pub prefix_size: u64,
+}
+
+impl PolyglotCandidate {
+    /// Whether the other format is an executable, as in self-extracting
+    /// archives, rather than a document or an image
+    pub(crate) fn is_executable(&self) -> bool {
+        matches!(self.format, "pe" | "elf")
+    }
+}
+
+fn stub_kind(magic: &[u8]) -> Option<&'static str> {
+    if magic.starts_with(b"MZ") {
+        Some("pe")
+    } else if magic.starts_with(b"\x7fELF") {
+        Some("elf")
+    } else if [
+        b"\xfe\xed\xfa\xce",
+        b"\xfe\xed\xfa\xcf",
+        b"\xce\xfa\xed\xfe",
+        b"\xcf\xfa\xed\xfe",
+    ]
+    .iter()
+    .any(|m| magic.starts_with(*m))
+    {
+        Some("mach-o")
+    } else if magic.starts_with(b"#!") {
+        Some("script")
+    } else {
+        None
+    }
+}
+
+fn read_at<F: HasCursor>(file: &F, offset: u64, length: u64) -> Option<Vec<u8>> {
+    let mut buf = vec![0u8; usize::try_from(length).ok()?];
+    file.cursor_at(offset).read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+/// Returns the start of the run of `byte`s that ends at `end`
+fn padding_start<F: HasCursor>(file: &F, end: u64, byte: u8) -> Option<u64> {
+    let mut start = end;
+    while start > 0 {
+        let chunk_start = start.saturating_sub(CHUNK_LENGTH);
+        let chunk = read_at(file, chunk_start, start - chunk_start)?;
+        match chunk.iter().rposition(|b| *b != byte) {
+            Some(position) => return Some(chunk_start + position as u64 + 1),
+            None => start = chunk_start,
+        }
+    }
+    Some(0)
+}
+
+/// Describes the bytes before `first_header_offset`, the offset of the
+/// first local file header. Returns `None` if there are none.
+pub(crate) fn find_layout<F: HasCursor>(file: &F, first_header_offset: u64) -> Option<Layout> {
+    if first_header_offset == 0 {
+        return None;
+    }
+    let prefix = Region {
+        start: 0,
+        end: first_header_offset,
+    };
+
+    let last = read_at(file, first_header_offset - 1, 1)?[0];
+    let padding = if FILLER_BYTES.contains(&last) {
+        let start = padding_start(file, first_header_offset, last)?;
+        (first_header_offset - start >= MIN_PADDING_LENGTH).then_some(Region {
+            start,
+            end: first_header_offset,
+        })
+    } else {
+        None
+    };
+
+    let stub_end = padding.as_ref().map_or(first_header_offset, |p| p.start);
+    let stub = (stub_end > 0).then_some(Region {
+        start: 0,
+        end: stub_end,
+    });
+    let stub_kind = match &stub {
+        Some(stub) => stub_kind(&read_at(file, 0, stub.end.min(4))?),
+        None => None,
+    };
+
+    Some(Layout {
+        prefix,
+        stub,
+        stub_kind,
+        padding_byte: padding.as_ref().map(|_| last),
+        padding,
+    })
+}
+
+/// Recognizes the format the prefix described by `layout` starts like, see
+/// [PolyglotCandidate]. A UTF-8 byte order mark before the magic is skipped.
+pub(crate) fn polyglot_candidate<F: HasCursor>(
+    file: &F,
+    layout: &Layout,
+) -> Option<PolyglotCandidate> {
+    let prefix_size = layout.prefix.end - layout.prefix.start;
+    let magic_length = POLYGLOT_FORMATS
+        .iter()
+        .map(|(magic, _)| magic.len())
+        .max()?;
+    let head = read_at(
+        file,
+        layout.prefix.start,
+        prefix_size.min((UTF8_BOM.len() + magic_length) as u64),
+    )?;
+    let head = head.strip_prefix(UTF8_BOM).unwrap_or(&head);
+    POLYGLOT_FORMATS
+        .iter()
+        .find(|(magic, _)| head.starts_with(magic))
+        .map(|(_, format)| PolyglotCandidate {
+            format,
+            prefix_size,
+        })
+}
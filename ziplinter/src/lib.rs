@@ -1,14 +1,87 @@
-use std::{fs::File, rc::Rc, sync::Mutex};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    convert::Infallible,
+    fs::File,
+    io::{self, Read, Seek},
+    ops::Range,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use rc_zip::{
     chrono::{DateTime, Utc},
-    encoding::Encoding,
-    fsm::{AexData, ParsedRanges},
-    parse::{EndOfCentralDirectory, Entry, ExtraAexField, Method, MethodSpecific, Mode, Version},
+    encoding::{Encoding, EncodingDetection},
+    error::UnsupportedError,
+    fsm::{AexData, EntryFsm, EntryReadMetrics, ParsedRange, ParsedRanges},
+    parse::{
+        EndOfCentralDirectory, Entry, ExtraAexField, ExtraUnicodeCommentField, Method,
+        MethodSpecific, Mode, TimestampSource, Version,
+    },
+};
+use rc_zip_sync::{
+    ArchiveHandle, EntryHandle, HasCursor, LocalHeaderInfo, ReadZip, SeekableReader,
 };
-use rc_zip_sync::{ArchiveHandle, EntryHandle, HasCursor, ReadZip};
 use serde::ser::SerializeStruct;
 
+mod apk;
+mod canonical;
+mod conformance;
+mod content;
+mod corpus;
+mod diff;
+mod digest;
+mod entry_id;
+mod extract;
+mod flags;
+mod layout;
+mod local_stream;
+mod method_stats;
+mod overhead;
+mod ratio_distribution;
+mod rules;
+mod split;
+mod tail;
+mod zipalign;
+pub use apk::{ApkSigningBlock, ApkSigningBlockPair};
+pub use canonical::to_canonical_json;
+pub use corpus::{CorpusReport, NameCount};
+pub use diff::{
+    diff_reports, first_divergence, DiffFinding, DiffSide, Divergence, EntryChange, ReportDiff,
+};
+pub use digest::{DigestAlgorithm, EntryDigest};
+pub use entry_id::entry_id;
+use entry_id::EntryIds;
+pub use extract::{
+    extract_all, extract_to_dir, for_each_entry_data, ExtractError, ExtractOptions, Extracted,
+    ExtractedToDir,
+};
+pub use flags::DecodedFlags;
+pub use layout::{Layout, PolyglotCandidate, Region};
+pub use local_stream::{parse_local_stream, StreamedEntry};
+pub use method_stats::MethodStats;
+pub use overhead::Overhead;
+use ratio_distribution::compression_ratio;
+pub use ratio_distribution::RatioDistribution;
+pub use rules::{rule_by_code, Rule, RuleSet, RULES};
+pub use split::{
+    parse_split, parse_split_streaming, parse_split_with, Segment, SplitArchive, SplitCursor,
+};
+pub use tail::{parse_tail, TailError, TailSummary, TAIL_LENGTH};
+pub use zipalign::Alignment;
+
+mod findings;
+pub use findings::{Finding, Severity};
+
+mod sarif;
+mod stats;
+pub use sarif::to_sarif;
+pub use stats::Stats;
+
+/// A header from the central directory.
+///
+/// Only the central directory has the creator version, disk number, internal
+/// and external attributes (and so the file `mode`), and the file comment.
 #[derive(serde::Serialize)]
 pub struct CentralDirectoryFileHeader {
     /// version made by
@@ -20,12 +93,19 @@ pub struct CentralDirectoryFileHeader {
     /// general purpose bit flag
     pub flags: u16,
 
+    /// [Self::flags] broken out into its meaningful bits
+    pub flags_decoded: DecodedFlags,
+
     /// compression method
     pub method: Method,
 
     /// last mod file datetime
     pub modified: DateTime<Utc>,
 
+    /// Where [Self::modified] comes from: the MS-DOS date and time, or a
+    /// more precise extra field
+    pub timestamp_source: TimestampSource,
+
     /// crc32 hash
     pub crc32: u32,
 
@@ -35,6 +115,16 @@ pub struct CentralDirectoryFileHeader {
     /// uncompressed size
     pub uncompressed_size: u32,
 
+    /// [Self::compressed_size], or the one from the zip64 extra field if
+    /// that's a 0xFFFFFFFF placeholder
+    #[serde(skip)]
+    pub zip64_compressed_size: u64,
+
+    /// [Self::uncompressed_size], or the one from the zip64 extra field if
+    /// that's a 0xFFFFFFFF placeholder
+    #[serde(skip)]
+    pub zip64_uncompressed_size: u64,
+
     /// disk number start
     pub disk_nbr_start: u16,
 
@@ -50,43 +140,153 @@ pub struct CentralDirectoryFileHeader {
     /// name field
     pub name: String,
 
+    /// length of the raw, undecoded name field in bytes
+    pub name_length: usize,
+
     /// extra field
     pub extra: Vec<u8>,
 
+    /// extra field length, as declared in the header
+    pub extra_length: u16,
+
+    /// Ratio of uncompressed to compressed size, as declared, if the
+    /// compressed size isn't zero. Sizes in the zip64 extra field are used
+    /// instead of their 0xFFFFFFFF placeholders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression_ratio: Option<f64>,
+
+    /// Whether the name starts with a slash or drive letter, or has a `..`
+    /// component, so that extracting it naively may write outside the
+    /// destination
+    pub is_path_traversal: bool,
+
+    /// The name the way `unzip` extracts it, only present if it's a path
+    /// traversal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sanitized_name: Option<String>,
+
+    /// Number of components of the name, see [ParseOptions::max_path_depth]
+    pub path_depth: usize,
+
+    /// The name as decoded by each supported encoding, if asked for with
+    /// [ParseOptions::name_encodings] and the name isn't plain ASCII
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_under_encodings: Option<NameUnderEncodings>,
+
     /// comment field
     pub comment: String,
 
+    /// UTF-8 comment from the Unicode Comment extra field (0x6375), if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unicode_comment: Option<String>,
+
     /// File mode.
     pub mode: Mode,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aex: Option<ExtraAexField>,
+
+    /// The raw, undecoded name field
+    #[serde(skip)]
+    pub(crate) raw_name: Vec<u8>,
+
+    /// The raw, undecoded comment field
+    #[serde(skip)]
+    pub(crate) raw_comment: Vec<u8>,
+
+    /// The Unicode Comment extra field, kept for the checks
+    #[serde(skip)]
+    pub(crate) unicode_comment_field: Option<ExtraUnicodeCommentField<'static>>,
+}
+
+/// One name, decoded by each encoding an archive may have used for it. The
+/// archive picks a single encoding for all names, so these show whether that
+/// choice changes what a name means.
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct NameUnderEncodings {
+    /// Decoded as CP-437, the original encoding of the zip format
+    pub cp437: String,
+
+    /// Decoded as Shift JIS, with invalid sequences replaced by U+FFFD
+    pub shift_jis: String,
+
+    /// Decoded as UTF-8, with invalid sequences replaced by U+FFFD
+    pub utf8_lossy: String,
+}
+
+impl NameUnderEncodings {
+    /// Decodes `name`, or returns `None` if it's ASCII and thus decodes the
+    /// same either way
+    fn new(name: &[u8]) -> Option<Self> {
+        (!name.is_ascii()).then(|| NameUnderEncodings {
+            cp437: Encoding::Cp437.decode_lossy(name),
+            shift_jis: Encoding::ShiftJis.decode_lossy(name),
+            utf8_lossy: Encoding::Utf8.decode_lossy(name),
+        })
+    }
 }
 
 impl CentralDirectoryFileHeader {
-    fn from_rc_zip(value: &rc_zip::parse::CentralDirectoryFileHeader<'_>, entry: &Entry) -> Self {
+    fn from_rc_zip(
+        value: &rc_zip::parse::CentralDirectoryFileHeader<'_>,
+        entry: &Entry,
+        options: &ParseOptions,
+    ) -> Self {
+        let unicode_comment_field =
+            value
+                .unicode_comment_field()
+                .map(|field| ExtraUnicodeCommentField {
+                    comment: field.comment.into_owned().into(),
+                    ..field
+                });
         CentralDirectoryFileHeader {
             creator_version: value.creator_version,
             reader_version: value.reader_version,
             flags: value.flags,
+            flags_decoded: value.flags.into(),
             method: value.method,
             modified: entry.modified,
+            timestamp_source: entry.timestamp_source,
             crc32: value.crc32,
             compressed_size: value.compressed_size,
             uncompressed_size: value.uncompressed_size,
+            zip64_compressed_size: entry.compressed_size,
+            zip64_uncompressed_size: entry.uncompressed_size,
+            compression_ratio: compression_ratio(entry.uncompressed_size, entry.compressed_size),
             disk_nbr_start: value.disk_nbr_start,
             internal_attrs: value.internal_attrs,
             external_attrs: value.external_attrs,
             header_offset: value.header_offset,
             name: entry.name.clone(),
+            path_depth: findings::path_depth(&entry.name),
+            is_path_traversal: findings::is_path_traversal(&entry.name),
+            sanitized_name: findings::is_path_traversal(&entry.name)
+                .then(|| findings::sanitized_name(&entry.name)),
+            name_length: value.name.len(),
             extra: value.extra.to_vec(),
+            extra_length: value.extra_length,
+            name_under_encodings: options
+                .name_encodings
+                .then(|| NameUnderEncodings::new(&value.name))
+                .flatten(),
             comment: entry.comment.clone(),
+            unicode_comment: unicode_comment_field
+                .as_ref()
+                .map(|field| String::from_utf8_lossy(&field.comment).into_owned()),
             mode: entry.mode,
             aex: entry.aex,
+            raw_name: value.name.to_vec(),
+            raw_comment: value.comment.to_vec(),
+            unicode_comment_field,
         }
     }
 }
 
+/// A local file header, along with what was measured reading the entry's data.
+///
+/// Local file headers have no internal or external attributes, so there is
+/// no `mode` here: see the [CentralDirectoryFileHeader] instead. The sizes
+/// and CRC-32 may be zero, deferred to a data descriptor.
 #[derive(serde::Serialize)]
 pub struct LocalFileHeader {
     /// version needed to extract
@@ -95,6 +295,9 @@ pub struct LocalFileHeader {
     /// general purpose bit flag
     pub flags: u16,
 
+    /// [Self::flags] broken out into its meaningful bits
+    pub flags_decoded: DecodedFlags,
+
     /// compression method
     pub method: Method,
 
@@ -111,6 +314,10 @@ pub struct LocalFileHeader {
     /// See [Self::accessed] for caveats.
     pub accessed: Option<DateTime<Utc>>,
 
+    /// Where the timestamps come from: the MS-DOS date and time, or a more
+    /// precise extra field
+    pub timestamp_source: TimestampSource,
+
     /// crc-32
     pub crc32: u32,
 
@@ -120,6 +327,12 @@ pub struct LocalFileHeader {
     /// uncompressed size
     pub uncompressed_size: u64,
 
+    /// Ratio of uncompressed to compressed size, as declared, if the
+    /// compressed size isn't zero, which it is when the sizes are deferred
+    /// to a data descriptor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression_ratio: Option<f64>,
+
     /// Offset of the local file header in the zip file
     ///
     /// ```text
@@ -148,59 +361,239 @@ pub struct LocalFileHeader {
     /// file name
     pub name: String,
 
+    /// length of the raw, undecoded name field in bytes
+    pub name_length: usize,
+
+    /// Whether the name starts with a slash or drive letter, or has a `..`
+    /// component, see [CentralDirectoryFileHeader::is_path_traversal]
+    pub is_path_traversal: bool,
+
+    /// The name the way `unzip` extracts it, only present if it's a path
+    /// traversal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sanitized_name: Option<String>,
+
     /// extra field
     pub extra: Vec<u8>,
 
+    /// extra field length, as declared in the header
+    pub extra_length: u16,
+
     /// method-specific fields
     pub method_specific: MethodSpecific,
 
-    /// File mode.
-    pub mode: Mode,
-
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aex: Option<ExtraAexField>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aex_data: Option<AexData>,
+
+    /// Whether this build has a decompressor for [Self::method]. If not, the
+    /// data isn't read, so the measured sizes are 0 and there's no content.
+    pub method_supported: bool,
+
+    /// Amount of compressed bytes actually read while decompressing the entry
+    pub measured_compressed: u64,
+
+    /// Amount of bytes actually produced while decompressing the entry
+    ///
+    /// For AE-x encrypted entries that couldn't be decrypted, this is the size
+    /// of the (still encrypted) data, not of the plaintext.
+    pub measured_uncompressed: u64,
+
+    /// Whether the CRC-32 and sizes in the data descriptor match those
+    /// measured while decompressing, only present for entries with a data
+    /// descriptor whose data could be checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub descriptor_matches_data: Option<bool>,
+
+    /// The first decompressed bytes as hex, if asked for with
+    /// [ParseOptions::content_preview_bytes]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_preview: Option<String>,
+
+    /// Whether there is more content than `content_preview` shows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_preview_truncated: Option<bool>,
+
+    /// Kind of content, as recognized by its magic bytes, e.g. `PNG` or `ELF`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detected_type: Option<&'static str>,
+
+    /// Where the data of a stored entry starts and the boundary it has to be
+    /// aligned to, if asked for with [ParseOptions::check_alignment]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alignment: Option<Alignment>,
+
+    /// Whether the data, as long as either header declares, runs past the
+    /// start of the central directory
+    pub overruns_central_directory: bool,
+
+    /// Whether this header declares a CRC-32 of 0 for nonempty content,
+    /// without a data descriptor to supply the real one. Readers that skip
+    /// checking a CRC-32 of 0 then accept any content.
+    pub zero_crc_nonempty: bool,
+
+    /// Whether the central directory header says this is a directory, by its
+    /// mode or by a name ending in `/`, yet it has content
+    pub suspicious_directory_entry: bool,
+
+    /// Digest of the decompressed content, if asked for with
+    /// [ParseOptions::digest] and the content could be decrypted
+    #[serde(flatten)]
+    pub digest: Option<EntryDigest>,
+
+    /// The raw, undecoded name field
+    #[serde(skip)]
+    pub(crate) raw_name: Vec<u8>,
+
+    /// The first bytes of the decompressed content, used by content-aware checks
+    #[serde(skip)]
+    pub(crate) content_sample: Vec<u8>,
+
+    /// Whether the data descriptor had to be read with the other size width
+    /// than the local file header implies
+    #[serde(skip)]
+    pub(crate) data_descriptor_size_ambiguous: bool,
+
+    /// Whether there is a zip64 extended information extra field
+    #[serde(skip)]
+    pub(crate) zip64_extra_field: bool,
+
+    /// Whether either size is 0xFFFFFFFF, meaning it's in the zip64 extra field
+    #[serde(skip)]
+    pub(crate) zip64_size_sentinel: bool,
 }
 
 impl LocalFileHeader {
     fn from_rc_zip<F: HasCursor>(
         entry: EntryHandle<'_, F>,
-        parsed_ranges: Rc<Mutex<ParsedRanges>>,
+        parsed_ranges: Rc<RefCell<ParsedRanges>>,
+        options: &ParseOptions,
     ) -> Result<Self, Error> {
-        let (value, aex_data) = entry.local_header(parsed_ranges)?.ok_or(Error {
-            error: format!("Can't get local file header for \"{}\"", entry.name),
-        })?;
+        let mut hasher = options.digest.map(digest::Hasher::new);
+        let result = entry.local_header_with(
+            parsed_ranges,
+            options.content_sample_length(),
+            options.password.as_deref(),
+            |data| {
+                if let Some(hasher) = &mut hasher {
+                    hasher.update(data)
+                }
+            },
+        );
+        let method_supported = !result.as_ref().is_err_and(is_unsupported_method_error);
+        let info = match result {
+            // still report what the header says, just without measurements
+            Err(_) if !method_supported => {
+                entry.read_local_header()?.map(|header| LocalHeaderInfo {
+                    header,
+                    aex_data: None,
+                    metrics: EntryReadMetrics {
+                        compressed_size: 0,
+                        uncompressed_size: 0,
+                        crc32: 0,
+                        data_descriptor_size_ambiguous: false,
+                        descriptor_matches_data: None,
+                    },
+                    content_prefix: Vec::new(),
+                })
+            }
+            result => result.map_err(|error| Error {
+                deflate_failed: is_deflate_error(&error),
+                ..Error::from(error)
+            })?,
+        }
+        .ok_or(Error::from(format_args!(
+            "Can't get local file header for \"{}\"",
+            entry.name
+        )))?;
+        let value = info.header;
+        let suspicious_directory_entry = (entry.mode.has(Mode::DIR) && entry.uncompressed_size > 0)
+            || (entry.name.ends_with('/') && info.metrics.uncompressed_size > 0);
         let entry = value.as_entry()?;
+        // without the password, there's no content to hash
+        let decrypted = value.flags & findings::FLAG_ENCRYPTED == 0
+            || info
+                .aex_data
+                .as_ref()
+                .is_some_and(|aex_data| aex_data.password_correct == Some(true));
+
+        let preview_length = options.content_preview_length();
+        let preview = (preview_length > 0).then(|| {
+            let preview = &info.content_prefix[..preview_length.min(info.content_prefix.len())];
+            (
+                content::to_hex(preview),
+                info.metrics.uncompressed_size > preview.len() as u64,
+            )
+        });
 
         Ok(LocalFileHeader {
             reader_version: value.reader_version,
             flags: value.flags,
+            flags_decoded: value.flags.into(),
             method: value.method,
             modified: entry.modified,
             created: entry.created,
             accessed: entry.accessed,
+            timestamp_source: entry.timestamp_source,
             crc32: value.crc32,
             compressed_size: entry.compressed_size,
             uncompressed_size: entry.uncompressed_size,
+            compression_ratio: compression_ratio(entry.uncompressed_size, entry.compressed_size),
             gid: entry.gid,
             uid: entry.uid,
             header_offset: entry.header_offset,
+            is_path_traversal: findings::is_path_traversal(&entry.name),
+            sanitized_name: findings::is_path_traversal(&entry.name)
+                .then(|| findings::sanitized_name(&entry.name)),
             name: entry.name,
+            name_length: value.name.len(),
             extra: value.extra.to_vec(),
+            extra_length: value.extra_length,
             method_specific: value.method_specific,
-            mode: entry.mode,
             aex: entry.aex,
-            aex_data: aex_data.to_owned(),
+            aex_data: info.aex_data,
+            method_supported,
+            measured_compressed: info.metrics.compressed_size,
+            measured_uncompressed: info.metrics.uncompressed_size,
+            descriptor_matches_data: info.metrics.descriptor_matches_data,
+            content_preview_truncated: preview.as_ref().map(|(_, truncated)| *truncated),
+            content_preview: preview.map(|(hex, _)| hex),
+            detected_type: content::detected_type(&info.content_prefix),
+            alignment: None,
+            overruns_central_directory: false,
+            zero_crc_nonempty: findings::zero_crc_nonempty(
+                value.flags,
+                value.crc32,
+                entry.uncompressed_size,
+                entry.aex.as_ref(),
+            ),
+            suspicious_directory_entry,
+            digest: hasher.filter(|_| decrypted).map(digest::Hasher::finish),
+            raw_name: value.name.to_vec(),
+            content_sample: info.content_prefix,
+            data_descriptor_size_ambiguous: info.metrics.data_descriptor_size_ambiguous,
+            zip64_extra_field: value.has_zip64_extra_field(),
+            zip64_size_sentinel: value.compressed_size == u32::MAX
+                || value.uncompressed_size == u32::MAX,
         })
     }
 }
 
 /// File metadata which consists of an `Entry`, and some additional data from  the`CentralDirectoryFileHeader`
-struct FileMetadata {
-    central: CentralDirectoryFileHeader,
-    local: Result<LocalFileHeader, Error>,
+pub struct FileMetadata {
+    /// Identifier of the entry that doesn't depend on its index, see [entry_id]
+    pub id: String,
+    /// The entry's central directory header
+    pub central: CentralDirectoryFileHeader,
+    /// The entry's local file header, or why it couldn't be read
+    pub local: Result<LocalFileHeader, Error>,
+    /// Absolute offset of the local header, corrected for any prepended data
+    pub header_offset: u64,
+    /// Fields the central directory and local file headers disagree on, as
+    /// `field: central=…, local=…`
+    pub discrepancies: Vec<String>,
 }
 
 impl serde::Serialize for FileMetadata {
@@ -209,91 +602,2215 @@ impl serde::Serialize for FileMetadata {
     where
         S: serde::Serializer,
     {
-        let mut file_metadata = serializer.serialize_struct("FileMetadata", 2)?;
+        let mut file_metadata = serializer.serialize_struct("FileMetadata", 4)?;
+        file_metadata.serialize_field("id", &self.id)?;
         file_metadata.serialize_field("central", &self.central)?;
         match &self.local {
             Ok(local) => file_metadata.serialize_field("local", &local)?,
             Err(error) => file_metadata.serialize_field("local", &error)?,
         }
+        if !self.discrepancies.is_empty() {
+            file_metadata.serialize_field("discrepancies", &self.discrepancies)?;
+        }
         file_metadata.end()
     }
 }
 
+/// Two parsed ranges that claim the same bytes
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct Overlap {
+    /// The range that starts first
+    pub first: ParsedRange,
+
+    /// The range that starts last
+    pub second: ParsedRange,
+
+    /// Start of the bytes both ranges claim
+    pub start: u64,
+
+    /// End of the bytes both ranges claim (excluding)
+    pub end: u64,
+}
+
+/// Version of the shape of the JSON report, reported as `format_version`.
+///
+/// This is bumped whenever a property is removed, renamed or changes type or
+/// meaning. Adding properties, finding codes or enum values is not considered
+/// a breaking change, so consumers should ignore what they don't know.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// The report on an archive, as returned by [analyze_file]. Serialized, this
+/// is the JSON that [parse_file] returns; see the README for what each
+/// property means.
 #[derive(serde::Serialize)]
-struct ZipMetadata<'a> {
-    eocd: &'a EndOfCentralDirectory<'static>,
-    encoding: Encoding,
-    size: u64,
-    comment: &'a String,
-    contents: Vec<FileMetadata>,
-    parsed_ranges: ParsedRanges,
+pub struct ZipMetadata {
+    /// See [FORMAT_VERSION]
+    pub format_version: u32,
+
+    /// Version of ziplinter that produced the report
+    pub ziplinter_version: &'static str,
+
+    /// The end of central directory records
+    pub eocd: EndOfCentralDirectory<'static>,
+
+    /// Text encoding used for names and comments
+    pub encoding: Encoding,
+
+    /// How [Self::encoding] was chosen
+    pub encoding_detection: EncodingDetection,
+
+    /// Size of the file in bytes
+    pub size: u64,
+
+    /// Number of disks (segments) the archive spans, according to the end of
+    /// central directory record
+    pub disk_count: u16,
+
+    /// Whether the archive spans more than one disk, so that on its own, a
+    /// single segment holds only part of it
+    pub is_split: bool,
+
+    /// Number of entries by the disk their local file header is on, as
+    /// `disk_nbr_start` in the central directory
+    pub entries_per_disk: BTreeMap<u16, usize>,
+
+    /// The archive comment
+    pub comment: String,
+
+    /// The entries, in the order of the central directory
+    pub contents: Vec<FileMetadata>,
+
+    /// The ranges of the file that were parsed
+    pub parsed_ranges: ParsedRanges,
+
+    /// The ranges of the file that no parsed range covers
+    pub gaps: Vec<Range<u64>>,
+
+    /// The fraction of the file that parsed ranges cover
+    pub coverage: f64,
+
+    /// The pairs of parsed ranges that claim the same bytes, with identical
+    /// ranges of the same kind paired only once, see [ParsedRanges::overlaps]
+    pub overlaps: Vec<Overlap>,
+
+    /// Whether there are more overlapping pairs than [Self::overlaps] lists
+    pub overlaps_truncated: bool,
+
+    /// The names shared by more than one entry of the central directory
+    pub duplicate_names: Vec<String>,
+
+    /// Number of entries and their sizes, by compression method
+    pub method_stats: BTreeMap<String, MethodStats>,
+
+    /// The distinct compression methods in the central directory
+    pub methods_present: Vec<Method>,
+
+    /// Those of [Self::methods_present] this build can't decompress
+    pub methods_undecodable: Vec<Method>,
+
+    /// How much of the parsed ranges is structure rather than file data
+    pub overhead: Overhead,
+
+    /// The highest compression ratio of any header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_compression_ratio: Option<f64>,
+
+    /// Distribution of the compression ratios of the entries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ratio_distribution: Option<RatioDistribution>,
+
+    /// Whether the distribution of compression ratios looks like a zip bomb
+    pub bomb_fingerprint: bool,
+
+    /// The APK signing block, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apk_signing_block: Option<ApkSigningBlock>,
+
+    /// What precedes the first entry, if anything
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+
+    /// The other format the bytes before the first entry start like, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polyglot_candidate: Option<PolyglotCandidate>,
+
+    /// Whether all stored entries are aligned, if asked for with
+    /// [ParseOptions::check_alignment]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zipalign_ok: Option<bool>,
+
+    /// Everything the checks found
+    pub findings: Vec<Finding>,
+
+    /// Whether the archive conforms to the zip specification
+    pub strict_conformant: bool,
+
+    /// The requirements of the zip specification the archive violates
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conformance_violations: Vec<String>,
+
+    /// See [Self::entries_by_finding]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub findings_index: BTreeMap<&'static str, Vec<usize>>,
+
+    /// What it took to analyze the archive, if asked for with
+    /// [ParseOptions::collect_stats]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<Stats>,
+}
+
+impl ZipMetadata {
+    /// Groups the indices of entries in `contents` by the codes of the
+    /// findings they triggered, e.g. to list every entry with a path traversal.
+    pub fn entries_by_finding(&self) -> BTreeMap<&'static str, Vec<usize>> {
+        let mut index: BTreeMap<&'static str, Vec<usize>> = BTreeMap::new();
+        for finding in &self.findings {
+            if let Some(entry) = finding.entry {
+                let entries = index.entry(finding.code).or_default();
+                if !entries.contains(&entry) {
+                    entries.push(entry);
+                }
+            }
+        }
+        index
+    }
+}
+
+impl ZipMetadata {
+    /// Keeps only the entries whose name `keep` accepts, along with the
+    /// findings about them and those about the archive as a whole
+    fn retain_entries(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        let mut kept = 0;
+        let new_indices: Vec<Option<usize>> = self
+            .contents
+            .iter()
+            .map(|file| {
+                keep(&file.central.name).then(|| {
+                    kept += 1;
+                    kept - 1
+                })
+            })
+            .collect();
+        let mut indices = new_indices.iter();
+        self.contents
+            .retain(|_| indices.next().is_some_and(Option::is_some));
+        self.findings.retain_mut(|finding| match finding.entry {
+            Some(entry) => {
+                finding.entry = new_indices[entry];
+                finding.entry.is_some()
+            }
+            None => true,
+        });
+        self.findings_index = self.entries_by_finding();
+    }
+}
+
+/// Whether `name` matches `pattern`, see [ParseOptions::entry_filter]
+fn name_matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
 }
 
-impl<'a, F> From<&'a mut ArchiveHandle<'a, F>> for ZipMetadata<'a>
+impl<'a, F> From<&'a mut ArchiveHandle<'a, F>> for ZipMetadata
 where
     F: HasCursor,
 {
     fn from(archive: &'a mut ArchiveHandle<'a, F>) -> Self {
-        let contents = archive
+        Self::new_lenient(archive, &ParseOptions::default(), &mut |_| {})
+    }
+}
+
+/// Collects findings, passing each one on as soon as it's found
+struct FindingSink<'a, E> {
+    /// All findings so far, whatever their severity
+    findings: Vec<Finding>,
+
+    /// Findings the options leave out of the report aren't passed on
+    options: &'a ParseOptions,
+
+    on_finding: &'a mut dyn FnMut(&Finding),
+
+    /// Decides whether a violated requirement of the spec ends the analysis:
+    /// strict mode fails with an [Error], lenient mode can't fail at all
+    on_violation: fn(String) -> Result<(), E>,
+}
+
+impl<E> FindingSink<'_, E> {
+    /// Adds `findings`, passing on those the options report. In strict mode,
+    /// this stops at the first finding that violates the spec, see
+    /// [Self::reject].
+    fn extend(
+        &mut self,
+        findings: impl IntoIterator<Item = Finding>,
+        contents: &[FileMetadata],
+    ) -> Result<(), E> {
+        for mut finding in findings {
+            if let Some(entry) = finding.entry {
+                finding.offset.get_or_insert(contents[entry].header_offset);
+                finding.entry_id = Some(contents[entry].id.clone());
+            }
+            if self.options.reports(finding.code, finding.severity) {
+                (self.on_finding)(&finding);
+            }
+            let violation = conformance::finding_violation(&finding);
+            self.findings.push(finding);
+            self.reject(violation)?;
+        }
+        Ok(())
+    }
+
+    /// Passes the first of `violations` on, so that a strict analysis ends
+    /// at the first requirement of the spec that's violated
+    fn reject(&self, violations: impl IntoIterator<Item = String>) -> Result<(), E> {
+        match violations.into_iter().next() {
+            Some(violation) => (self.on_violation)(violation),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Ends a strict analysis at the first requirement of the spec that's violated
+fn strict_violation(violation: String) -> Result<(), Error> {
+    Err(Error::from(format_args!(
+        "not strictly conformant: {violation}"
+    )))
+}
+
+impl ZipMetadata {
+    /// Analyzes `archive`, failing in strict mode at the first requirement
+    /// of the spec that's violated, see [ParseOptions::strict]
+    fn new<F: HasCursor>(
+        archive: &mut ArchiveHandle<'_, F>,
+        options: &ParseOptions,
+        on_finding: &mut dyn FnMut(&Finding),
+    ) -> Result<Self, Error> {
+        if options.strict {
+            Self::analyze_archive(archive, options, on_finding, strict_violation)
+        } else {
+            Ok(Self::new_lenient(archive, options, on_finding))
+        }
+    }
+
+    /// Analyzes `archive` all the way through, whatever `options.strict` says
+    fn new_lenient<F: HasCursor>(
+        archive: &mut ArchiveHandle<'_, F>,
+        options: &ParseOptions,
+        on_finding: &mut dyn FnMut(&Finding),
+    ) -> Self {
+        match Self::analyze_archive(archive, options, on_finding, |_| Ok::<(), Infallible>(())) {
+            Ok(metadata) => metadata,
+            Err(never) => match never {},
+        }
+    }
+
+    fn analyze_archive<F: HasCursor, E>(
+        archive: &mut ArchiveHandle<'_, F>,
+        options: &ParseOptions,
+        on_finding: &mut dyn FnMut(&Finding),
+        on_violation: fn(String) -> Result<(), E>,
+    ) -> Result<Self, E> {
+        let mut sink = FindingSink {
+            findings: Vec::new(),
+            options,
+            on_finding,
+            on_violation,
+        };
+        sink.reject(conformance::eocd_violations(
+            &archive.eocd,
+            archive.entries().count(),
+        ))?;
+
+        let mut entries_duration = Duration::ZERO;
+        let mut contents: Vec<FileMetadata> = Vec::new();
+        let mut ids = EntryIds::default();
+        for (index, (entry, directory_header)) in archive
             .entries()
             .zip(archive.directory_headers.iter())
-            .map(|(entry, directory_header)| FileMetadata {
-                central: CentralDirectoryFileHeader::from_rc_zip(directory_header, entry.entry),
-                local: LocalFileHeader::from_rc_zip(entry, archive.parsed_ranges.clone()),
+            .enumerate()
+        {
+            let read_start = Instant::now();
+            let central_entry = entry.entry;
+            let mut file = FileMetadata {
+                id: ids.next(&entry.entry.name, entry.entry.header_offset),
+                central: CentralDirectoryFileHeader::from_rc_zip(
+                    directory_header,
+                    entry.entry,
+                    options,
+                ),
+                header_offset: entry.entry.header_offset,
+                local: LocalFileHeader::from_rc_zip(entry, archive.parsed_ranges.clone(), options),
+                discrepancies: Vec::new(),
+            };
+            let overrun = content::data_offset(archive.file(), file.header_offset).and_then(
+                |(data_start, local_compressed_size)| {
+                    findings::central_directory_overrun(
+                        index,
+                        data_start,
+                        central_entry,
+                        match &file.local {
+                            Ok(local) => local.compressed_size,
+                            Err(_) => local_compressed_size as u64,
+                        },
+                        archive.eocd.directory_offset(),
+                    )
+                },
+            );
+            if let Ok(local) = &mut file.local {
+                file.discrepancies = findings::discrepancies(central_entry, &file.central, local);
+                local.overruns_central_directory = overrun.is_some();
+            }
+            entries_duration += read_start.elapsed();
+
+            let mut entry_findings =
+                findings::check_entry(index, &file.central, file.local.as_ref().ok(), options);
+            entry_findings.extend(overrun);
+            if let Err(error) = &mut file.local {
+                let wanted = options.reports("deflate_declared_but_invalid", Severity::Warning)
+                    || options.content_preview_length() > 0;
+                if error.deflate_failed && wanted {
+                    let raw = content::raw_data_prefix(
+                        archive.file(),
+                        file.header_offset,
+                        content::CONTENT_SAMPLE_LENGTH.max(options.content_preview_length()),
+                    )
+                    .unwrap_or_default();
+                    if options.content_preview_length() > 0 {
+                        let preview = &raw[..raw.len().min(options.content_preview_length())];
+                        error.content_preview_as_stored = Some(content::to_hex(preview));
+                    }
+                    if options.reports("deflate_declared_but_invalid", Severity::Warning) {
+                        entry_findings.push(findings::deflate_declared_but_invalid(
+                            index,
+                            &file.central,
+                            &raw,
+                        ));
+                    }
+                }
+            }
+            if options.detect_mislabeled_methods
+                && options.reports("method_mislabeled", Severity::Warning)
+                && file.local.is_err()
+            {
+                entry_findings.extend(findings::method_mislabeled(
+                    archive.file(),
+                    index,
+                    central_entry,
+                ));
+            }
+            sink.reject(conformance::entry_violations(&file))?;
+            contents.push(file);
+            sink.extend(entry_findings, &contents)?;
+        }
+
+        // annotated first, so that checks of the space between structures see it
+        let apk_signing_block =
+            apk::find_signing_block(archive.file(), archive.eocd.directory_offset());
+        if let (Some(block), Some(mut parsed_ranges)) = (
+            &apk_signing_block,
+            ParsedRanges::lock(&archive.parsed_ranges),
+        ) {
+            block.annotate(&mut parsed_ranges);
+        }
+
+        if let Some(parsed_ranges) = ParsedRanges::lock(&archive.parsed_ranges) {
+            let entries = findings::entries_by_header_offset(&contents);
+            sink.extend(findings::check_ranges(&parsed_ranges, &entries), &contents)?;
+            sink.extend(
+                findings::check_directory_size(&parsed_ranges, &archive.eocd),
+                &contents,
+            )?;
+            if options.reports("structure_overlap", Severity::Warning) {
+                sink.extend(findings::check_overlaps(&parsed_ranges), &contents)?;
+            }
+            if options.reports("local_header_size_inconsistent", Severity::Warning) {
+                sink.extend(
+                    findings::check_local_header_sizes(&parsed_ranges, &contents, &entries),
+                    &contents,
+                )?;
+            }
+            if options.reports("post_descriptor_gap", Severity::Warning) {
+                sink.extend(
+                    findings::check_post_descriptor_gaps(&parsed_ranges, &contents),
+                    &contents,
+                )?;
+            }
+            if options.reports("data_region_size_mismatch", Severity::Warning) {
+                sink.extend(
+                    findings::check_data_regions(&parsed_ranges, &contents),
+                    &contents,
+                )?;
+            }
+            if options.check_alignment {
+                let misaligned = zipalign::check_alignment(&parsed_ranges, &mut contents, &entries);
+                sink.extend(misaligned, &contents)?;
+            }
+        }
+        sink.extend(findings::check_empty_directory(&archive.eocd), &contents)?;
+        if options.reports("eocd_signature_collision", Severity::Warning) {
+            sink.extend(
+                findings::check_eocd_signatures(
+                    archive.file(),
+                    archive.size,
+                    archive.eocd.dir.offset,
+                ),
+                &contents,
+            )?;
+        }
+        if options.reports("repeated_identical_entries", Severity::Warning) {
+            sink.extend(
+                findings::check_repeated_entries(archive.entries().map(|entry| entry.entry)),
+                &contents,
+            )?;
+        }
+        if options.reports("offset_cycle", Severity::Error) {
+            sink.extend(findings::check_offset_cycles(&contents), &contents)?;
+        }
+        let layout = contents
+            .iter()
+            .map(|file| file.header_offset)
+            .min()
+            .and_then(|first_header_offset| {
+                layout::find_layout(archive.file(), first_header_offset)
+            });
+        let polyglot_candidate = layout
+            .as_ref()
+            .and_then(|layout| layout::polyglot_candidate(archive.file(), layout));
+        if options.reports("polyglot_candidate", Severity::Warning) {
+            sink.extend(
+                polyglot_candidate.as_ref().map(findings::check_polyglot),
+                &contents,
+            )?;
+        }
+        let mut findings = sink.findings;
+        let conformance_violations = conformance::violations(&archive.eocd, &contents, &findings);
+        findings.retain(|finding| options.reports(finding.code, finding.severity));
+
+        let (methods_present, methods_undecodable) =
+            method_stats::methods_present(archive.entries().map(|entry| entry.entry));
+
+        let zipalign_ok = options.check_alignment.then(|| {
+            contents
+                .iter()
+                .filter_map(|file| file.local.as_ref().ok()?.alignment.as_ref())
+                .all(Alignment::is_aligned)
+        });
+
+        let parsed_ranges = ParsedRanges::lock(&archive.parsed_ranges)
+            .map(|parsed_ranges| parsed_ranges.clone())
+            .unwrap_or_default();
+        let gaps = parsed_ranges.gaps(archive.size);
+        let coverage = parsed_ranges.coverage(archive.size);
+        let range_overlaps = parsed_ranges.overlaps(findings::MAX_OVERLAPS);
+        let overlaps_truncated = range_overlaps.truncated;
+        let overlaps = range_overlaps
+            .pairs
+            .into_iter()
+            .map(|(first, second, shared)| Overlap {
+                first: first.clone(),
+                second: second.clone(),
+                start: shared.start,
+                end: shared.end,
             })
             .collect();
+        let overhead = overhead::overhead(&parsed_ranges);
+        let duplicate_names =
+            duplicate_names(archive.entries().map(|entry| entry.entry.name.as_str()));
+        let max_compression_ratio = contents
+            .iter()
+            .flat_map(|file| {
+                let local = file.local.as_ref().ok();
+                [
+                    file.central.compression_ratio,
+                    local.and_then(|local| local.compression_ratio),
+                ]
+            })
+            .flatten()
+            .max_by(f64::total_cmp);
+        let ratio_distribution = RatioDistribution::new(archive.entries().map(|entry| entry.entry));
+        let bomb_fingerprint = ratio_distribution
+            .as_ref()
+            .is_some_and(RatioDistribution::is_bomb_fingerprint);
+
+        let disk_count =
+            u16::try_from(archive.eocd.disk_nbr().saturating_add(1)).unwrap_or(u16::MAX);
+        let mut entries_per_disk = BTreeMap::new();
+        for file in &contents {
+            *entries_per_disk
+                .entry(file.central.disk_nbr_start)
+                .or_default() += 1;
+        }
 
-        ZipMetadata {
-            eocd: &archive.eocd,
+        let mut metadata = ZipMetadata {
+            format_version: FORMAT_VERSION,
+            ziplinter_version: env!("CARGO_PKG_VERSION"),
+            eocd: archive.eocd.clone(),
             encoding: archive.encoding,
+            encoding_detection: archive.encoding_detection,
             size: archive.size,
-            comment: &archive.comment,
+            disk_count,
+            is_split: disk_count > 1,
+            entries_per_disk,
+            comment: archive.comment.clone(),
             contents,
-            parsed_ranges: archive.parsed_ranges.try_lock().unwrap().clone(),
+            parsed_ranges,
+            gaps,
+            coverage,
+            overlaps,
+            overlaps_truncated,
+            duplicate_names,
+            method_stats: method_stats::method_stats(archive.entries().map(|entry| entry.entry)),
+            methods_present,
+            methods_undecodable,
+            overhead,
+            max_compression_ratio,
+            ratio_distribution,
+            bomb_fingerprint,
+            apk_signing_block,
+            layout,
+            polyglot_candidate,
+            zipalign_ok,
+            findings,
+            strict_conformant: conformance_violations.is_empty(),
+            conformance_violations,
+            findings_index: BTreeMap::new(),
+            stats: None,
+        };
+        metadata.findings_index = metadata.entries_by_finding();
+        if options.collect_stats {
+            let read_metrics = archive.read_metrics;
+            let locals = metadata
+                .contents
+                .iter()
+                .filter_map(|file| file.local.as_ref().ok());
+            metadata.stats = Some(Stats {
+                bytes_read: read_metrics.bytes_read,
+                process_calls: read_metrics.process_calls,
+                peak_buffered: read_metrics.peak_buffered,
+                central_directory_micros: 0,
+                entries_micros: entries_duration.as_micros() as u64,
+                entry_bytes_read: locals.clone().map(|local| local.measured_compressed).sum(),
+                decompressed_bytes: locals.map(|local| local.measured_uncompressed).sum(),
+            });
         }
+        Ok(metadata)
     }
 }
 
+/// Why an archive, or one of its entries, couldn't be analyzed
 #[derive(serde::Serialize)]
-struct Error {
+pub struct Error {
     error: String,
+
+    /// Whether this is a failure to decompress Deflate data
+    #[serde(skip)]
+    deflate_failed: bool,
+
+    /// For Deflate data that couldn't be decompressed, a preview of the data
+    /// read as if it were stored, see [ParseOptions::content_preview_bytes]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_preview_as_stored: Option<String>,
 }
 
 impl<T: std::fmt::Debug> From<T> for Error {
     fn from(error: T) -> Self {
         Error {
             error: format!("{error:?}"),
+            deflate_failed: false,
+            content_preview_as_stored: None,
         }
     }
 }
 
-pub fn parse_bytes(bytes: &[u8]) -> serde_json::Value {
-    match bytes.read_zip() {
-        Ok(mut archive) => serde_json::to_value(ZipMetadata::from(&mut archive)).unwrap(),
-        Err(error) => serde_json::to_value(Error::from(error)).unwrap(),
+impl Error {
+    /// The description of the error, as in the `error` property of a report
+    pub fn message(&self) -> &str {
+        &self.error
     }
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.error)
+    }
+}
+
+/// Whether `error` is that there's no decompressor for the entry's method,
+/// because it isn't supported or its feature isn't enabled
+fn is_unsupported_method_error(error: &std::io::Error) -> bool {
+    matches!(
+        error
+            .get_ref()
+            .and_then(|error| error.downcast_ref::<rc_zip::error::Error>()),
+        Some(rc_zip::error::Error::Unsupported(
+            UnsupportedError::MethodNotSupported(_) | UnsupportedError::MethodNotEnabled(_)
+        ))
+    )
+}
+
+/// Whether `error` is a failure to decompress Deflate data
+fn is_deflate_error(error: &std::io::Error) -> bool {
+    matches!(
+        error
+            .get_ref()
+            .and_then(|error| error.downcast_ref::<rc_zip::error::Error>()),
+        Some(rc_zip::error::Error::Decompression {
+            method: Method::Deflate,
+            ..
+        })
+    )
+}
+
+/// Options that control how an archive is analyzed
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Password used to decrypt AE-x encrypted entries, so that their content
+    /// can be decompressed and checked like that of any other entry
+    pub password: Option<Vec<u8>>,
+
+    /// Findings below this severity are left out of the report. Checks that
+    /// can only result in such findings are skipped altogether, unless their
+    /// findings count towards `strict_conformant`:
+    ///
+    /// - above `info`, `stored_high_entropy` and `unusual_gp_flags`, and
+    ///   content is only sampled as far as needed for `detected_type`
+    /// - above `warning`, also every check of the structure and entries that
+    ///   can only report warnings: `structure_overlap`,
+    ///   `local_header_size_inconsistent`, `post_descriptor_gap`,
+    ///   `data_region_size_mismatch`, `eocd_signature_collision`,
+    ///   `repeated_identical_entries`, `polyglot_candidate`,
+    ///   `deflate_declared_but_invalid`, `method_mislabeled`,
+    ///   `data_descriptor_size_ambiguous`, `suspicious_directory_entry`,
+    ///   `unicode_comment_mismatch`, `extra_length_mismatch`,
+    ///   `zip64_determination_inconsistent`, `excessive_path_depth`,
+    ///   `reserved_device_name` and `extension_mismatch`
+    pub min_severity: Severity,
+
+    /// When nonzero, the first this many decompressed bytes of each entry
+    /// (up to 64 KiB) are included in the report as `content_preview`
+    pub content_preview_bytes: usize,
+
+    /// Whether to include `stats` in the report: how many bytes were read
+    /// and decompressed, how many iterations it took to parse the central
+    /// directory and how long that took compared to reading the entries
+    pub collect_stats: bool,
+
+    /// Whether to include `name_under_encodings` for each entry whose name
+    /// isn't plain ASCII: the name decoded as CP-437, Shift JIS and UTF-8,
+    /// to see whether the encoding the archive was decoded with matters
+    pub name_encodings: bool,
+
+    /// Whether to reject archives that aren't `strict_conformant`: instead of
+    /// the report, the result is an error with the first requirement of the
+    /// spec the archive violates. The analysis ends there, so no findings
+    /// after it are passed on.
+    pub strict: bool,
+
+    /// Entries whose names have more components than this are reported as
+    /// `excessive_path_depth`
+    pub max_path_depth: usize,
+    /// Whether to check that the data of stored entries is aligned the way
+    /// `zipalign` aligns it for Android: reported as `alignment` of each
+    /// stored entry, and `zipalign_ok` for the whole archive
+    pub check_alignment: bool,
+
+    /// The checks whose findings are reported. Disabled checks still count
+    /// towards `strict_conformant`.
+    pub rules: RuleSet,
+
+    /// Whether to try decoding entries that fail to decode with their
+    /// declared method with the other supported methods, reporting those
+    /// that work as `method_mislabeled`. Each trial decodes at most 1 MiB.
+    pub detect_mislabeled_methods: bool,
+
+    /// The encoding to decode names and comments with, instead of guessing
+    /// it, for archives whose provenance is known. Reported as `forced` in
+    /// `encoding_detection`.
+    pub forced_encoding: Option<Encoding>,
+
+    /// The hash function to compute a digest of each entry's decompressed
+    /// content with, reported as a property of the local file header named
+    /// after it, e.g. `sha256`. Off by default, since it adds hashing every
+    /// byte of content to the work.
+    pub digest: Option<DigestAlgorithm>,
+
+    /// Only report the entries whose name is this, or starts with what comes
+    /// before it if it ends in `*`, to keep the report of a huge archive
+    /// manageable. The checks still see every entry, findings about the
+    /// other entries are left out, and archive-wide properties such as
+    /// `eocd` stay as they are.
+    pub entry_filter: Option<String>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            password: None,
+            min_severity: Severity::default(),
+            content_preview_bytes: 0,
+            collect_stats: false,
+            name_encodings: false,
+            strict: false,
+            max_path_depth: 128,
+            check_alignment: false,
+            rules: RuleSet::default(),
+            detect_mislabeled_methods: false,
+            forced_encoding: None,
+            digest: None,
+            entry_filter: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// How much decompressed content of each entry to include in the report
+    fn content_preview_length(&self) -> usize {
+        self.content_preview_bytes
+            .min(content::MAX_CONTENT_PREVIEW_LENGTH)
+    }
+
+    /// How much decompressed content of each entry to keep for content-aware
+    /// checks and the content preview
+    fn content_sample_length(&self) -> usize {
+        let checks = if self.reports("stored_high_entropy", Severity::Info) {
+            content::CONTENT_SAMPLE_LENGTH
+        } else {
+            content::SIGNATURE_LENGTH
+        };
+        checks.max(self.content_preview_length())
+    }
+
+    /// Whether findings of the check `code` at `severity` make it into the
+    /// report, see [Self::min_severity] and [Self::rules]
+    pub(crate) fn reports(&self, code: &str, severity: Severity) -> bool {
+        severity >= self.min_severity && self.rules.is_enabled(code)
+    }
+}
+
+/// The names that occur more than once in `names`, once each, in the order
+/// of their first occurrence
+fn duplicate_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut counts = BTreeMap::<&str, usize>::new();
+    let mut order = Vec::new();
+    for name in names {
+        let count = counts.entry(name).or_default();
+        if *count == 0 {
+            order.push(name);
+        }
+        *count += 1;
+    }
+    order
+        .into_iter()
+        .filter(|name| counts[name] > 1)
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Serializes a report. Serialization failures become an error report too,
+/// so that parsing never panics.
+fn to_json(report: impl serde::Serialize) -> serde_json::Value {
+    serde_json::to_value(report)
+        .unwrap_or_else(|error| serde_json::json!({ "error": error.to_string() }))
+}
+
+/// The highest severity of the findings in `report`, as returned by any of
+/// the `parse_*` functions, e.g. to decide whether to reject the archive. A
+/// report with an `error`, for an archive that couldn't be analyzed at all,
+/// counts as [Severity::Error]. `None` if there are no findings.
+pub fn max_severity(report: &serde_json::Value) -> Option<Severity> {
+    if report.get("error").is_some() {
+        return Some(Severity::Error);
+    }
+    report["findings"]
+        .as_array()?
+        .iter()
+        .filter_map(|finding| finding["severity"].as_str()?.parse().ok())
+        .max()
+}
+
+/// Analyzes an in-memory zip archive.
+///
+/// This never panics, whatever the input: archives that can't be parsed at
+/// all result in a report with just an `error` property.
+pub fn parse_bytes(bytes: &[u8]) -> serde_json::Value {
+    parse_bytes_with(bytes, &ParseOptions::default())
+}
+
+/// Like [parse_bytes], with non-default [ParseOptions].
+pub fn parse_bytes_with(bytes: &[u8], options: &ParseOptions) -> serde_json::Value {
+    analyze(&bytes, options, &mut |_| {})
+}
+
+/// Like [parse_bytes_with], but also passes each finding to `on_finding` as
+/// soon as it's found, e.g. to get early signal while scanning a huge
+/// archive. Findings below [ParseOptions::min_severity] aren't passed on.
+/// The report still has all findings.
+pub fn parse_bytes_streaming(
+    bytes: &[u8],
+    options: &ParseOptions,
+    on_finding: &mut dyn FnMut(&Finding),
+) -> serde_json::Value {
+    analyze(&bytes, options, on_finding)
+}
+
+/// Analyzes a zip archive on disk, see [parse_bytes].
 pub fn parse_file(file: &File) -> serde_json::Value {
-    match file.read_zip() {
-        Ok(mut archive) => serde_json::to_value(ZipMetadata::from(&mut archive)).unwrap(),
-        Err(error) => serde_json::to_value(Error::from(error)).unwrap(),
+    parse_file_with(file, &ParseOptions::default())
+}
+
+/// Like [parse_file], with non-default [ParseOptions].
+pub fn parse_file_with(file: &File, options: &ParseOptions) -> serde_json::Value {
+    match analyze_file_with(file, options) {
+        Ok(metadata) => to_json(metadata),
+        Err(error) => to_json(error),
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// Like [parse_file_with], passing findings on as [parse_bytes_streaming] does.
+pub fn parse_file_streaming(
+    file: &File,
+    options: &ParseOptions,
+    on_finding: &mut dyn FnMut(&Finding),
+) -> serde_json::Value {
+    analyze(file, options, on_finding)
+}
 
-    use insta::assert_json_snapshot;
-    use std::{error::Error, path::Path};
+/// Analyzes a zip archive from any seekable reader, e.g. a memory-mapped
+/// region, without reading it into memory first. See [parse_bytes].
+pub fn parse_reader<R: Read + Seek>(reader: R) -> serde_json::Value {
+    parse_reader_with(reader, &ParseOptions::default())
+}
 
-    fn process_zip_file(zip_path: &Path) -> Result<serde_json::Value, Box<dyn Error>> {
-        let file = std::fs::File::open(zip_path).unwrap();
-        let mut archive = file.read_zip()?;
+/// Like [parse_reader], with non-default [ParseOptions].
+pub fn parse_reader_with<R: Read + Seek>(reader: R, options: &ParseOptions) -> serde_json::Value {
+    analyze(&SeekableReader::new(reader), options, &mut |_| {})
+}
 
-        let metadata = ZipMetadata::from(&mut archive);
-        Ok(serde_json::to_value(metadata)?)
+/// Analyzes a zip archive on disk like [parse_file], but returns the report
+/// itself rather than its JSON, for callers that want to inspect it without
+/// going through [serde_json::Value].
+pub fn analyze_file(file: &File) -> Result<ZipMetadata, Error> {
+    analyze_file_with(file, &ParseOptions::default())
+}
+
+/// Like [analyze_file], with non-default [ParseOptions].
+pub fn analyze_file_with(file: &File, options: &ParseOptions) -> Result<ZipMetadata, Error> {
+    report(file, options, &mut |_| {})
+}
+
+fn analyze<Z: ReadZip>(
+    zip: &Z,
+    options: &ParseOptions,
+    on_finding: &mut dyn FnMut(&Finding),
+) -> serde_json::Value {
+    match report(zip, options, on_finding) {
+        Ok(metadata) => to_json(metadata),
+        Err(error) => to_json(error),
+    }
+}
+
+fn report<Z: ReadZip>(
+    zip: &Z,
+    options: &ParseOptions,
+    on_finding: &mut dyn FnMut(&Finding),
+) -> Result<ZipMetadata, Error> {
+    let start = Instant::now();
+    let mut archive = match options.forced_encoding {
+        Some(encoding) => zip.read_zip_with_encoding(encoding)?,
+        None => zip.read_zip()?,
+    };
+    let central_directory_duration = start.elapsed();
+    let mut metadata = ZipMetadata::new(&mut archive, options, on_finding)?;
+    if let Some(pattern) = &options.entry_filter {
+        metadata.retain_entries(|name| name_matches(pattern, name));
+    }
+    if let Some(stats) = &mut metadata.stats {
+        stats.central_directory_micros = central_directory_duration.as_micros() as u64;
+    }
+    Ok(metadata)
+}
+
+/// Parses the local file header at `offset`, without consulting the central
+/// directory, e.g. to confirm an offset found elsewhere.
+///
+/// Fails if the bytes there aren't a valid local file header, including when
+/// the file ends before the header does, or if its compression method isn't
+/// supported.
+pub fn validate_local_header_at<F: HasCursor>(
+    file: &F,
+    offset: u64,
+) -> Result<rc_zip::parse::LocalFileHeader<'static>, rc_zip::error::Error> {
+    let mut cursor = file.cursor_at(offset);
+    let mut fsm = EntryFsm::new(None, None, None);
+    loop {
+        let read = cursor.read(fsm.space())?;
+        fsm.fill(read);
+        if fsm.process_till_header()?.is_some() {
+            break;
+        }
+        if read == 0 {
+            return Err(rc_zip::error::FormatError::InvalidLocalHeader.into());
+        }
+    }
+    Ok(fsm
+        .local_header_entry()
+        .clone()
+        .expect("the local header is parsed along with the entry"))
+}
+
+/// Builds just the parsed ranges of the archive in `file`, e.g. to look up
+/// what a byte belongs to with [ParsedRanges::at], without decompressing any
+/// entry or running any checks.
+///
+/// The central directory and end of central directory records are parsed
+/// as for a report. The local file header, file data and data descriptor of
+/// each entry are where the headers declare them, rather than where reading
+/// the data finds them.
+pub fn parsed_ranges_file(file: &File) -> Result<ParsedRanges, Error> {
+    let archive = file.read_zip()?;
+    let mut parsed_ranges = ParsedRanges::lock(&archive.parsed_ranges)
+        .map(|parsed_ranges| parsed_ranges.clone())
+        .unwrap_or_default();
+    for entry in archive.entries() {
+        let entry = entry.entry;
+        let Some((data_start, _)) = content::data_offset(file, entry.header_offset) else {
+            continue;
+        };
+        let name = Some(entry.name.clone());
+        parsed_ranges.insert_range(
+            entry.header_offset..data_start,
+            "local file header",
+            name.clone(),
+        );
+        parsed_ranges.insert_offset_length(
+            data_start,
+            entry.compressed_size,
+            "file data",
+            name.clone(),
+        );
+        if entry.flags & findings::FLAG_DATA_DESCRIPTOR != 0 {
+            let descriptor_start = data_start.saturating_add(entry.compressed_size);
+            let mut signature = [0u8; 4];
+            let signed = file
+                .cursor_at(descriptor_start)
+                .read_exact(&mut signature)
+                .is_ok_and(|_| signature == *b"PK\x07\x08");
+            let zip64 = entry.compressed_size >= u32::MAX as u64
+                || entry.uncompressed_size >= u32::MAX as u64;
+            // the CRC-32 and both sizes, after the optional signature
+            let length = 4 + if zip64 { 16 } else { 8 } + if signed { 4 } else { 0 };
+            parsed_ranges.insert_offset_length(descriptor_start, length, "data descriptor", name);
+        }
+    }
+    if let Some(block) = apk::find_signing_block(file, archive.eocd.directory_offset()) {
+        block.annotate(&mut parsed_ranges);
+    }
+    Ok(parsed_ranges)
+}
+
+/// Reads the raw bytes of `range` from the archive, e.g. to inspect the data
+/// a finding or parsed range points at. Fails if the range is reversed or
+/// extends past the end of the file.
+pub fn read_range(file: &File, range: Range<u64>) -> io::Result<Vec<u8>> {
+    let size = file.metadata()?.len();
+    if range.start > range.end || range.end > size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "range {}..{} is not within the {size} bytes of the file",
+                range.start, range.end
+            ),
+        ));
+    }
+    let length = usize::try_from(range.end - range.start).map_err(io::Error::other)?;
+    let mut data = vec![0u8; length];
+    file.cursor_at(range.start).read_exact(&mut data)?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use insta::assert_json_snapshot;
+    use std::{error::Error, path::Path};
+
+    fn process_zip_file(zip_path: &Path) -> Result<serde_json::Value, Box<dyn Error>> {
+        let file = std::fs::File::open(zip_path).unwrap();
+        let mut archive = file.read_zip()?;
+
+        let metadata = ZipMetadata::from(&mut archive);
+        Ok(serde_json::to_value(metadata)?)
+    }
+
+    #[test]
+    fn decrypt_aex_with_password() {
+        let bytes = std::fs::read("../testdata/aes-password.zip").unwrap();
+
+        let options = ParseOptions {
+            password: Some(b"ziplinter".to_vec()),
+            ..Default::default()
+        };
+        let value = parse_bytes_with(&bytes, &options);
+        for file in value["contents"].as_array().unwrap() {
+            let local = &file["local"];
+            assert_eq!(local["aex_data"]["password_correct"], true, "{local}");
+            assert_eq!(local["aex_data"]["authenticated"], true, "{local}");
+            assert_eq!(local["measured_uncompressed"], local["uncompressed_size"]);
+        }
+
+        let options = ParseOptions {
+            password: Some(b"wrong".to_vec()),
+            ..Default::default()
+        };
+        let value = parse_bytes_with(&bytes, &options);
+        for file in value["contents"].as_array().unwrap() {
+            assert_eq!(file["local"]["aex_data"]["password_correct"], false);
+        }
+    }
+
+    #[test]
+    fn verify_aex_password() {
+        let file = File::open("../testdata/aes-password.zip").unwrap();
+        let metadata = analyze_file(&file).unwrap_or_else(|error| panic!("{error}"));
+        for file in &metadata.contents {
+            let Ok(local) = &file.local else {
+                panic!("{}", file.central.name)
+            };
+            let aex_data = local.aex_data.as_ref().unwrap();
+            assert!(aex_data.verify_password("ziplinter"));
+            assert!(!aex_data.verify_password("wrong"));
+        }
+    }
+
+    #[test]
+    fn stream_entry_data() {
+        let file = File::open("../testdata/test.zip").unwrap();
+        let mut streamed: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+        for_each_entry_data(&file, |entry, chunk| {
+            streamed
+                .entry(entry.name.clone())
+                .or_default()
+                .extend_from_slice(chunk)
+        })
+        .unwrap();
+        let extracted = extract_all(&file, &ExtractOptions::default()).unwrap();
+        assert_eq!(streamed, extracted.files);
+
+        let file = File::open("../testdata/found-me.txt").unwrap();
+        assert!(for_each_entry_data(&file, |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn extract_all_keeps_last_duplicate() {
+        let file = std::fs::File::open("../testdata/same-names.zip").unwrap();
+        let extracted = extract_all(&file, &ExtractOptions::default()).unwrap();
+        assert_eq!(extracted.files.len(), 1);
+        let codes: Vec<_> = extracted.findings.iter().map(|f| f.code).collect();
+        assert_eq!(codes, ["duplicate_name", "duplicate_name"]);
+        assert_eq!(extracted.findings[1].entry, Some(2));
+
+        let options = ExtractOptions {
+            max_entry_bytes: 4,
+            ..Default::default()
+        };
+        let extracted = extract_all(&file, &options).unwrap();
+        assert!(extracted.files.is_empty());
+        assert!(extracted
+            .findings
+            .iter()
+            .all(|f| f.code == "entry_too_large"));
+
+        let options = ExtractOptions {
+            max_total_bytes: 25,
+            ..Default::default()
+        };
+        assert!(matches!(
+            extract_all(&file, &options),
+            Err(ExtractError::BudgetExceeded {
+                max_total_bytes: 25
+            })
+        ));
+    }
+
+    #[test]
+    fn extract_to_dir_stays_inside() {
+        let dest = std::env::temp_dir().join(format!("ziplinter-extract-{}", std::process::id()));
+        let extract = |name: &str, options: &ExtractOptions| {
+            let file = std::fs::File::open(format!("../testdata/{name}")).unwrap();
+            extract_to_dir(&file, &dest, options).unwrap()
+        };
+        let codes = |extracted: &ExtractedToDir| -> Vec<&str> {
+            extracted.findings.iter().map(|f| f.code).collect()
+        };
+
+        let extracted = extract("test.zip", &ExtractOptions::default());
+        assert!(extracted.findings.is_empty());
+        assert_eq!(extracted.paths.len(), 2);
+        for path in &extracted.paths {
+            assert!(dest.join(path).is_file());
+        }
+
+        for name in ["relative.zip", "absolute-name.zip"] {
+            let extracted = extract(name, &ExtractOptions::default());
+            assert!(extracted.paths.is_empty());
+            assert_eq!(codes(&extracted), ["unsafe_path_skipped"]);
+        }
+
+        // "symlink" points to "../target", so it's skipped even when allowed
+        let options = ExtractOptions {
+            allow_symlinks: true,
+            ..Default::default()
+        };
+        for options in [ExtractOptions::default(), options.clone()] {
+            let extracted = extract("symlink.zip", &options);
+            assert!(extracted.paths.is_empty());
+            assert_eq!(codes(&extracted), ["symlink_skipped"]);
+        }
+        assert!(std::fs::symlink_metadata(dest.join("symlink")).is_err());
+
+        // "z" points to "q/..", where "q" is a link to "." extracted before it
+        let extracted = extract("symlink-chain.zip", &options);
+        if cfg!(unix) {
+            assert_eq!(extracted.paths, [Path::new("q")]);
+            assert_eq!(codes(&extracted), ["symlink_skipped"]);
+            assert_eq!(extracted.findings[0].entry, Some(1));
+        }
+        assert!(std::fs::symlink_metadata(dest.join("z")).is_err());
+
+        std::fs::remove_dir_all(&dest).unwrap();
+    }
+
+    #[test]
+    fn min_severity_skips_findings() {
+        let bytes = std::fs::read("../testdata/stored-high-entropy.zip").unwrap();
+        let codes = |value: &serde_json::Value| -> Vec<String> {
+            value["findings"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|finding| finding["code"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert!(codes(&parse_bytes(&bytes)).contains(&"stored_high_entropy".to_string()));
+
+        let options = ParseOptions {
+            min_severity: Severity::Warning,
+            ..Default::default()
+        };
+        let value = parse_bytes_with(&bytes, &options);
+        assert!(!codes(&value).contains(&"stored_high_entropy".to_string()));
+        for finding in value["findings"].as_array().unwrap() {
+            assert_ne!(finding["severity"], "info");
+        }
+
+        // the content type is still detected for warnings
+        let bytes = std::fs::read("../testdata/extension-mismatch.zip").unwrap();
+        let value = parse_bytes_with(&bytes, &options);
+        assert!(codes(&value).contains(&"extension_mismatch".to_string()));
+
+        // skipped checks whose findings decide conformance still run
+        let bytes = std::fs::read("../testdata/name-length-mismatch.zip").unwrap();
+        let options = ParseOptions {
+            min_severity: Severity::Error,
+            ..Default::default()
+        };
+        let value = parse_bytes_with(&bytes, &options);
+        assert_eq!(
+            value["conformance_violations"],
+            parse_bytes(&bytes)["conformance_violations"]
+        );
+        assert_eq!(value["strict_conformant"], false);
+        for finding in value["findings"].as_array().unwrap() {
+            assert_eq!(finding["severity"], "error");
+        }
+    }
+
+    #[test]
+    fn disabled_checks_are_skipped() {
+        let bytes = std::fs::read("../testdata/extension-mismatch.zip").unwrap();
+        let mut rules = RuleSet::all();
+        rules.disable("extension_mismatch").unwrap();
+        let options = ParseOptions {
+            rules,
+            ..Default::default()
+        };
+        let metadata = report(&bytes.as_slice(), &options, &mut |_| {})
+            .unwrap_or_else(|error| panic!("{error}"));
+        let file = &metadata.contents[0];
+        let codes = |options: &ParseOptions| -> Vec<&'static str> {
+            findings::check_entry(0, &file.central, file.local.as_ref().ok(), options)
+                .iter()
+                .map(|finding| finding.code)
+                .collect()
+        };
+        assert!(codes(&ParseOptions::default()).contains(&"extension_mismatch"));
+        assert!(!codes(&options).contains(&"extension_mismatch"));
+
+        // checks whose findings decide conformance still run
+        let bytes = std::fs::read("../testdata/name-length-mismatch.zip").unwrap();
+        let options = ParseOptions {
+            rules: RuleSet::none(),
+            ..Default::default()
+        };
+        let value = parse_bytes_with(&bytes, &options);
+        assert_eq!(value["findings"], serde_json::json!([]));
+        assert_eq!(value["strict_conformant"], false);
+    }
+
+    #[test]
+    fn unsupported_method_huge_size() {
+        // method 1 with a zip64 compressed size of u64::MAX
+        let bytes = std::fs::read("../testdata/unsupported-method-huge-size.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        let data = value["parsed_ranges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|range| range["contains"] == "file data")
+            .unwrap();
+        assert_eq!(data["end"], u64::MAX);
+        assert_eq!(
+            value["overhead"]["data_bytes"],
+            u64::MAX - data["start"].as_u64().unwrap()
+        );
+    }
+
+    #[test]
+    fn overhead_saturates() {
+        // declared extents of data that couldn't be read can be this large
+        let mut ranges = ParsedRanges::new();
+        ranges.insert_range(0..u64::MAX, "file data", None);
+        ranges.insert_range(0..u64::MAX - 55, "file data", None);
+        ranges.insert_range(0..30, "local file header", None);
+        let overhead = overhead::overhead(&ranges);
+        assert_eq!(overhead.data_bytes, u64::MAX);
+        assert_eq!(overhead.overhead_bytes, 30);
+        assert!(overhead
+            .overhead_ratio
+            .is_some_and(|ratio| ratio > 0.0 && ratio < 1e-15));
+    }
+
+    #[test]
+    fn report_has_format_version() {
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        assert_eq!(value["format_version"], 2);
+        assert_eq!(value["ziplinter_version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn methods_undecodable() {
+        let mut bytes = std::fs::read("../testdata/test.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        assert_eq!(
+            value["methods_present"],
+            serde_json::json!(["Deflate", "Store"])
+        );
+        assert_eq!(value["methods_undecodable"], serde_json::json!([]));
+
+        // declare the first entry as XZ-compressed in the central directory
+        let central = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x01\x02")
+            .unwrap();
+        bytes[central + 10..central + 12].copy_from_slice(&95u16.to_le_bytes());
+        let value = parse_bytes(&bytes);
+        assert_eq!(value["methods_present"], serde_json::json!(["Xz", "Store"]));
+        assert_eq!(value["methods_undecodable"], serde_json::json!(["Xz"]));
+    }
+
+    #[test]
+    fn zipalign() {
+        let options = ParseOptions {
+            check_alignment: true,
+            ..Default::default()
+        };
+        let bytes = std::fs::read("../testdata/zipaligned.zip").unwrap();
+        let value = parse_bytes_with(&bytes, &options);
+        assert_eq!(value["zipalign_ok"], true);
+        let library = &value["contents"][1]["local"]["alignment"];
+        assert_eq!(library["aligned_to"], 4096);
+        assert_eq!(library["offset"], 4096);
+
+        let bytes = std::fs::read("../testdata/apk-signing-block.zip").unwrap();
+        let value = parse_bytes_with(&bytes, &options);
+        assert_eq!(value["zipalign_ok"], false);
+        assert_eq!(
+            value["findings_index"]["misaligned_entry"],
+            serde_json::json!([0, 1])
+        );
+
+        // only reported when asked for
+        assert!(parse_bytes(&bytes).get("zipalign_ok").is_none());
+    }
+
+    #[test]
+    fn local_stream() {
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        let central = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x01\x02")
+            .unwrap();
+
+        let mut names = Vec::new();
+        for stream in [&bytes[..], &bytes[..central]] {
+            names.clear();
+            let value = parse_local_stream(stream, &mut |entry| names.push(entry.name.clone()));
+            assert_eq!(names, ["test.txt", "gophercolor16x16.png"]);
+            assert_eq!(value["entries"][1]["name"], "gophercolor16x16.png");
+            let codes: Vec<_> = value["findings"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|f| &f["code"])
+                .collect();
+            assert_eq!(codes, ["no_central_directory"]);
+        }
+
+        // broken off in the middle of the data of the second entry
+        let value = parse_local_stream(&bytes[..central - 10], &mut |_| {});
+        assert_eq!(value["entries"].as_array().unwrap().len(), 1);
+        assert_eq!(value["findings"][1]["code"], "stream_unreadable");
+        assert_eq!(value["findings"][1]["entry"], 1);
+
+        // not even the first local file header is complete
+        let value = parse_local_stream(&bytes[..20], &mut |_| {});
+        assert!(value["error"].is_string());
+    }
+
+    #[test]
+    fn descriptor_matches_data() {
+        let bytes = std::fs::read("../testdata/descriptor-mismatch.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        let local = &value["contents"][0]["local"];
+        assert_eq!(local["descriptor_matches_data"], false, "{local}");
+
+        let bytes = std::fs::read("../testdata/dd.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        assert_eq!(
+            value["contents"][0]["local"]["descriptor_matches_data"],
+            true
+        );
+
+        // without a data descriptor, there is nothing to compare
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        assert!(value["contents"][0]["local"]
+            .get("descriptor_matches_data")
+            .is_none());
+    }
+
+    #[test]
+    fn path_traversal() {
+        for (name, sanitized) in [
+            ("relative.zip", "tmp/relative"),
+            ("absolute-name.zip", "tmp/absolute"),
+        ] {
+            let bytes = std::fs::read(format!("../testdata/{name}")).unwrap();
+            let value = parse_bytes(&bytes);
+            let file = &value["contents"][0];
+            for header in [&file["central"], &file["local"]] {
+                assert_eq!(header["is_path_traversal"], true, "{name}");
+                assert_eq!(header["sanitized_name"], sanitized, "{name}");
+            }
+        }
+
+        assert!(findings::is_path_traversal("C:\\Windows\\win.ini"));
+        assert_eq!(
+            findings::sanitized_name("C:\\Windows\\win.ini"),
+            "Windows/win.ini"
+        );
+        assert!(findings::is_path_traversal("a/../b"));
+        assert!(!findings::is_path_traversal("a/..b/c:d"));
+    }
+
+    #[test]
+    fn bomb_fingerprint() {
+        // one entry of zeros among ordinary text
+        let bytes = std::fs::read("../testdata/ratio-outlier.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        assert_eq!(value["ratio_distribution"]["outlier_count"], 1);
+        assert_eq!(value["bomb_fingerprint"], true);
+        assert_eq!(
+            value["max_compression_ratio"],
+            value["ratio_distribution"]["max"]
+        );
+
+        // the same highly compressed entry over and over
+        let bytes = std::fs::read("../testdata/repeated-identical-entries.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        assert_eq!(value["ratio_distribution"]["outlier_count"], 0);
+        assert_eq!(value["bomb_fingerprint"], true);
+
+        // a single highly compressed entry is left to a threshold
+        for name in ["test.zip", "found-me-zstd.zip"] {
+            let bytes = std::fs::read(format!("../testdata/{name}")).unwrap();
+            assert_eq!(parse_bytes(&bytes)["bomb_fingerprint"], false, "{name}");
+        }
+    }
+
+    #[test]
+    fn first_divergence_names_the_structure() {
+        let reference = std::fs::File::open("../testdata/test.zip").unwrap();
+        assert_eq!(first_divergence(&reference, &reference).unwrap(), None);
+
+        let mut bytes = std::fs::read("../testdata/test.zip").unwrap();
+        let central = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x01\x02")
+            .unwrap();
+        // the last byte of the first entry's name in its central directory header
+        let offset = central + 46 + "test.txt".len() - 1;
+        bytes[offset] = b'X';
+        let path =
+            std::env::temp_dir().join(format!("ziplinter-diverged-{}.zip", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let other = std::fs::File::open(&path).unwrap();
+        let divergence = first_divergence(&reference, &other).unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(divergence.offset, offset as u64);
+        assert_eq!(divergence.structure_offset, Some(central as u64));
+        assert_eq!(
+            divergence.to_string(),
+            format!("central directory header for \"test.txt\" at offset {offset}")
+        );
+    }
+
+    #[test]
+    fn tail_summary() {
+        let file = std::fs::File::open("../testdata/test.zip").unwrap();
+        let summary = parse_tail(&file).unwrap();
+        assert_eq!(
+            summary,
+            TailSummary {
+                entries: 2,
+                directory_size: 168,
+                directory_offset: 954,
+                comment: "This is a zipfile comment.".into(),
+                zip64: false,
+            }
+        );
+
+        let file = std::fs::File::open("../testdata/zip64.zip").unwrap();
+        let summary = parse_tail(&file).unwrap();
+        assert!(summary.zip64);
+        assert_eq!(summary.entries, 1);
+
+        let file = std::fs::File::open("../testdata/blue.png").unwrap();
+        assert!(matches!(parse_tail(&file), Err(TailError::Zip(_))));
+
+        // moving the zip64 end of central directory record out of the tail by
+        // padding between it and its locator, which points at it by offset
+        let mut bytes = std::fs::read("../testdata/zip64.zip").unwrap();
+        let locator = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x06\x07")
+            .unwrap();
+        bytes.splice(locator..locator, vec![0; TAIL_LENGTH as usize]);
+        let path = std::env::temp_dir().join(format!("ziplinter-tail-{}.zip", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let result = parse_tail(&file);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(TailError::Insufficient { .. })));
+
+        // pointing the zip64 locator past the end of the file
+        let mut bytes = std::fs::read("../testdata/zip64.zip").unwrap();
+        let locator = bytes
+            .windows(4)
+            .position(|window| window == b"PK\x06\x07")
+            .unwrap();
+        let past_end = bytes.len() as u64 + 1000;
+        bytes[locator + 8..locator + 16].copy_from_slice(&past_end.to_le_bytes());
+        let path = std::env::temp_dir().join(format!(
+            "ziplinter-tail-past-end-{}.zip",
+            std::process::id()
+        ));
+        std::fs::write(&path, &bytes).unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let result = parse_tail(&file);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(TailError::Zip(_))));
+    }
+
+    #[test]
+    fn offset_cycle_unsupported_method() {
+        // entries this build can't decompress still chain by their sizes
+        let mut bytes = std::fs::read("../testdata/offset-cycle.zip").unwrap();
+        for (signature, method) in [(b"PK\x03\x04", 8), (b"PK\x01\x02", 10)] {
+            let headers: Vec<_> = bytes
+                .windows(4)
+                .enumerate()
+                .filter(|(_, window)| window == signature)
+                .map(|(offset, _)| offset)
+                .collect();
+            for header in headers {
+                bytes[header + method..header + method + 2].copy_from_slice(&11u16.to_le_bytes());
+            }
+        }
+        let value = parse_bytes(&bytes);
+        assert_eq!(value["contents"][0]["local"]["method_supported"], false);
+        let cycles: Vec<_> = value["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|finding| finding["code"] == "offset_cycle")
+            .collect();
+        assert_eq!(cycles.len(), 1, "{value}");
+        assert_eq!(cycles[0]["entry"], 0);
+    }
+
+    #[test]
+    fn parsed_ranges_without_decompressing() {
+        for name in ["test.zip", "dd.zip", "apk-signing-block.zip", "zip64.zip"] {
+            let path = Path::new("../testdata").join(name);
+            let file = std::fs::File::open(&path).unwrap();
+            let parsed_ranges = parsed_ranges_file(&file).unwrap_or_else(|error| panic!("{error}"));
+            let metadata = analyze_file(&file).unwrap_or_else(|error| panic!("{error}"));
+            for offset in 0..metadata.size {
+                let label = |range: &ParsedRange| {
+                    (
+                        range.contains().to_owned(),
+                        range.filename().map(str::to_owned),
+                    )
+                };
+                assert_eq!(
+                    parsed_ranges.at(offset).map(label),
+                    metadata.parsed_ranges.at(offset).map(label),
+                    "{name} at {offset}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn many_entries_share_a_header() {
+        // a stored entry "a" with content "x", which every central directory
+        // header points to
+        let mut bytes = b"PK\x03\x04\x0a\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        bytes.extend_from_slice(&0x8cdc_1683u32.to_le_bytes());
+        bytes.extend_from_slice(&[1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        bytes.extend_from_slice(b"ax");
+        let directory_offset = bytes.len() as u32;
+        let entries = 2000u16;
+        for _ in 0..entries {
+            bytes.extend_from_slice(b"PK\x01\x02\x0a\x00\x0a\x00\x00\x00\x00\x00\x00\x00\x00\x00");
+            bytes.extend_from_slice(&0x8cdc_1683u32.to_le_bytes());
+            bytes.extend_from_slice(&[1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+            // attributes and the offset of the local header
+            bytes.extend_from_slice(&[0; 10]);
+            bytes.push(b'a');
+        }
+        let directory_size = bytes.len() as u32 - directory_offset;
+        bytes.extend_from_slice(b"PK\x05\x06\x00\x00\x00\x00");
+        bytes.extend_from_slice(&entries.to_le_bytes());
+        bytes.extend_from_slice(&entries.to_le_bytes());
+        bytes.extend_from_slice(&directory_size.to_le_bytes());
+        bytes.extend_from_slice(&directory_offset.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+
+        let value = parse_bytes(&bytes);
+        assert_eq!(value["contents"].as_array().unwrap().len(), 2000);
+        // the shared header and data, each paired once
+        assert_eq!(
+            value["overlaps"].as_array().unwrap().len(),
+            2,
+            "{}",
+            value["overlaps"]
+        );
+        assert_eq!(value["overlaps_truncated"], false);
+        let overlap_findings = value["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|finding| finding["code"] == "structure_overlap")
+            .count();
+        assert_eq!(overlap_findings, 2);
+    }
+
+    #[test]
+    fn method_mislabeled() {
+        let codes = |value: &serde_json::Value| -> Vec<String> {
+            value["findings"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|f| f["code"].as_str().unwrap().to_string())
+                .collect()
+        };
+        let options = ParseOptions {
+            detect_mislabeled_methods: true,
+            ..Default::default()
+        };
+
+        let bytes = std::fs::read("../testdata/method-mislabeled.zip").unwrap();
+        assert!(!codes(&parse_bytes(&bytes)).contains(&"method_mislabeled".into()));
+        let value = parse_bytes_with(&bytes, &options);
+        assert!(
+            codes(&value).contains(&"method_mislabeled".into()),
+            "{value}"
+        );
+
+        // the other way around: stored data labeled as Deflate
+        let bytes = std::fs::read("../testdata/deflate-declared-stored.zip").unwrap();
+        let value = parse_bytes_with(&bytes, &options);
+        let finding = value["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|f| f["code"] == "method_mislabeled")
+            .unwrap();
+        assert!(finding["message"].as_str().unwrap().ends_with("as Store"));
+
+        // entries that decode fine aren't tried
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        assert!(!codes(&parse_bytes_with(&bytes, &options)).contains(&"method_mislabeled".into()));
+    }
+
+    #[test]
+    fn entry_ids() {
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        for file in value["contents"].as_array().unwrap() {
+            let name = file["central"]["name"].as_str().unwrap();
+            let offset = file["central"]["header_offset"].as_u64().unwrap();
+            assert_eq!(file["id"], entry_id(name, offset));
+        }
+
+        // duplicate central directory headers for the same local file header
+        let bytes = std::fs::read("../testdata/same-names.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        let ids: Vec<_> = value["contents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|file| file["id"].as_str().unwrap())
+            .collect();
+        let id = entry_id("aaaa", 88);
+        assert_eq!(ids, [id.clone(), format!("{id}-2"), format!("{id}-3")]);
+
+        let bytes = std::fs::read("../testdata/zero-crc-nonempty.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        for finding in value["findings"].as_array().unwrap() {
+            let entry = finding["entry"].as_u64().unwrap() as usize;
+            assert_eq!(finding["entry_id"], value["contents"][entry]["id"]);
+        }
+    }
+
+    #[test]
+    fn split_archive() {
+        let segments = [
+            Path::new("../testdata/split.z01"),
+            Path::new("../testdata/split.z02"),
+            Path::new("../testdata/split.zip"),
+        ];
+        let value = parse_split(&segments);
+        assert_eq!(value["findings"], serde_json::json!([]), "{value}");
+        let sizes: Vec<_> = value["segments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|segment| segment["size"].as_u64().unwrap())
+            .collect();
+        assert_eq!(sizes, [72, 70, 133]);
+        assert_eq!(value["disk_count"], 3);
+        assert_eq!(value["is_split"], true);
+        assert_eq!(
+            value["entries_per_disk"],
+            serde_json::json!({ "0": 1, "1": 1 })
+        );
+        for (file, segment) in value["contents"].as_array().unwrap().iter().zip([0, 1]) {
+            assert_eq!(file["segment"], segment);
+            assert_eq!(
+                file["local"]["measured_uncompressed"],
+                file["central"]["uncompressed_size"]
+            );
+        }
+
+        // filtered entries keep the segment of their own local file header
+        let options = ParseOptions {
+            entry_filter: Some("second.txt".into()),
+            ..Default::default()
+        };
+        let value = parse_split_with(&segments, &options);
+        let contents = value["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(contents[0]["segment"], 1);
+
+        // the last segment on its own has local file header offsets for other disks
+        let value = parse_split(&segments[2..]);
+        assert_eq!(value["strict_conformant"], false);
+
+        // segments out of order make entries run into each other
+        let swapped = [segments[1], segments[0], segments[2]];
+        let mut streamed = Vec::new();
+        let value = parse_split_streaming(&swapped, &ParseOptions::default(), &mut |finding| {
+            streamed.push(serde_json::to_value(finding).unwrap())
+        });
+        assert!(!streamed.is_empty());
+        assert_eq!(value["findings"], serde_json::Value::from(streamed));
+    }
+
+    #[test]
+    fn content_preview() {
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        let options = ParseOptions {
+            content_preview_bytes: 4,
+            ..Default::default()
+        };
+        let value = parse_bytes_with(&bytes, &options);
+        let local = &value["contents"][0]["local"];
+        assert_eq!(local["content_preview"], "54686973");
+        assert_eq!(local["content_preview_truncated"], true);
+
+        let value = parse_bytes(&bytes);
+        assert!(value["contents"][0]["local"]
+            .get("content_preview")
+            .is_none());
+    }
+
+    #[test]
+    fn max_severity_of_report() {
+        let severity = |name: &str| {
+            let bytes = std::fs::read(Path::new("../testdata").join(name)).unwrap();
+            max_severity(&parse_bytes(&bytes))
+        };
+        assert_eq!(severity("test.zip"), None);
+        assert_eq!(severity("found-me.txt"), Some(Severity::Error));
+        assert_eq!(severity("aex-flag-missing.zip"), Some(Severity::Warning));
+        assert_eq!(severity("header-data-overlap.zip"), Some(Severity::Error));
+
+        // reports spell severities the way they are parsed back
+        for severity in [Severity::Info, Severity::Warning, Severity::Error] {
+            assert_eq!(serde_json::json!(severity), severity.as_str());
+            assert_eq!(severity.as_str().parse(), Ok(severity));
+        }
+    }
+
+    #[test]
+    fn forced_encoding() {
+        let bytes = std::fs::read("../testdata/shift-jis.zip").unwrap();
+        let detected = parse_bytes(&bytes);
+        assert_eq!(detected["encoding"], "ShiftJis");
+        assert_eq!(detected["encoding_detection"]["forced"], false);
+
+        let options = ParseOptions {
+            forced_encoding: Some(Encoding::Cp437),
+            ..Default::default()
+        };
+        let forced = parse_bytes_with(&bytes, &options);
+        assert_eq!(forced["encoding"], "Cp437");
+        assert_eq!(forced["encoding_detection"]["forced"], true);
+        assert_eq!(
+            forced["encoding_detection"]["guess"],
+            serde_json::Value::Null
+        );
+        assert_ne!(
+            forced["contents"][0]["central"]["name"],
+            detected["contents"][0]["central"]["name"]
+        );
+    }
+
+    #[test]
+    fn analyze_file_report() {
+        let file = File::open("../testdata/test.zip").unwrap();
+        let metadata = analyze_file(&file).unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(metadata.contents.len(), 2);
+        assert_eq!(metadata.contents[0].central.name, "test.txt");
+        assert!(metadata.contents[0].local.is_ok());
+        assert_eq!(serde_json::to_value(&metadata).unwrap(), parse_file(&file));
+
+        let file = File::open("../testdata/found-me.txt").unwrap();
+        assert!(analyze_file(&file).is_err());
+
+        let file = File::open("../testdata/zip64.zip").unwrap();
+        let metadata = analyze_file(&file).unwrap_or_else(|error| panic!("{error}"));
+        let central = &metadata.contents[0].central;
+        assert_eq!(central.compressed_size, u32::MAX);
+        assert_eq!(central.zip64_compressed_size, 36);
+        assert_eq!(central.zip64_uncompressed_size, 36);
+    }
+
+    #[test]
+    fn entry_digest() {
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        let value = parse_bytes(&bytes);
+        assert!(value["contents"][0]["local"].get("sha256").is_none());
+
+        let digest = |algorithm, name: &str| {
+            let options = ParseOptions {
+                digest: Some(algorithm),
+                ..Default::default()
+            };
+            parse_bytes_with(&bytes, &options)["contents"][0]["local"][name].clone()
+        };
+        assert_eq!(
+            digest(DigestAlgorithm::Sha256, "sha256"),
+            "3162f80e9db2a1c7229ce55d6dbc8c4496936f66c7df5136d24e8f9973cf64bc"
+        );
+        assert_eq!(
+            digest(DigestAlgorithm::Sha1, "sha1"),
+            "d2471cb0f466c65a2f85c179a419042a94c278a6"
+        );
+        assert_eq!(
+            digest(DigestAlgorithm::Blake3, "blake3"),
+            "66def90edb11e1df8481879dfbdbcb205bfbdd1bfc167165c0633186b14cff0d"
+        );
+
+        // without the password, there's no content to hash
+        let bytes = std::fs::read("../testdata/aes-password.zip").unwrap();
+        let options = ParseOptions {
+            digest: Some(DigestAlgorithm::Sha256),
+            ..Default::default()
+        };
+        let value = parse_bytes_with(&bytes, &options);
+        assert!(value["contents"][0]["local"].get("sha256").is_none());
+    }
+
+    #[test]
+    fn parse_reader_matches_parse_bytes() {
+        for name in [
+            "test.zip",
+            "zip64.zip",
+            "sfx-padded-stub.zip",
+            "found-me.txt",
+        ] {
+            let bytes = std::fs::read(Path::new("../testdata").join(name)).unwrap();
+            assert_eq!(
+                parse_reader(std::io::Cursor::new(&bytes)),
+                parse_bytes(&bytes),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn corpus_report_merges_reports() {
+        let reports = ["test.zip", "extension-mismatch.zip", "found-me.txt"]
+            .into_iter()
+            .map(|name| {
+                let file = std::fs::File::open(Path::new("../testdata").join(name)).unwrap();
+                (name.into(), parse_file(&file))
+            });
+        let corpus = CorpusReport::merge(reports);
+
+        assert_eq!(corpus.archives, 3);
+        assert!(corpus.unparseable.contains_key(Path::new("found-me.txt")));
+        assert_eq!(corpus.total_entries, 6);
+        assert_eq!(corpus.methods["Deflate"], 5);
+        assert_eq!(corpus.methods["Store"], 1);
+        assert_eq!(corpus.archives_by_finding["extension_mismatch"], 1);
+        assert_eq!(
+            corpus.suspicious_names,
+            [
+                NameCount {
+                    name: "notes.txt".into(),
+                    count: 1
+                },
+                NameCount {
+                    name: "report.pdf".into(),
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn canonical_json_is_deterministic() {
+        let value = serde_json::json!({
+            "b": [1.0, -0.0, 2.5, 1e300, 1.5e-7],
+            "a": { "é": null, "z": "line\n", "B": true },
+        });
+        assert_eq!(
+            to_canonical_json(&value),
+            r#"{"a":{"B":true,"z":"line\n","é":null},"b":[1,0,2.5,1e+300,1.5e-7]}"#
+        );
+
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        assert_eq!(
+            to_canonical_json(&parse_bytes(&bytes)),
+            to_canonical_json(&parse_bytes(&bytes))
+        );
+    }
+
+    #[test]
+    fn name_under_encodings() {
+        let bytes = std::fs::read("../testdata/shift-jis.zip").unwrap();
+        let report = parse_bytes(&bytes);
+        assert!(report["contents"][0]["central"]
+            .get("name_under_encodings")
+            .is_none());
+
+        let options = ParseOptions {
+            name_encodings: true,
+            ..Default::default()
+        };
+        let report = parse_bytes_with(&bytes, &options);
+        let central = &report["contents"][0]["central"];
+        let names = &central["name_under_encodings"];
+        assert_eq!(names["shift_jis"], central["name"]);
+        assert_ne!(names["cp437"], central["name"]);
+        assert!(names["utf8_lossy"]
+            .as_str()
+            .unwrap()
+            .contains(char::REPLACEMENT_CHARACTER));
+    }
+
+    #[test]
+    fn validate_local_header_at_offsets() {
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        let report = parse_bytes(&bytes);
+        let file = &report["contents"][1];
+        let offset = file["central"]["header_offset"].as_u64().unwrap();
+
+        let header = validate_local_header_at(&bytes.as_slice(), offset).unwrap();
+        assert_eq!(
+            header.name.as_ref(),
+            file["central"]["name"].as_str().unwrap().as_bytes()
+        );
+        assert!(validate_local_header_at(&bytes.as_slice(), offset + 1).is_err());
+        assert!(validate_local_header_at(&bytes.as_slice(), bytes.len() as u64).is_err());
+    }
+
+    #[test]
+    fn read_range_within_file() {
+        let file = File::open("../testdata/test.zip").unwrap();
+        let size = file.metadata().unwrap().len();
+        assert_eq!(read_range(&file, 0..4).unwrap(), b"PK\x03\x04");
+        assert_eq!(read_range(&file, size..size).unwrap(), b"");
+        assert!(read_range(&file, size - 1..size + 1).is_err());
+        assert!(read_range(&file, Range { start: 4, end: 0 }).is_err());
+    }
+
+    #[test]
+    fn diff_reports_separates_changes() {
+        let old = parse_bytes(&std::fs::read("../testdata/test.zip").unwrap());
+        assert!(diff_reports(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        let contents = new["contents"].as_array_mut().unwrap();
+        let removed = contents.remove(0);
+        contents[0]["central"]["crc32"] = 0.into();
+        let mut added = removed.clone();
+        added["central"]["name"] = "added.txt".into();
+        contents.push(added);
+        new["findings"] = serde_json::json!([
+            { "code": "tampered", "severity": "error", "message": "tampered", "offset": 1 }
+        ]);
+
+        let diff = diff_reports(&old, &new);
+        let name = |file: &serde_json::Value| file["central"]["name"].as_str().unwrap().to_string();
+        assert_eq!(diff.added.entries, vec!["added.txt".to_string()]);
+        assert_eq!(diff.removed.entries, vec![name(&removed)]);
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].name, name(&new["contents"][0]));
+        assert_eq!(diff.modified[0].new_crc32, 0);
+        assert_eq!(diff.added.findings.len(), 1);
+        assert_eq!(diff.added.findings[0].code, "tampered");
+        assert_eq!(
+            diff.removed.findings.len(),
+            old["findings"].as_array().unwrap().len()
+        );
+    }
+
+    #[test]
+    fn strict_rejects_violations() {
+        let options = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        assert_eq!(
+            parse_bytes_with(&bytes, &options)["strict_conformant"],
+            true
+        );
+
+        let bytes = std::fs::read("../testdata/header-data-overlap.zip").unwrap();
+        assert_eq!(parse_bytes(&bytes)["strict_conformant"], false);
+        let report = parse_bytes_with(&bytes, &options);
+        let error = report["error"].as_str().unwrap();
+        assert!(error.starts_with("not strictly conformant: "), "{error}");
+
+        // the analysis ends at the violation, before any later checks
+        let mut streamed = Vec::new();
+        let report =
+            parse_bytes_streaming(&bytes, &options, &mut |finding| streamed.push(finding.code));
+        assert!(report.get("error").is_some());
+        assert_eq!(streamed, ["header_data_overlap"]);
+    }
+
+    #[test]
+    fn local_header_has_no_central_only_fields() {
+        for entry in std::fs::read_dir("../testdata").unwrap() {
+            let bytes = std::fs::read(entry.unwrap().path()).unwrap();
+            let report = parse_bytes(&bytes);
+            for file in report["contents"].as_array().into_iter().flatten() {
+                for field in ["internal_attrs", "external_attrs", "mode", "comment"] {
+                    assert!(file["local"].get(field).is_none(), "{field} in {file}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_finding_has_a_rule() {
+        let codes: Vec<&str> = RULES.iter().map(|rule| rule.code).collect();
+        assert!(codes.is_sorted(), "{codes:?}");
+
+        let options = ParseOptions {
+            check_alignment: true,
+            ..Default::default()
+        };
+        for entry in std::fs::read_dir("../testdata").unwrap() {
+            let bytes = std::fs::read(entry.unwrap().path()).unwrap();
+            let report = parse_bytes_with(&bytes, &options);
+            for finding in report["findings"].as_array().into_iter().flatten() {
+                let code = finding["code"].as_str().unwrap();
+                assert!(rule_by_code(code).is_some(), "{code} has no rule");
+            }
+        }
+    }
+
+    #[test]
+    fn disabled_rules_are_left_out() {
+        let bytes = std::fs::read("../testdata/header-data-overlap.zip").unwrap();
+        let mut options = ParseOptions::default();
+        options.rules.disable("header_data_overlap").unwrap();
+        assert!(options.rules.enable("no_such_rule").is_err());
+
+        let mut streamed = Vec::new();
+        let report =
+            parse_bytes_streaming(&bytes, &options, &mut |finding| streamed.push(finding.code));
+        assert!(!streamed.is_empty());
+        assert!(!streamed.contains(&"header_data_overlap"));
+        let codes: Vec<_> = report["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|finding| finding["code"].as_str().unwrap())
+            .collect();
+        assert_eq!(codes, streamed);
+        // disabled checks still count towards conformance
+        assert_eq!(report["strict_conformant"], false);
+
+        assert!(RuleSet::security().is_enabled("header_data_overlap"));
+        assert!(!RuleSet::conformance().is_enabled("offset_cycle"));
+        assert!(!RuleSet::none().is_enabled("offset_cycle"));
+    }
+
+    #[test]
+    fn stream_findings() {
+        let bytes = std::fs::read("../testdata/header-data-overlap.zip").unwrap();
+        let mut streamed = Vec::new();
+        let report = parse_bytes_streaming(&bytes, &ParseOptions::default(), &mut |finding| {
+            streamed.push(serde_json::to_value(finding).unwrap())
+        });
+        assert!(!streamed.is_empty());
+        assert_eq!(report["findings"], serde_json::Value::Array(streamed));
+    }
+
+    #[test]
+    fn collect_stats() {
+        let bytes = std::fs::read("../testdata/test.zip").unwrap();
+        assert!(parse_bytes(&bytes).get("stats").is_none());
+
+        let options = ParseOptions {
+            collect_stats: true,
+            ..Default::default()
+        };
+        let stats = &parse_bytes_with(&bytes, &options)["stats"];
+        // the end of central directory is searched for in the whole file
+        assert!(stats["bytes_read"].as_u64().unwrap() >= bytes.len() as u64);
+        assert!(stats["process_calls"].as_u64().unwrap() > 0);
+        assert_eq!(stats["decompressed_bytes"], 26 + 785);
+    }
+
+    #[test]
+    fn entry_filter() {
+        let bytes = std::fs::read("../testdata/directory-with-data.zip").unwrap();
+        let all = parse_bytes(&bytes);
+        for (pattern, names) in [
+            ("readme.txt", &["readme.txt"][..]),
+            ("hidden*", &["hidden/"]),
+            ("*", &["readme.txt", "hidden/"]),
+            ("readme", &[]),
+        ] {
+            let options = ParseOptions {
+                entry_filter: Some(pattern.to_string()),
+                ..Default::default()
+            };
+            let value = parse_bytes_with(&bytes, &options);
+            assert_eq!(value["size"], all["size"]);
+            assert_eq!(value["eocd"], all["eocd"]);
+            let contents = value["contents"].as_array().unwrap();
+            let kept: Vec<_> = contents
+                .iter()
+                .map(|file| &file["central"]["name"])
+                .collect();
+            assert_eq!(kept, names, "{pattern}");
+            // the directory's finding follows it to its new index
+            let findings: Vec<_> = value["findings"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|finding| finding["code"] == "suspicious_directory_entry")
+                .collect();
+            assert_eq!(findings.len(), names.contains(&"hidden/") as usize);
+            for finding in findings {
+                let entry = finding["entry"].as_u64().unwrap() as usize;
+                assert_eq!(contents[entry]["id"], finding["entry_id"]);
+            }
+        }
+    }
+
+    #[test]
+    fn sarif_results_follow_findings() {
+        let file = std::fs::File::open("../testdata/aex-flag-missing.zip").unwrap();
+        let report = parse_file(&file);
+        let sarif = to_sarif(&report, "aex-flag-missing.zip");
+
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "ziplinter");
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), report["findings"].as_array().unwrap().len());
+        for (result, finding) in results.iter().zip(report["findings"].as_array().unwrap()) {
+            assert_eq!(result["ruleId"], finding["code"]);
+            assert_eq!(result["level"], "warning");
+            let location = &result["locations"][0];
+            assert_eq!(
+                location["physicalLocation"]["region"]["byteOffset"],
+                finding["offset"]
+            );
+            if finding["offset"] == 0 {
+                // the local file header of the entry, with its AE-x extra field
+                assert_eq!(location["physicalLocation"]["region"]["byteLength"], 83);
+            }
+            assert_eq!(
+                location["logicalLocations"][0]["fullyQualifiedName"],
+                report["contents"][finding["entry"].as_u64().unwrap() as usize]["central"]["name"]
+            );
+        }
+    }
+
+    #[test]
+    fn parse_bytes_never_panics() {
+        // seeds for the fuzzer, including inputs that used to panic
+        let corpus = std::fs::read_dir("../fuzz/corpus/parse_bytes").unwrap();
+        for seed in corpus {
+            let bytes = std::fs::read(seed.unwrap().path()).unwrap();
+            parse_bytes(&bytes);
+        }
+
+        // damaged versions of the fixtures
+        for fixture in std::fs::read_dir("../testdata").unwrap() {
+            let bytes = std::fs::read(fixture.unwrap().path()).unwrap();
+            for cut in [1, 7, 22, 46, bytes.len() / 3, bytes.len() / 2] {
+                parse_bytes(&bytes[..bytes.len().saturating_sub(cut)]);
+                parse_bytes(&bytes[cut.min(bytes.len())..]);
+            }
+        }
     }
 
     #[test]
@@ -0,0 +1,141 @@
+//! Linting of a "streaming zip" that is read front to back and has no
+//! central directory (yet), e.g. because the producer is still writing it.
+//!
+//! Unlike a damaged archive, there is no directory to recover here: all that
+//! is known about an entry is what its local file header and data
+//! descriptor say, so nothing can be cross-validated.
+
+use std::io::{self, Read};
+
+use rc_zip::{
+    chrono::{DateTime, Utc},
+    parse::{Entry, Method},
+};
+use rc_zip_sync::ReadZipStreaming;
+
+use crate::{to_json, Error, Finding, Severity, FORMAT_VERSION};
+
+/// An entry as found in a stream of local file headers
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct StreamedEntry {
+    /// file name
+    pub name: String,
+
+    /// general purpose bit flag
+    pub flags: u16,
+
+    /// compression method
+    pub method: Method,
+
+    /// last modification date and time
+    pub modified: DateTime<Utc>,
+
+    /// CRC-32 as declared in the local file header, zero if it's deferred
+    /// to a data descriptor
+    pub crc32: u32,
+
+    /// compressed size as declared in the local file header
+    pub compressed_size: u64,
+
+    /// uncompressed size as declared in the local file header
+    pub uncompressed_size: u64,
+
+    /// Amount of bytes actually produced while decompressing the entry
+    pub measured_uncompressed: u64,
+}
+
+impl StreamedEntry {
+    fn new(entry: &Entry, measured_uncompressed: u64) -> Self {
+        Self {
+            name: entry.name.clone(),
+            flags: entry.flags,
+            method: entry.method,
+            modified: entry.modified,
+            crc32: entry.crc32,
+            compressed_size: entry.compressed_size,
+            uncompressed_size: entry.uncompressed_size,
+            measured_uncompressed,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct LocalStreamReport {
+    format_version: u32,
+    ziplinter_version: &'static str,
+    entries: Vec<StreamedEntry>,
+    findings: Vec<Finding>,
+}
+
+/// Analyzes a stream of local file headers, entry data and data
+/// descriptors, reading it front to back without a central directory.
+///
+/// Each entry is passed to `on_entry` as soon as its data has been read. The
+/// report always has a `no_central_directory` finding, as nothing could be
+/// cross-validated. If the stream can't be read to the end, e.g. because it
+/// breaks off in the middle of an entry, that is reported as
+/// `stream_unreadable` after the entries read so far. Streams that don't
+/// start with a valid local file header result in a report with just an
+/// `error` property.
+pub fn parse_local_stream<R: Read>(
+    reader: R,
+    on_entry: &mut dyn FnMut(&StreamedEntry),
+) -> serde_json::Value {
+    let mut current = match reader.stream_zip_entries_throwing_caution_to_the_wind() {
+        Ok(current) => current,
+        Err(error) => return to_json(Error::from(error)),
+    };
+
+    let mut entries = Vec::new();
+    let mut findings = vec![Finding::new(
+        "no_central_directory",
+        Severity::Info,
+        "entries were read from local file headers only, without a central directory to cross-validate them against",
+    )];
+    loop {
+        let index = entries.len();
+        let read = io::copy(&mut current, &mut io::sink());
+        let entry = match read {
+            Ok(measured) => StreamedEntry::new(current.entry(), measured),
+            Err(error) => {
+                findings.push(
+                    Finding::new(
+                        "stream_unreadable",
+                        Severity::Warning,
+                        format!(
+                            "reading the data of \"{}\" failed, so the rest of the stream couldn't be read: {error}",
+                            current.entry().name
+                        ),
+                    )
+                    .for_entry(index),
+                );
+                break;
+            }
+        };
+        on_entry(&entry);
+        entries.push(entry);
+
+        match current.finish() {
+            Ok(Some(next)) => current = next,
+            Ok(None) => break,
+            Err(error) => {
+                findings.push(Finding::new(
+                    "stream_unreadable",
+                    Severity::Warning,
+                    format!(
+                        "reading the local file header after {} entries failed: {error}",
+                        entries.len()
+                    ),
+                ));
+                break;
+            }
+        }
+    }
+
+    to_json(LocalStreamReport {
+        format_version: FORMAT_VERSION,
+        ziplinter_version: env!("CARGO_PKG_VERSION"),
+        entries,
+        findings,
+    })
+}
@@ -1,3 +1,288 @@
+use std::{
+    fs::File,
+    io::{BufReader, IsTerminal, Read},
+    ops::Range,
+    path::PathBuf,
+};
+
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use rc_zip::encoding::Encoding;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// The full report, as JSON
+    Json,
+    /// The findings, as a SARIF log
+    Sarif,
+    /// The full report, as YAML
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Rules {
+    /// Every check
+    All,
+    /// Checks for archives that readers may interpret differently, or that
+    /// try to harm the reader
+    Security,
+    /// Checks for violations of the zip specification
+    Conformance,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FailOn {
+    /// Always exit with status 0
+    None,
+    /// Exit with status 1 if there's a warning or an error
+    Warning,
+    /// Exit with status 1 if there's an error
+    Error,
+}
+
+impl FailOn {
+    /// The lowest severity that fails, if any
+    fn severity(self) -> Option<ziplinter::Severity> {
+        match self {
+            FailOn::None => None,
+            FailOn::Warning => Some(ziplinter::Severity::Warning),
+            FailOn::Error => Some(ziplinter::Severity::Error),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print what the structure that contains a byte offset contains, and
+    /// the name of its entry, e.g. to find out what an odd byte in a hex
+    /// dump belongs to
+    Explain {
+        /// Path to the zip file
+        path: PathBuf,
+
+        /// The offset, in decimal or as hex starting with 0x
+        #[arg(value_parser = parse_offset)]
+        offset: u64,
+    },
+}
+
+#[derive(Parser)]
+#[command(about = "A zip file analyzer")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to the zip file to analyze, or `-` to read it from stdin, which
+    /// is also the default when stdin isn't a terminal
+    path: Option<PathBuf>,
+
+    /// The other segments of a split archive, in order, after the first one
+    /// at the path above. Can be repeated.
+    #[arg(long, value_name = "PATH")]
+    segment: Vec<PathBuf>,
+
+    /// Password used to decrypt AE-x encrypted entries
+    #[arg(long)]
+    password: Option<String>,
+
+    /// Leave out findings below this severity: info, warning or error
+    #[arg(long, default_value = "info")]
+    min_severity: ziplinter::Severity,
+
+    /// Include the first this many decompressed bytes of each entry, as hex
+    #[arg(long, default_value_t = 0)]
+    content_preview: usize,
+
+    /// Include statistics about the work it took to analyze the archive
+    #[arg(long)]
+    stats: bool,
+
+    /// Include each non-ASCII name as decoded by every supported encoding
+    #[arg(long)]
+    name_encodings: bool,
+
+    /// Print the raw bytes of this range of the file as a hex dump, instead
+    /// of analyzing it
+    #[arg(long, value_name = "START:END", value_parser = parse_range)]
+    dump_range: Option<Range<u64>>,
+
+    /// Compare the file byte for byte with this known-good copy, and print
+    /// where it first differs instead of analyzing it
+    #[arg(long, value_name = "REFERENCE")]
+    first_divergence_from: Option<PathBuf>,
+
+    /// Only read the end of central directory, from the last 64 KiB or so
+    /// of the file, and print what it says about the archive
+    #[arg(long)]
+    tail: bool,
+
+    /// Reject archives that don't strictly conform to the zip specification,
+    /// reporting the first violation as an error and exiting with status 1
+    #[arg(long)]
+    strict: bool,
+
+    /// Exit with status 1 if there are findings at or above this severity,
+    /// or the archive couldn't be analyzed at all: none, warning or error
+    #[arg(long, value_enum, default_value = "none")]
+    fail_on: FailOn,
+
+    /// Decode names and comments as utf-8, cp-437 or shift-jis, instead of
+    /// guessing their encoding
+    #[arg(long, value_parser = parse_encoding)]
+    encoding: Option<Encoding>,
+
+    /// Include a digest of each entry's decompressed content, computed with
+    /// sha256, sha1 or blake3
+    #[arg(long, value_name = "ALGORITHM")]
+    digest: Option<ziplinter::DigestAlgorithm>,
+
+    /// Only include the entries with this name, or whose name starts with
+    /// what comes before a trailing `*`
+    #[arg(long, value_name = "NAME")]
+    entry: Option<String>,
+
+    /// Report entries whose names have more path components than this
+    #[arg(long, default_value_t = 128)]
+    max_path_depth: usize,
+
+    /// Check that stored entries are aligned the way `zipalign` aligns them
+    #[arg(long)]
+    zipalign: bool,
+
+    /// Try decoding entries that fail to decode with their declared method
+    /// with the other supported methods
+    #[arg(long)]
+    detect_mislabeled_methods: bool,
+
+    /// Print each finding to stderr as a line of JSON as soon as it's found,
+    /// before the full report. With --local-stream, each entry instead
+    #[arg(long)]
+    stream: bool,
+
+    /// Read the file front to back as a stream of local file headers, for
+    /// streaming zips that don't have a central directory (yet)
+    #[arg(long)]
+    local_stream: bool,
+
+    /// The set of checks to start from
+    #[arg(long, value_enum, default_value = "all")]
+    rules: Rules,
+
+    /// Also run the check with this finding code. Can be repeated.
+    #[arg(long, value_name = "CODE", value_parser = parse_rule)]
+    enable: Vec<String>,
+
+    /// Don't run the check with this finding code. Can be repeated.
+    #[arg(long, value_name = "CODE", value_parser = parse_rule)]
+    disable: Vec<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "json")]
+    format: Format,
+
+    /// Print canonical JSON, with sorted keys and without whitespace, so that
+    /// the output of scans of the same archive is byte-identical
+    #[arg(long)]
+    canonical: bool,
+
+    /// Print the JSON on a single line instead of indented, e.g. to process
+    /// the reports of many archives line by line
+    #[arg(short, long, conflicts_with = "canonical")]
+    compact: bool,
+}
+
+fn parse_range(range: &str) -> Result<Range<u64>, String> {
+    let (start, end) = range
+        .split_once(':')
+        .ok_or_else(|| format!("expected START:END, got \"{range}\""))?;
+    let start = start
+        .parse()
+        .map_err(|error| format!("invalid start: {error}"))?;
+    let end = end
+        .parse()
+        .map_err(|error| format!("invalid end: {error}"))?;
+    Ok(start..end)
+}
+
+fn parse_offset(offset: &str) -> Result<u64, String> {
+    match offset.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => offset.parse(),
+    }
+    .map_err(|error| format!("invalid offset \"{offset}\": {error}"))
+}
+
+fn parse_encoding(encoding: &str) -> Result<Encoding, String> {
+    [Encoding::Utf8, Encoding::Cp437, Encoding::ShiftJis]
+        .into_iter()
+        .find(|candidate| candidate.to_string() == encoding)
+        .ok_or_else(|| {
+            format!("unknown encoding \"{encoding}\", expected utf-8, cp-437 or shift-jis")
+        })
+}
+
+fn parse_rule(code: &str) -> Result<String, String> {
+    match ziplinter::rule_by_code(code) {
+        Some(rule) => Ok(rule.code.to_string()),
+        None => Err(format!("unknown rule \"{code}\"")),
+    }
+}
+
+/// Prints `data`, which starts at `offset` in the file, 16 bytes per line
+fn print_hex_dump(offset: u64, data: &[u8]) {
+    for (line, chunk) in data.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02x}")).collect();
+        let text: String = chunk
+            .iter()
+            .map(|b| {
+                if b.is_ascii_graphic() || *b == b' ' {
+                    *b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        println!(
+            "{:08x}  {:<47}  |{text}|",
+            offset + line as u64 * 16,
+            hex.join(" ")
+        );
+    }
+}
+
+/// Prints `value` as JSON, on a single line if `compact`
+fn print_json(value: &impl serde::Serialize, compact: bool) {
+    let json = if compact {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    };
+    println!("{}", json.unwrap());
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("error: {message}");
+    std::process::exit(1);
+}
+
+/// Where the archive is read from
+enum Input {
+    File(File),
+    /// All of stdin, read up front since parsing needs to seek
+    Stdin(Vec<u8>),
+}
+
+impl Input {
+    /// The file to read, or exits if the archive is read from stdin, which
+    /// `option` doesn't support
+    fn file(&self, option: &str) -> &File {
+        match self {
+            Input::File(file) => file,
+            Input::Stdin(_) => fail(format!("{option} needs a path, it can't read from stdin")),
+        }
+    }
+}
+
 fn main() {
     #[cfg(feature = "tracing")]
     {
@@ -7,13 +292,156 @@ fn main() {
         tracing::subscriber::set_global_default(subscriber).unwrap();
     }
 
-    let mut args = std::env::args();
-    let _ = args.next();
-    let path = args
-        .next()
-        .expect("Please provide a path to the zip file to analyze");
+    let cli = Cli::parse();
+    #[cfg(feature = "yaml")]
+    if matches!(cli.format, Format::Yaml) && (cli.canonical || cli.compact) {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--canonical and --compact only apply to JSON, not to --format yaml",
+            )
+            .exit()
+    }
+    let mut rules = match cli.rules {
+        Rules::All => ziplinter::RuleSet::all(),
+        Rules::Security => ziplinter::RuleSet::security(),
+        Rules::Conformance => ziplinter::RuleSet::conformance(),
+    };
+    for code in &cli.enable {
+        rules.enable(code).unwrap();
+    }
+    for code in &cli.disable {
+        rules.disable(code).unwrap();
+    }
+    let options = ziplinter::ParseOptions {
+        password: cli.password.map(String::into_bytes),
+        min_severity: cli.min_severity,
+        content_preview_bytes: cli.content_preview,
+        collect_stats: cli.stats,
+        name_encodings: cli.name_encodings,
+        strict: cli.strict,
+        max_path_depth: cli.max_path_depth,
+        check_alignment: cli.zipalign,
+        rules,
+        detect_mislabeled_methods: cli.detect_mislabeled_methods,
+        forced_encoding: cli.encoding,
+        digest: cli.digest,
+        entry_filter: cli.entry,
+    };
+
+    if let Some(Command::Explain { path, offset }) = &cli.command {
+        let file =
+            File::open(path).unwrap_or_else(|error| fail(format!("{}: {error}", path.display())));
+        let parsed_ranges =
+            ziplinter::parsed_ranges_file(&file).unwrap_or_else(|error| fail(error));
+        let Some(range) = parsed_ranges.at(*offset) else {
+            fail(format!(
+                "offset {offset} is not covered by any parsed structure"
+            ))
+        };
+        match range.filename() {
+            Some(filename) => println!("{} of \"{filename}\"", range.contains()),
+            None => println!("{}", range.contains()),
+        }
+        return;
+    }
 
-    let file = std::fs::File::open(path).unwrap();
-    let value = ziplinter::parse_file(&file);
-    println!("{}", serde_json::to_string_pretty(&value).unwrap());
+    let path = cli.path.clone().unwrap_or_else(|| {
+        if std::io::stdin().is_terminal() {
+            Cli::command()
+                .error(
+                    clap::error::ErrorKind::MissingRequiredArgument,
+                    "a path to the zip file is required when stdin is a terminal",
+                )
+                .exit()
+        }
+        PathBuf::from("-")
+    });
+    let input = if path.as_os_str() == "-" {
+        let mut bytes = Vec::new();
+        if let Err(error) = std::io::stdin().read_to_end(&mut bytes) {
+            fail(error);
+        }
+        Input::Stdin(bytes)
+    } else {
+        Input::File(
+            File::open(&path).unwrap_or_else(|error| fail(format!("{}: {error}", path.display()))),
+        )
+    };
+
+    if let Some(range) = cli.dump_range {
+        match ziplinter::read_range(input.file("--dump-range"), range.clone()) {
+            Ok(data) => print_hex_dump(range.start, &data),
+            Err(error) => fail(error),
+        }
+        return;
+    }
+
+    if cli.tail {
+        match ziplinter::parse_tail(input.file("--tail")) {
+            Ok(summary) => print_json(&summary, cli.compact),
+            Err(error) => fail(error),
+        }
+        return;
+    }
+
+    if let Some(reference) = &cli.first_divergence_from {
+        let file = input.file("--first-divergence-from");
+        let reference = File::open(reference)
+            .unwrap_or_else(|error| fail(format!("{}: {error}", reference.display())));
+        match ziplinter::first_divergence(&reference, file) {
+            Ok(divergence) => print_json(&divergence, cli.compact),
+            Err(error) => fail(error),
+        }
+        return;
+    }
+
+    let mut on_finding = |finding: &ziplinter::Finding| {
+        if cli.stream {
+            eprintln!("{}", serde_json::to_string(finding).unwrap())
+        }
+    };
+    let value = if cli.local_stream {
+        let reader: Box<dyn Read> = match &input {
+            Input::File(file) => Box::new(BufReader::new(file)),
+            Input::Stdin(bytes) => Box::new(bytes.as_slice()),
+        };
+        ziplinter::parse_local_stream(reader, &mut |entry| {
+            if cli.stream {
+                eprintln!("{}", serde_json::to_string(entry).unwrap())
+            }
+        })
+    } else if !cli.segment.is_empty() {
+        input.file("--segment");
+        let segments: Vec<&std::path::Path> = std::iter::once(&path)
+            .chain(&cli.segment)
+            .map(PathBuf::as_path)
+            .collect();
+        ziplinter::parse_split_streaming(&segments, &options, &mut on_finding)
+    } else {
+        match &input {
+            Input::File(file) => ziplinter::parse_file_streaming(file, &options, &mut on_finding),
+            Input::Stdin(bytes) => {
+                ziplinter::parse_bytes_streaming(bytes, &options, &mut on_finding)
+            }
+        }
+    };
+    let failed = cli
+        .fail_on
+        .severity()
+        .is_some_and(|fail_on| ziplinter::max_severity(&value) >= Some(fail_on));
+    let rejected = cli.strict && value.get("error").is_some();
+    let value = match cli.format {
+        Format::Sarif => ziplinter::to_sarif(&value, &path.to_string_lossy()),
+        _ => value,
+    };
+    match cli.format {
+        #[cfg(feature = "yaml")]
+        Format::Yaml => print!("{}", serde_yaml_ng::to_string(&value).unwrap()),
+        _ if cli.canonical => println!("{}", ziplinter::to_canonical_json(&value)),
+        _ => print_json(&value, cli.compact),
+    }
+    if rejected || failed {
+        std::process::exit(1);
+    }
 }
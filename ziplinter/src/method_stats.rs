@@ -0,0 +1,71 @@
+//! Compression efficiency per method, from the declared sizes of the entries.
+//! An archive where one method accounts for a pathological expansion ratio
+//! stands out here, even when no single entry does.
+
+use std::collections::BTreeMap;
+
+use rc_zip::parse::{Entry, Method};
+
+/// Aggregate sizes of the entries compressed with one method
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct MethodStats {
+    /// Number of entries
+    pub count: u64,
+
+    /// Declared compressed bytes over all entries
+    pub total_in: u64,
+
+    /// Declared uncompressed bytes over all entries
+    pub total_out: u64,
+
+    /// `total_out` divided by `total_in`, or `None` if `total_in` is zero
+    pub ratio: Option<f64>,
+}
+
+/// Aggregates the declared sizes of `entries` by compression method, keyed
+/// by the lowercase method name, e.g. `deflate` or `unrecognized(7)`
+pub(crate) fn method_stats<'a>(
+    entries: impl IntoIterator<Item = &'a Entry>,
+) -> BTreeMap<String, MethodStats> {
+    let mut stats: BTreeMap<String, MethodStats> = BTreeMap::new();
+    for entry in entries {
+        let method = stats
+            .entry(format!("{:?}", entry.method).to_lowercase())
+            .or_default();
+        method.count += 1;
+        method.total_in = method.total_in.saturating_add(entry.compressed_size);
+        method.total_out = method.total_out.saturating_add(entry.uncompressed_size);
+    }
+    for method in stats.values_mut() {
+        method.ratio =
+            (method.total_in > 0).then(|| method.total_out as f64 / method.total_in as f64);
+    }
+    stats
+}
+
+/// The distinct compression methods of `entries`, in order of first use,
+/// and those of them this build can't decompress. For AE-x encrypted
+/// entries, the method of the encrypted data counts as well.
+pub(crate) fn methods_present<'a>(
+    entries: impl IntoIterator<Item = &'a Entry>,
+) -> (Vec<Method>, Vec<Method>) {
+    let mut present = Vec::new();
+    for entry in entries {
+        let inner = entry
+            .aex
+            .as_ref()
+            .filter(|_| entry.method == Method::Aex)
+            .map(|aex| Method::from(aex.compression_method));
+        for method in std::iter::once(entry.method).chain(inner) {
+            if !present.contains(&method) {
+                present.push(method);
+            }
+        }
+    }
+    let undecodable = present
+        .iter()
+        .copied()
+        .filter(|method| !method.is_decodable())
+        .collect();
+    (present, undecodable)
+}
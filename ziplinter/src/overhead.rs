@@ -0,0 +1,37 @@
+//! How much of an archive is structure rather than file data. Many tiny
+//! files or bloated headers show up as a high overhead ratio, and so does
+//! data hidden in header fields.
+
+use rc_zip::fsm::ParsedRanges;
+
+/// Bytes of structure versus bytes of file data, over all parsed ranges
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Overhead {
+    /// Bytes of everything but file data: headers, data descriptors, the
+    /// central directory, the end of central directory record and annotated
+    /// structures such as an APK signing block
+    pub overhead_bytes: u64,
+
+    /// Bytes of (compressed) file data
+    pub data_bytes: u64,
+
+    /// `overhead_bytes` as a fraction of both, or `None` if both are zero
+    pub overhead_ratio: Option<f64>,
+}
+
+/// Sums up the lengths of the parsed ranges by whether they hold file data
+pub(crate) fn overhead(ranges: &ParsedRanges) -> Overhead {
+    let mut overhead = Overhead::default();
+    for range in ranges.iter() {
+        let length = range.range().end.saturating_sub(range.range().start);
+        // declared lengths can be huge, the counts stop at u64::MAX
+        if range.contains() == "file data" {
+            overhead.data_bytes = overhead.data_bytes.saturating_add(length);
+        } else {
+            overhead.overhead_bytes = overhead.overhead_bytes.saturating_add(length);
+        }
+    }
+    let total = overhead.overhead_bytes as f64 + overhead.data_bytes as f64;
+    overhead.overhead_ratio = (total > 0.0).then(|| overhead.overhead_bytes as f64 / total);
+    overhead
+}
@@ -0,0 +1,82 @@
+//! The distribution of compression ratios over the entries of an archive.
+//! A zip bomb hidden among benign entries, or one made of many copies of the
+//! same entry, stands out in the distribution even when a single threshold
+//! on the ratio would have to be too strict or too lenient to catch it.
+
+use rc_zip::parse::Entry;
+
+/// Ratios below this are unremarkable for any method: text compresses to a
+/// tenth, Deflate can't do better than about 1:1032
+const SUSPICIOUS_RATIO: f64 = 100.0;
+
+/// An entry is an outlier if its ratio is at least this many times the median
+const OUTLIER_FACTOR: f64 = 20.0;
+
+/// Ratio of `uncompressed_size` to `compressed_size`, or `None` if the
+/// compressed size is zero
+pub(crate) fn compression_ratio(uncompressed_size: u64, compressed_size: u64) -> Option<f64> {
+    (compressed_size > 0).then(|| uncompressed_size as f64 / compressed_size as f64)
+}
+
+/// Summary of the ratios of uncompressed to compressed size of the entries,
+/// from their declared sizes. Entries with a compressed size of zero have no
+/// ratio and are left out.
+#[derive(serde::Serialize, Debug, Clone, PartialEq)]
+pub struct RatioDistribution {
+    /// The highest ratio
+    pub max: f64,
+
+    /// The median ratio
+    pub median: f64,
+
+    /// The 99th percentile (nearest rank)
+    pub p99: f64,
+
+    /// Number of entries whose ratio is suspiciously high, and far above
+    /// the median
+    pub outlier_count: u64,
+
+    /// Whether at least half of the entries, and more than one, share the
+    /// same suspiciously high ratio
+    #[serde(skip)]
+    uniformly_suspicious: bool,
+}
+
+impl RatioDistribution {
+    /// Summarizes the ratios of `entries`, or returns `None` if none of them
+    /// has a ratio
+    pub(crate) fn new<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Option<Self> {
+        let mut ratios: Vec<f64> = entries
+            .into_iter()
+            .filter_map(|entry| compression_ratio(entry.uncompressed_size, entry.compressed_size))
+            .collect();
+        ratios.sort_by(f64::total_cmp);
+        let count = ratios.len();
+        let max = *ratios.last()?;
+        let median = match count % 2 {
+            0 => (ratios[count / 2 - 1] + ratios[count / 2]) / 2.0,
+            _ => ratios[count / 2],
+        };
+        let p99 = ratios[(count * 99).div_ceil(100) - 1];
+        let outlier_count = ratios
+            .iter()
+            .filter(|ratio| **ratio >= SUSPICIOUS_RATIO && **ratio >= OUTLIER_FACTOR * median)
+            .count() as u64;
+        let at_max = ratios.iter().filter(|ratio| **ratio == max).count();
+        let uniformly_suspicious = at_max > 1 && at_max * 2 >= count && max >= SUSPICIOUS_RATIO;
+        Some(Self {
+            max,
+            median,
+            p99,
+            outlier_count,
+            uniformly_suspicious,
+        })
+    }
+
+    /// Whether the distribution looks like that of a zip bomb: a few entries
+    /// with ratios far above the rest, or many entries with the same
+    /// suspiciously high ratio
+    pub(crate) fn is_bomb_fingerprint(&self) -> bool {
+        self.outlier_count > 0 || self.uniformly_suspicious
+    }
+}
@@ -0,0 +1,294 @@
+//! The registry of checks, each identified by the stable `code` of the
+//! findings it produces, and sets of enabled checks to analyze with.
+
+use std::collections::BTreeSet;
+
+/// A check, as listed in [RULES]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    /// The `code` of the findings this check produces
+    pub code: &'static str,
+
+    /// What the check looks for
+    pub summary: &'static str,
+
+    /// Whether the check is part of [RuleSet::security]: it looks for
+    /// archives that different readers may interpret differently, or that
+    /// try to harm the reader
+    pub security: bool,
+
+    /// Whether the check is part of [RuleSet::conformance]: it looks for
+    /// violations of the zip specification
+    pub conformance: bool,
+}
+
+const fn rule(
+    code: &'static str,
+    summary: &'static str,
+    security: bool,
+    conformance: bool,
+) -> Rule {
+    Rule {
+        code,
+        summary,
+        security,
+        conformance,
+    }
+}
+
+/// Every check that analyzing an archive can run, in alphabetical order
+pub const RULES: &[Rule] = &[
+    rule(
+        "aex_length_inconsistent",
+        "AE-x encrypted data too short for its salt, password verification value and authentication code",
+        false,
+        true,
+    ),
+    rule(
+        "data_descriptor_size_ambiguous",
+        "data descriptor that only lines up with the next record when read with the other size width",
+        true,
+        false,
+    ),
+    rule(
+        "data_region_size_mismatch",
+        "compressed size that doesn't match the space before the next structure",
+        true,
+        true,
+    ),
+    rule(
+        "deflate_declared_but_invalid",
+        "entry declared as Deflate whose data isn't valid Deflate",
+        true,
+        false,
+    ),
+    rule(
+        "directory_size_sum_mismatch",
+        "central directory size that differs from the sum of its headers",
+        true,
+        true,
+    ),
+    rule(
+        "empty_directory_inconsistent",
+        "end of central directory record whose entry count, size and offset disagree",
+        true,
+        true,
+    ),
+    rule(
+        "encryption_flag_inconsistent",
+        "encryption flag, method and extra field that disagree",
+        true,
+        true,
+    ),
+    rule(
+        "eocd_signature_collision",
+        "end of central directory signature other than the one of the actual record",
+        true,
+        false,
+    ),
+    rule(
+        "excessive_path_depth",
+        "entry name with more path components than the configured maximum",
+        true,
+        false,
+    ),
+    rule(
+        "extension_mismatch",
+        "file extension that doesn't match the detected content",
+        true,
+        false,
+    ),
+    rule(
+        "extra_length_mismatch",
+        "extra field length that doesn't match the extra field read",
+        false,
+        true,
+    ),
+    rule(
+        "header_data_overlap",
+        "local file header overlapping the data of an entry",
+        true,
+        true,
+    ),
+    rule(
+        "local_header_size_inconsistent",
+        "local file header whose parsed size differs from its field lengths",
+        false,
+        true,
+    ),
+    rule(
+        "measured_size_mismatch",
+        "declared sizes that differ from the sizes measured while decompressing",
+        true,
+        true,
+    ),
+    rule(
+        "method_mislabeled",
+        "entry data in another format than its method says, when detecting mislabeled methods",
+        true,
+        false,
+    ),
+    rule(
+        "misaligned_entry",
+        "stored entry not aligned the way zipalign aligns it, when checking alignment",
+        false,
+        false,
+    ),
+    rule(
+        "name_length_mismatch",
+        "local and central directory headers storing different file names",
+        true,
+        true,
+    ),
+    rule(
+        "offset_cycle",
+        "local file headers whose sizes lead a streaming reader in a cycle",
+        true,
+        false,
+    ),
+    rule(
+        "overruns_central_directory",
+        "entry data whose declared size runs past the start of the central directory",
+        true,
+        true,
+    ),
+    rule(
+        "polyglot_candidate",
+        "bytes before the first entry that start like a PE, ELF, PDF, PNG or GIF file",
+        true,
+        false,
+    ),
+    rule(
+        "post_descriptor_gap",
+        "bytes between a data descriptor and the next structure",
+        true,
+        false,
+    ),
+    rule(
+        "repeated_identical_entries",
+        "many entries with identical sizes and CRC-32, as in zip bombs",
+        true,
+        false,
+    ),
+    rule(
+        "reserved_device_name",
+        "path component that is a reserved device name on Windows",
+        true,
+        false,
+    ),
+    rule(
+        "stored_high_entropy",
+        "stored entry whose content looks compressed or encrypted",
+        true,
+        false,
+    ),
+    rule(
+        "structure_overlap",
+        "two structures, e.g. central directory headers, that claim the same bytes",
+        true,
+        false,
+    ),
+    rule(
+        "suspicious_directory_entry",
+        "directory entry with content, which extractors that create the directory ignore",
+        true,
+        false,
+    ),
+    rule(
+        "unicode_comment_mismatch",
+        "Info-ZIP Unicode comment extra field that doesn't match the comment",
+        true,
+        false,
+    ),
+    rule(
+        "unusual_gp_flags",
+        "general purpose flags that are reserved or rarely used",
+        false,
+        true,
+    ),
+    rule(
+        "zero_crc_nonempty",
+        "non-empty entry with a CRC-32 of zero",
+        true,
+        true,
+    ),
+    rule(
+        "zip64_determination_inconsistent",
+        "local file header with a zip64 extra field but no 0xFFFFFFFF size, or the other way around",
+        true,
+        false,
+    ),
+];
+
+/// Looks up a rule by its code
+pub fn rule_by_code(code: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.code == code)
+}
+
+/// The checks to run while analyzing an archive. Disabled checks don't run,
+/// unless they decide `strict_conformant`: their findings are then left out
+/// of the report, though they still count towards it.
+///
+/// The default is [RuleSet::all].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSet {
+    enabled: BTreeSet<&'static str>,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl RuleSet {
+    fn with(filter: impl Fn(&Rule) -> bool) -> Self {
+        Self {
+            enabled: RULES
+                .iter()
+                .filter(|rule| filter(rule))
+                .map(|rule| rule.code)
+                .collect(),
+        }
+    }
+
+    /// Every check in [RULES]
+    pub fn all() -> Self {
+        Self::with(|_| true)
+    }
+
+    /// No checks at all, e.g. to enable only a few
+    pub fn none() -> Self {
+        Self::with(|_| false)
+    }
+
+    /// The checks for archives that different readers may interpret
+    /// differently, or that try to harm the reader
+    pub fn security() -> Self {
+        Self::with(|rule| rule.security)
+    }
+
+    /// The checks for violations of the zip specification
+    pub fn conformance() -> Self {
+        Self::with(|rule| rule.conformance)
+    }
+
+    /// Enables the check with this code. Fails for codes not in [RULES].
+    pub fn enable(&mut self, code: &str) -> Result<(), String> {
+        let rule = rule_by_code(code).ok_or_else(|| format!("unknown rule \"{code}\""))?;
+        self.enabled.insert(rule.code);
+        Ok(())
+    }
+
+    /// Disables the check with this code. Fails for codes not in [RULES].
+    pub fn disable(&mut self, code: &str) -> Result<(), String> {
+        let rule = rule_by_code(code).ok_or_else(|| format!("unknown rule \"{code}\""))?;
+        self.enabled.remove(rule.code);
+        Ok(())
+    }
+
+    /// Whether findings with this code are reported
+    pub fn is_enabled(&self, code: &str) -> bool {
+        self.enabled.contains(code)
+    }
+}
@@ -0,0 +1,109 @@
+//! Conversion of reports to SARIF, the Static Analysis Results Interchange
+//! Format, so that findings can be ingested by code scanning dashboards.
+//! See <https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html>.
+
+use serde_json::{json, Value};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const VERSION: &str = "2.1.0";
+
+/// SARIF level for a serialized [crate::Severity]
+fn level(severity: &Value) -> &'static str {
+    match severity.as_str() {
+        Some("error") => "error",
+        Some("warning") => "warning",
+        _ => "note",
+    }
+}
+
+/// Length of the parsed range of the report that starts at `offset`, such as
+/// the local file header a finding about an entry points at
+fn range_length_at(report: &Value, offset: u64) -> Option<u64> {
+    report["parsed_ranges"]
+        .as_array()?
+        .iter()
+        .find(|range| range["start"].as_u64() == Some(offset))
+        .and_then(|range| Some(range["end"].as_u64()? - offset))
+}
+
+/// Converts a report, as returned by [crate::parse_file], into a SARIF log
+/// with a single run. `artifact_uri` identifies the analyzed archive.
+///
+/// Each finding becomes a result whose rule is the finding's `code`, located
+/// at the finding's byte offset in the archive, spanning the parsed range
+/// that starts there if any, and, for findings about an entry, at that
+/// entry's name.
+pub fn to_sarif(report: &Value, artifact_uri: &str) -> Value {
+    let findings = report["findings"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut rules: Vec<&str> = Vec::new();
+    let mut results = Vec::new();
+    for finding in findings {
+        let code = finding["code"].as_str().unwrap_or_default();
+        let rule_index = match rules.iter().position(|rule| *rule == code) {
+            Some(index) => index,
+            None => {
+                rules.push(code);
+                rules.len() - 1
+            }
+        };
+
+        let mut location = json!({
+            "physicalLocation": {
+                "artifactLocation": { "uri": artifact_uri, "index": 0 },
+            },
+        });
+        if let Some(offset) = finding["offset"].as_u64() {
+            let mut region = json!({ "byteOffset": offset });
+            if let Some(length) = range_length_at(report, offset) {
+                region["byteLength"] = json!(length);
+            }
+            location["physicalLocation"]["region"] = region;
+        }
+        if let Some(entry) = finding["entry"].as_u64() {
+            let name = &report["contents"][entry as usize]["central"]["name"];
+            location["logicalLocations"] = json!([{ "fullyQualifiedName": name }]);
+        }
+
+        results.push(json!({
+            "ruleId": code,
+            "ruleIndex": rule_index,
+            "level": level(&finding["severity"]),
+            "message": { "text": finding["message"] },
+            "locations": [location],
+        }));
+    }
+
+    let mut run = json!({
+        "tool": {
+            "driver": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+                "informationUri": "https://github.com/trifectatechfoundation/ziplinter",
+                "rules": rules.iter().map(|rule| json!({ "id": rule })).collect::<Vec<_>>(),
+            },
+        },
+        "artifacts": [{ "location": { "uri": artifact_uri } }],
+        "results": results,
+    });
+
+    // the archive couldn't be parsed at all
+    if let Some(error) = report["error"].as_str() {
+        run["invocations"] = json!([{
+            "executionSuccessful": false,
+            "toolExecutionNotifications": [{
+                "level": "error",
+                "message": { "text": error },
+            }],
+        }]);
+    }
+
+    json!({
+        "$schema": SCHEMA,
+        "version": VERSION,
+        "runs": [run],
+    })
+}
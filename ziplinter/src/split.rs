@@ -0,0 +1,195 @@
+//! Split (multi-disk) archives, e.g. `archive.z01`, `archive.z02` and
+//! `archive.zip`, read as the concatenation of their segments.
+//!
+//! Offsets in the report, like those of parsed ranges and findings, are
+//! offsets in that concatenation. Offsets stored in the archive are relative
+//! to the segment (disk) they point into, and are translated using the disk
+//! numbers stored along with them.
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use rc_zip::{encoding::Encoding, fsm::ArchiveFsm};
+use rc_zip_sync::{ArchiveHandle, HasCursor, ReadZip, ReadZipWithSize};
+
+use crate::{report, to_json, Error, Finding, ParseOptions};
+
+/// A segment of a split archive
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// Path the segment was read from
+    pub path: PathBuf,
+
+    /// Offset of the segment in the concatenation of all segments
+    pub offset: u64,
+
+    /// Size of the segment in bytes
+    pub size: u64,
+}
+
+/// The segments of a split archive, readable as if they were a single file
+pub struct SplitArchive {
+    files: Vec<File>,
+    segments: Vec<Segment>,
+    size: u64,
+}
+
+impl SplitArchive {
+    /// Opens the segments, which must be in order: the first disk first, the
+    /// one with the end of central directory record last.
+    pub fn open(paths: &[&Path]) -> io::Result<Self> {
+        let mut files = Vec::with_capacity(paths.len());
+        let mut segments = Vec::with_capacity(paths.len());
+        let mut size = 0;
+        for path in paths {
+            let file = File::open(path)?;
+            let length = file.metadata()?.len();
+            segments.push(Segment {
+                path: path.to_path_buf(),
+                offset: size,
+                size: length,
+            });
+            files.push(file);
+            size += length;
+        }
+        Ok(Self {
+            files,
+            segments,
+            size,
+        })
+    }
+
+    /// The segments, in order
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Translates an offset in the concatenation of all segments to the
+    /// index of the segment it's in and the offset within that segment.
+    /// Returns `None` past the end of the last segment.
+    pub fn segment_at(&self, offset: u64) -> Option<(usize, u64)> {
+        // empty segments start where the next one does, so this skips them
+        let index = self
+            .segments
+            .partition_point(|segment| segment.offset <= offset)
+            .checked_sub(1)?;
+        let segment = &self.segments[index];
+        let within = offset - segment.offset;
+        (within < segment.size).then_some((index, within))
+    }
+}
+
+/// A [Read] over the concatenation of the segments of a [SplitArchive]
+pub struct SplitCursor<'a> {
+    archive: &'a SplitArchive,
+    offset: u64,
+}
+
+impl Read for SplitCursor<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some((index, within)) = self.archive.segment_at(self.offset) else {
+            return Ok(0);
+        };
+        // reads never cross a segment boundary, the next read continues in
+        // the next segment
+        let remaining = self.archive.segments[index].size - within;
+        let length = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        let read = self.archive.files[index]
+            .cursor_at(within)
+            .read(&mut buf[..length])?;
+        self.offset += read as u64;
+        Ok(read)
+    }
+}
+
+impl HasCursor for SplitArchive {
+    type Cursor<'a> = SplitCursor<'a>;
+
+    fn cursor_at(&self, offset: u64) -> Self::Cursor<'_> {
+        SplitCursor {
+            archive: self,
+            offset,
+        }
+    }
+}
+
+impl ReadZip for SplitArchive {
+    type File = Self;
+
+    fn read_zip(&self) -> Result<ArchiveHandle<'_, Self>, rc_zip::error::Error> {
+        let disk_offsets = self.segments.iter().map(|segment| segment.offset).collect();
+        self.read_zip_with_disk_offsets(self.size, disk_offsets)
+    }
+
+    fn read_zip_with_encoding(
+        &self,
+        encoding: Encoding,
+    ) -> Result<ArchiveHandle<'_, Self>, rc_zip::error::Error> {
+        let disk_offsets = self.segments.iter().map(|segment| segment.offset).collect();
+        self.read_zip_with_fsm(
+            ArchiveFsm::new(self.size)
+                .with_disk_offsets(disk_offsets)
+                .with_encoding(encoding),
+        )
+    }
+}
+
+/// Analyzes a split archive, given the paths of all of its segments in
+/// order, see [SplitArchive::open]. The report is that of the archive the
+/// segments make up together, with the `segments` it was read from, and
+/// the `segment` each entry's local file header is on.
+pub fn parse_split(segments: &[&Path]) -> serde_json::Value {
+    parse_split_with(segments, &ParseOptions::default())
+}
+
+/// Like [parse_split], with non-default [ParseOptions].
+pub fn parse_split_with(segments: &[&Path], options: &ParseOptions) -> serde_json::Value {
+    parse_split_streaming(segments, options, &mut |_| {})
+}
+
+/// Like [parse_split_with], but also passes each finding to `on_finding` as
+/// soon as it's found, see [crate::parse_bytes_streaming].
+pub fn parse_split_streaming(
+    segments: &[&Path],
+    options: &ParseOptions,
+    on_finding: &mut dyn FnMut(&Finding),
+) -> serde_json::Value {
+    let archive = match SplitArchive::open(segments) {
+        Ok(archive) => archive,
+        Err(error) => return to_json(Error::from(error)),
+    };
+    let metadata = match report(&archive, options, on_finding) {
+        Ok(metadata) => metadata,
+        Err(error) => return to_json(error),
+    };
+    // from the entries that are left after filtering, so they line up with
+    // `contents` in the report
+    let entry_segments: Vec<Option<usize>> = metadata
+        .contents
+        .iter()
+        .map(|file| {
+            archive
+                .segment_at(file.header_offset)
+                .map(|(index, _)| index)
+        })
+        .collect();
+    let mut report = to_json(metadata);
+    let Some(object) = report.as_object_mut() else {
+        return report;
+    };
+    if let Some(contents) = object
+        .get_mut("contents")
+        .and_then(serde_json::Value::as_array_mut)
+    {
+        for (file, segment) in contents.iter_mut().zip(entry_segments) {
+            file["segment"] = serde_json::json!(segment);
+        }
+    }
+    object.insert("segments".into(), serde_json::json!(archive.segments));
+    report
+}
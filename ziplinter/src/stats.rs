@@ -0,0 +1,27 @@
+//! Statistics about the work it took to analyze an archive, to find out why
+//! some archives are slow to lint.
+
+/// What it took to analyze an archive, see [crate::ParseOptions::collect_stats]
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct Stats {
+    /// Bytes read to parse the end of central directory and the central directory
+    pub bytes_read: u64,
+
+    /// Number of iterations of the state machine parsing the central directory
+    pub process_calls: u64,
+
+    /// Largest amount of data buffered at once while parsing the central directory
+    pub peak_buffered: u64,
+
+    /// Time spent parsing the central directory, in microseconds
+    pub central_directory_micros: u64,
+
+    /// Time spent reading local file headers and entry data, in microseconds
+    pub entries_micros: u64,
+
+    /// Compressed bytes read while decompressing entries
+    pub entry_bytes_read: u64,
+
+    /// Bytes produced while decompressing entries
+    pub decompressed_bytes: u64,
+}
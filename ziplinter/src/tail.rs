@@ -0,0 +1,149 @@
+//! Reading just the end of central directory of an archive, from a single
+//! small read at the end of the file, for triaging large numbers of
+//! archives without reading their central directories.
+
+use std::{fs::File, io::Read};
+
+use rc_zip::{
+    fsm::{ArchiveFsm, FsmResult},
+    parse::{EndOfCentralDirectory64Locator, EndOfCentralDirectoryRecord},
+};
+use rc_zip_sync::HasCursor;
+
+/// Length of a zip64 end of central directory record without its extensible
+/// data sector
+const EOCD64_RECORD_LENGTH: usize = 56;
+
+/// How many bytes [parse_tail] reads: enough for an end of central directory
+/// record with the longest possible comment, preceded by a zip64 locator and
+/// a zip64 end of central directory record without extensible data.
+pub const TAIL_LENGTH: u64 = (EndOfCentralDirectoryRecord::MAX_LENGTH
+    + EndOfCentralDirectory64Locator::LENGTH
+    + EOCD64_RECORD_LENGTH) as u64;
+
+/// What the end of central directory says about an archive, see [parse_tail]
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TailSummary {
+    /// Number of entries in the central directory
+    pub entries: u64,
+
+    /// Size of the central directory in bytes
+    pub directory_size: u64,
+
+    /// Offset of the central directory in the file, corrected for data
+    /// prepended to the archive
+    pub directory_offset: u64,
+
+    /// The archive comment, decoded as UTF-8 with invalid sequences replaced
+    pub comment: String,
+
+    /// Whether the archive has a zip64 end of central directory record
+    pub zip64: bool,
+}
+
+/// Why [parse_tail] failed
+#[derive(Debug)]
+pub enum TailError {
+    /// The file couldn't be read, or has no valid end of central directory
+    Zip(rc_zip::error::Error),
+
+    /// The zip64 end of central directory record isn't in the part of the
+    /// file that was read, e.g. because it has a large extensible data
+    /// sector or is stored at an unusual place
+    Insufficient {
+        /// Offset of the data that was needed
+        offset: u64,
+
+        /// Offset of the first byte that was read
+        tail_offset: u64,
+    },
+}
+
+impl std::fmt::Display for TailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TailError::Zip(error) => error.fmt(f),
+            TailError::Insufficient {
+                offset,
+                tail_offset,
+            } => write!(
+                f,
+                "needed data at offset {offset}, before the tail that was read from offset {tail_offset}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TailError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TailError::Zip(error) => Some(error),
+            TailError::Insufficient { .. } => None,
+        }
+    }
+}
+
+impl From<rc_zip::error::Error> for TailError {
+    fn from(error: rc_zip::error::Error) -> Self {
+        TailError::Zip(error)
+    }
+}
+
+impl From<std::io::Error> for TailError {
+    fn from(error: std::io::Error) -> Self {
+        TailError::Zip(error.into())
+    }
+}
+
+/// Reads the last [TAIL_LENGTH] bytes of `file` in a single read, and
+/// summarizes the end of central directory records found in it, without
+/// reading the central directory itself.
+pub fn parse_tail(file: &File) -> Result<TailSummary, TailError> {
+    let size = file.metadata()?.len();
+    let tail_length = size.min(TAIL_LENGTH);
+    let tail_offset = size - tail_length;
+    let mut tail = vec![0; tail_length as usize];
+    file.cursor_at(tail_offset).read_exact(&mut tail)?;
+
+    let mut fsm = ArchiveFsm::with_haystack_size(size, tail_length);
+    loop {
+        if let Some(eocd) = fsm.end_of_central_directory() {
+            return Ok(TailSummary {
+                entries: eocd.directory_records(),
+                directory_size: eocd.directory_size(),
+                directory_offset: eocd.directory_offset(),
+                comment: String::from_utf8_lossy(eocd.comment()).into_owned(),
+                zip64: eocd.dir64.is_some(),
+            });
+        }
+
+        if let Some(offset) = fsm.wants_read() {
+            if offset < tail_offset {
+                return Err(TailError::Insufficient {
+                    offset,
+                    tail_offset,
+                });
+            }
+            let available = tail
+                .get((offset - tail_offset) as usize..)
+                .unwrap_or_default();
+            let space = fsm.space();
+            let count = space.len().min(available.len());
+            if count == 0 {
+                return Err(rc_zip::error::Error::from(std::io::Error::from(
+                    std::io::ErrorKind::UnexpectedEof,
+                ))
+                .into());
+            }
+            space[..count].copy_from_slice(&available[..count]);
+            fsm.fill(count);
+        }
+
+        fsm = match fsm.process()? {
+            FsmResult::Continue(fsm) => fsm,
+            // the end of central directory comes before the central
+            // directory, so this returns before the archive is done
+            FsmResult::Done(_) => unreachable!(),
+        };
+    }
+}
@@ -0,0 +1,91 @@
+//! Verification that `zipalign` ran on an APK.
+//!
+//! Android maps uncompressed entries straight from the archive, which needs
+//! their data to be aligned: to 4 bytes in general, and to 4 KiB pages for
+//! shared libraries. See <https://developer.android.com/tools/zipalign>.
+
+use std::collections::HashMap;
+
+use rc_zip::{fsm::ParsedRanges, parse::Method};
+
+use crate::{FileMetadata, Finding, Severity};
+
+/// Alignment of the data of stored entries
+const DEFAULT_ALIGNMENT: u64 = 4;
+
+/// Alignment of the data of stored shared libraries
+const PAGE_ALIGNMENT: u64 = 4096;
+
+/// Where the data of a stored entry starts, and the boundary it has to be
+/// aligned to
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct Alignment {
+    /// Offset of the first byte of data in the file
+    pub offset: u64,
+
+    /// The boundary `offset` has to be a multiple of: 4096 for shared
+    /// libraries (`.so`), 4 for everything else
+    pub aligned_to: u64,
+}
+
+impl Alignment {
+    /// Whether the data starts at the required boundary
+    pub fn is_aligned(&self) -> bool {
+        self.offset.is_multiple_of(self.aligned_to)
+    }
+}
+
+fn required_alignment(name: &str) -> u64 {
+    if name.ends_with(".so") {
+        PAGE_ALIGNMENT
+    } else {
+        DEFAULT_ALIGNMENT
+    }
+}
+
+/// Records the alignment of the data of each stored file in its local
+/// header, taking the data offset from the end of the local file header
+/// range. Reports the entries whose data isn't aligned.
+pub(crate) fn check_alignment(
+    ranges: &ParsedRanges,
+    contents: &mut [FileMetadata],
+    entries: &HashMap<u64, usize>,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for range in ranges
+        .iter()
+        .filter(|range| range.contains() == "local file header")
+    {
+        let range = range.range();
+        let Some(&index) = entries.get(&range.start) else {
+            continue;
+        };
+        let Ok(local) = &mut contents[index].local else {
+            continue;
+        };
+        if local.method != Method::Store || local.name.ends_with('/') {
+            continue;
+        }
+
+        let alignment = Alignment {
+            offset: range.end,
+            aligned_to: required_alignment(&local.name),
+        };
+        if !alignment.is_aligned() {
+            findings.push(
+                Finding::new(
+                    "misaligned_entry",
+                    Severity::Warning,
+                    format!(
+                        "data of stored entry \"{}\" starts at {}, which isn't aligned to {} bytes",
+                        local.name, alignment.offset, alignment.aligned_to
+                    ),
+                )
+                .for_entry(index)
+                .at(alignment.offset),
+            );
+        }
+        local.alignment = Some(alignment);
+    }
+    findings
+}